@@ -0,0 +1,14 @@
+#version 140
+// Пример своего шейдера для материала: спрайт колышется, как флаг.
+// Material::new("wave").with_float("strength", 0.02)
+in vec2 v_tex_coords;
+in vec4 v_color;
+out vec4 color;
+uniform sampler2D tex;
+uniform vec4 tint;
+uniform float time;
+uniform float strength;
+void main() {
+    vec2 coords = v_tex_coords + vec2(sin(v_tex_coords.y * 12.0 + time * 4.0) * strength, 0.0);
+    color = texture(tex, coords) * v_color * tint;
+}
@@ -0,0 +1,212 @@
+use cgmath::{InnerSpace, MetricSpace, Point2, Vector2};
+use glium::glutin::dpi::PhysicalSize;
+use crate::physics::collision::Obstacles;
+use crate::physics::components::PhysicsComponent;
+use crate::Rect;
+
+/// Наименьшее и наибольшее время полёта, которые перебираются при поиске выстрела, в секундах
+const MIN_FLIGHT_TIME: f64 = 0.05;
+const MAX_FLIGHT_TIME: f64 = 120.0;
+/// Сколько вариантов времени полёта перебирается
+const FLIGHT_TIME_SAMPLES: usize = 400;
+/// Размер снаряда при проверке столкновений
+const PROJECTILE_SIZE: f64 = 4.0;
+/// Шаг моделирования по умолчанию в миллисекундах, примерно один кадр
+const DEFAULT_STEP: u32 = 16;
+/// Дольше этого выстрел по умолчанию не моделируется
+const DEFAULT_MAX_TIME: u32 = 60_000;
+
+/// Выстрел: угол в радианах (0 вправо, -PI/2 вверх, ось y вниз) и начальная скорость
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shot {
+    pub angle: f64,
+    pub speed: f64,
+}
+
+impl Shot {
+    pub fn from_velocity(velocity: Vector2<f64>) -> Self {
+        Shot {angle: velocity.y.atan2(velocity.x), speed: velocity.magnitude()}
+    }
+    /// Начальная скорость снаряда
+    pub fn velocity(&self) -> Vector2<f64> {
+        Vector2::new(self.angle.cos(), self.angle.sin()) * self.speed
+    }
+}
+
+/// Чем закончился смоделированный выстрел
+#[derive(Debug, Clone, Copy)]
+pub struct ShotResult {
+    /// Ближе всего снаряд подлетел к цели на это расстояние
+    pub closest: f64,
+    /// Где снаряд закончил полёт
+    pub end: Point2<f64>,
+    /// Снаряд врезался в препятствие
+    pub hit_obstacle: bool,
+    /// Сколько миллисекунд длился полёт
+    pub time: u32,
+}
+
+/// Условия, в которых летят снаряды: те же гравитация и ветер, что у PhysicsComponent.
+/// Ускорения в пикселях в секунду за секунду, ось y направлена вниз.
+#[derive(Debug, Clone, Copy)]
+pub struct Ballistics {
+    pub gravity: f64,
+    pub wind: f64,
+    /// Шаг моделирования в миллисекундах
+    pub step: u32,
+    /// Дольше этого полёт не моделируется
+    pub max_time: u32,
+}
+
+impl Ballistics {
+    pub fn new(gravity: f64, wind: f64) -> Self {
+        Ballistics {gravity, wind, step: DEFAULT_STEP, max_time: DEFAULT_MAX_TIME}
+    }
+    /// Меняет шаг моделирования и наибольшее время полёта
+    pub fn with_simulation(mut self, step: u32, max_time: u32) -> Self {
+        self.step = step.max(1);
+        self.max_time = max_time;
+        self
+    }
+    /// Берёт гравитацию и ветер у физики объекта
+    pub fn from_component(physics: &PhysicsComponent) -> Self {
+        Ballistics::new(physics.gravity(), physics.wind())
+    }
+    /// Ускорение снаряда, который ветер сносит с силой wind_factor
+    pub fn acceleration(&self, wind_factor: f64) -> Vector2<f64> {
+        Vector2::new(self.wind * wind_factor, self.gravity)
+    }
+    /// Все выстрелы, которые попадают из from в to со скоростью не больше max_speed,
+    /// от самых пологих к самым навесным
+    pub fn solutions(&self, from: Point2<f64>, to: Point2<f64>, max_speed: f64,
+                     wind_factor: f64) -> Vec<Shot> {
+        let a = self.acceleration(wind_factor);
+        let d = to - from;
+        let ratio = (MAX_FLIGHT_TIME / MIN_FLIGHT_TIME).powf(1.0 / FLIGHT_TIME_SAMPLES as f64);
+        let mut t = MIN_FLIGHT_TIME;
+        let mut shots = Vec::new();
+        for _ in 0..FLIGHT_TIME_SAMPLES {
+            // За время t снаряд пролетает v * t + a * t^2 / 2, отсюда нужная скорость
+            let velocity = (d - a * t * t / 2.0) / t;
+            if velocity.magnitude() <= max_speed {
+                shots.push(Shot::from_velocity(velocity));
+            }
+            t *= ratio;
+        }
+        shots
+    }
+    /// Выстрел из from в to с наименьшей скоростью, если он вообще возможен
+    pub fn solve(&self, from: Point2<f64>, to: Point2<f64>, max_speed: f64, wind_factor: f64)
+        -> Option<Shot>
+    {
+        self.solutions(from, to, max_speed, wind_factor).into_iter()
+            .min_by(|a, b| a.speed.total_cmp(&b.speed))
+    }
+    /// Моделирует полёт снаряда тем же шагом, что и PhysicsComponent, без спрайтов и окна.
+    /// Полёт заканчивается, когда снаряд врезается в препятствие, улетает ниже цели
+    /// или проходит max_time миллисекунд.
+    pub fn simulate(&self, from: Point2<f64>, shot: Shot, wind_factor: f64, target: Point2<f64>,
+                    obstacles: Option<&dyn Obstacles>) -> ShotResult {
+        let a = self.acceleration(wind_factor);
+        let step = self.step.max(1);
+        let dt = step as f64 / 1000.0;
+        let mut position = from;
+        let mut velocity = shot.velocity();
+        let mut result = ShotResult {
+            closest: from.distance(target),
+            end: from,
+            hit_obstacle: false,
+            time: 0,
+        };
+        while result.time < self.max_time {
+            let (distance, v) = PhysicsComponent::integrate(velocity, a, dt);
+            position += distance;
+            velocity = v;
+            result.time += step;
+            result.closest = result.closest.min(position.distance(target));
+            result.end = position;
+            if let Some(obstacles) = obstacles {
+                let half = PROJECTILE_SIZE / 2.0;
+                let rect = Rect::new(Point2::new(position.x - half, position.y + half),
+                                     PhysicalSize::new(PROJECTILE_SIZE, PROJECTILE_SIZE));
+                if obstacles.overlaps(&rect) {
+                    result.hit_obstacle = true;
+                    break;
+                }
+            }
+            if velocity.y > 0.0 && position.y > target.y + result.closest.max(PROJECTILE_SIZE) {
+                break;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rect::Rectangular;
+
+    const FROM: Point2<f64> = Point2::new(0.0, 500.0);
+    const TO: Point2<f64> = Point2::new(300.0, 450.0);
+
+    /// Стена от x до x + 10 во всю высоту
+    struct Wall(f64);
+
+    impl Obstacles for Wall {
+        fn overlaps(&self, rect: &Rect) -> bool {
+            rect.right() > self.0 && rect.left() < self.0 + 10.0
+        }
+    }
+
+    #[test]
+    fn solved_shot_lands_on_target_in_any_wind() {
+        for wind in [0.0, 60.0, -60.0] {
+            let ballistics = Ballistics::new(400.0, wind).with_simulation(1, 60_000);
+            let shot = ballistics.solve(FROM, TO, 1000.0, 1.0).unwrap();
+            let result = ballistics.simulate(FROM, shot, 1.0, TO, None);
+            assert!(result.closest < 1.0, "ветер {wind}: промах на {}", result.closest);
+            assert!(!result.hit_obstacle);
+            // Ветер, который на снаряд не действует, выстрел не меняет
+            let still = Ballistics::new(400.0, 0.0).solve(FROM, TO, 1000.0, 1.0).unwrap();
+            assert_eq!(ballistics.solve(FROM, TO, 1000.0, 0.0), Some(still));
+        }
+    }
+
+    #[test]
+    fn every_solution_hits_and_solve_takes_slowest() {
+        let ballistics = Ballistics::new(400.0, 30.0);
+        let shots = ballistics.solutions(FROM, TO, 600.0, 0.5);
+        assert!(shots.len() > 1);
+        // Чем навеснее выстрел, тем он круче направлен вверх
+        assert!(shots.first().unwrap().angle > shots.last().unwrap().angle);
+        for shot in &shots {
+            assert!(shot.speed <= 600.0);
+            let result = ballistics.simulate(FROM, *shot, 0.5, TO, None);
+            assert!(result.closest < 5.0, "{shot:?}: промах на {}", result.closest);
+        }
+        let slowest = shots.iter().map(|s| s.speed).fold(f64::INFINITY, f64::min);
+        assert_eq!(ballistics.solve(FROM, TO, 600.0, 0.5).unwrap().speed, slowest);
+    }
+
+    #[test]
+    fn unreachable_target_has_no_shot() {
+        let ballistics = Ballistics::new(400.0, 0.0);
+        assert_eq!(ballistics.solve(FROM, TO, 50.0, 1.0), None);
+        assert!(ballistics.solutions(FROM, Point2::new(30_000.0, 500.0), 300.0, 1.0).is_empty());
+        // Против сильного ветра не хватает скорости, по ветру хватает
+        let windy = Ballistics::new(400.0, -2000.0);
+        assert_eq!(windy.solve(FROM, TO, 400.0, 1.0), None);
+        assert!(windy.solve(FROM, TO, 400.0, -1.0).is_some());
+    }
+
+    #[test]
+    fn shot_stops_at_obstacle() {
+        let ballistics = Ballistics::new(400.0, 0.0);
+        let shot = ballistics.solve(FROM, TO, 1000.0, 1.0).unwrap();
+        let result = ballistics.simulate(FROM, shot, 1.0, TO, Some(&Wall(150.0)));
+        assert!(result.hit_obstacle);
+        assert!(result.end.x < 160.0 && result.closest > 100.0, "{result:?}");
+        assert!(result.time < ballistics.max_time);
+    }
+}
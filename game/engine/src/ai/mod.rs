@@ -0,0 +1,7 @@
+pub mod ballistics;
+pub mod opponent;
+pub mod weapon;
+
+pub use ballistics::{Ballistics, Shot, ShotResult};
+pub use opponent::{AiDecision, AiOpponent, Difficulty};
+pub use weapon::Weapon;
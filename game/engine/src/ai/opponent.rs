@@ -0,0 +1,302 @@
+use cgmath::{MetricSpace, Point2};
+use glium::glutin::dpi::PhysicalSize;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::ai::ballistics::{Ballistics, Shot, ShotResult};
+use crate::ai::weapon::Weapon;
+use crate::physics::collision::Obstacles;
+use crate::Rect;
+use crate::rect::Rectangular;
+
+/// Сколько разных выстрелов одним оружием проверяется на препятствия
+const CANDIDATE_SHOTS: usize = 12;
+
+/// Насколько сильно компьютер ошибается при прицеливании
+#[derive(Debug, Clone, Copy)]
+pub struct Difficulty {
+    /// Наибольшая ошибка угла в радианах
+    pub angle_error: f64,
+    /// Наибольшая ошибка скорости, доля от нужной скорости
+    pub speed_error: f64,
+}
+
+impl Difficulty {
+    pub fn easy() -> Self {
+        Difficulty {angle_error: 0.12, speed_error: 0.15}
+    }
+    pub fn normal() -> Self {
+        Difficulty {angle_error: 0.05, speed_error: 0.06}
+    }
+    pub fn hard() -> Self {
+        Difficulty {angle_error: 0.015, speed_error: 0.02}
+    }
+    /// Компьютер не ошибается совсем
+    pub fn perfect() -> Self {
+        Difficulty {angle_error: 0.0, speed_error: 0.0}
+    }
+}
+
+/// Что компьютер решил сделать в свой ход
+#[derive(Debug, Clone, PartialEq)]
+pub enum AiDecision {
+    /// Выстрелить оружием с индексом weapon
+    Fire { weapon: usize, shot: Shot },
+    /// Сначала дойти до x, оттуда стрелять удобнее
+    Walk { target_x: f64 },
+    /// Ни попасть, ни дойти до удобного места не получается
+    Skip,
+}
+
+/// Лучший найденный выстрел
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    weapon: usize,
+    shot: Shot,
+    result: ShotResult,
+}
+
+/// Компьютерный противник. Выбирает оружие, решает, куда стрелять,
+/// и при необходимости отходит на более удобную позицию.
+/// Всё считается без окна, моделированием тем же шагом, что у PhysicsComponent.
+#[derive(Debug)]
+pub struct AiOpponent {
+    weapons: Vec<Weapon>,
+    difficulty: Difficulty,
+    /// Шаг, с которым перебираются позиции для ходьбы, и наибольшее расстояние ходьбы
+    walk_step: f64,
+    walk_range: f64,
+    rng: StdRng,
+}
+
+impl AiOpponent {
+    pub fn new(weapons: Vec<Weapon>, difficulty: Difficulty) -> Self {
+        AiOpponent {
+            weapons,
+            difficulty,
+            walk_step: 40.0,
+            walk_range: 200.0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+    /// Противник с заданным seed, чтобы его ошибки повторялись
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+    /// Меняет шаг перебора позиций и то, насколько далеко противник готов уйти за ход
+    pub fn with_walk(mut self, step: f64, range: f64) -> Self {
+        self.walk_step = step;
+        self.walk_range = range;
+        self
+    }
+    pub fn weapons(&self) -> &Vec<Weapon> {
+        &self.weapons
+    }
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+    /// Решает, что делать бойцу в позиции from, если цель в позиции to
+    pub fn decide(&mut self, ballistics: &Ballistics, from: Point2<f64>, to: Point2<f64>,
+                  obstacles: Option<&dyn Obstacles>) -> AiDecision {
+        let best = self.best_shot(ballistics, from, to, obstacles);
+        if let Some(c) = best {
+            if c.result.closest <= self.weapons[c.weapon].blast_radius {
+                let shot = self.aim(c.shot);
+                return AiDecision::Fire {weapon: c.weapon, shot};
+            }
+        }
+        if let Some(target_x) = self.better_position(ballistics, from, to, obstacles) {
+            return AiDecision::Walk {target_x};
+        }
+        match best {
+            Some(c) => {
+                let shot = self.aim(c.shot);
+                AiDecision::Fire {weapon: c.weapon, shot}
+            }
+            None => AiDecision::Skip,
+        }
+    }
+    /// Оружие и выстрел, которыми из from ближе всего получается попасть в to.
+    /// Оружие, взрыв которого заденет самого стрелка, не выбирается
+    pub fn choose_weapon(&self, ballistics: &Ballistics, from: Point2<f64>, to: Point2<f64>,
+                         obstacles: Option<&dyn Obstacles>) -> Option<(usize, Shot)> {
+        self.best_shot(ballistics, from, to, obstacles).map(|c| (c.weapon, c.shot))
+    }
+    /// Добавляет к выстрелу случайную ошибку в пределах сложности
+    pub fn aim(&mut self, shot: Shot) -> Shot {
+        let d = self.difficulty;
+        let angle = if d.angle_error > 0.0 {
+            self.rng.gen_range(-d.angle_error..=d.angle_error)
+        } else {
+            0.0
+        };
+        let speed = if d.speed_error > 0.0 {
+            self.rng.gen_range(-d.speed_error..=d.speed_error)
+        } else {
+            0.0
+        };
+        Shot {angle: shot.angle + angle, speed: shot.speed * (1.0 + speed)}
+    }
+    /// Двигает бойца к target_x по горизонтали со скоростью speed пикселей в секунду.
+    /// Возвращает true, когда боец дошёл
+    pub fn walk<T: Rectangular>(unit: &mut T, target_x: f64, speed: f64, dt: u32) -> bool {
+        let center = unit.left() + unit.width() / 2.0;
+        let step = speed * dt as f64 / 1000.0;
+        let dx = target_x - center;
+        if dx.abs() <= step {
+            unit.move_by(dx, 0.0);
+            return true;
+        }
+        unit.move_by(step * dx.signum(), 0.0);
+        false
+    }
+    fn best_shot(&self, ballistics: &Ballistics, from: Point2<f64>, to: Point2<f64>,
+                 obstacles: Option<&dyn Obstacles>) -> Option<Candidate> {
+        let mut best: Option<Candidate> = None;
+        for (i, weapon) in self.weapons.iter().enumerate() {
+            if from.distance(to) <= weapon.blast_radius {
+                continue;
+            }
+            let shots = ballistics.solutions(from, to, weapon.max_speed, weapon.wind_factor);
+            let step = (shots.len() / CANDIDATE_SHOTS).max(1);
+            for shot in shots.into_iter().step_by(step) {
+                let result = ballistics.simulate(from, shot, weapon.wind_factor, to, obstacles);
+                let better = match &best {
+                    None => true,
+                    Some(b) => result.closest < b.result.closest
+                        || (result.closest == b.result.closest && shot.speed < b.shot.speed),
+                };
+                if better {
+                    best = Some(Candidate {weapon: i, shot, result});
+                }
+            }
+        }
+        best
+    }
+    /// Ближайшая позиция на той же высоте, откуда можно попасть
+    fn better_position(&self, ballistics: &Ballistics, from: Point2<f64>, to: Point2<f64>,
+                       obstacles: Option<&dyn Obstacles>) -> Option<f64> {
+        if self.walk_step <= 0.0 {
+            return None;
+        }
+        let steps = (self.walk_range / self.walk_step) as i32;
+        for i in 1..=steps {
+            // Сначала пробуем идти к цели, потом от неё
+            let toward = if to.x >= from.x {1.0} else {-1.0};
+            for direction in [toward, -toward] {
+                let x = from.x + direction * self.walk_step * i as f64;
+                let position = Point2::new(x, from.y);
+                let blocked = obstacles.map(|o| {
+                    o.overlaps(&Rect::new(position, PhysicalSize::new(1.0, 1.0)))
+                });
+                if blocked == Some(true) {
+                    continue;
+                }
+                if let Some(c) = self.best_shot(ballistics, position, to, obstacles) {
+                    if c.result.closest <= self.weapons[c.weapon].blast_radius {
+                        return Some(x);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aim_error_stays_within_difficulty() {
+        let shot = Shot {angle: -0.8, speed: 400.0};
+        for difficulty in [Difficulty::easy(), Difficulty::normal(), Difficulty::hard()] {
+            let mut ai = AiOpponent::new(Vec::new(), difficulty).with_seed(7);
+            let aimed: Vec<Shot> = (0..200).map(|_| ai.aim(shot)).collect();
+            for a in &aimed {
+                assert!((a.angle - shot.angle).abs() <= difficulty.angle_error, "{a:?}");
+                assert!((a.speed / shot.speed - 1.0).abs() <= difficulty.speed_error + 1e-12,
+                        "{a:?}");
+            }
+            // Ошибка действительно есть и не всегда одна и та же
+            assert!(aimed.iter().any(|a| *a != aimed[0]));
+            // С тем же seed ошибки повторяются
+            let mut again = AiOpponent::new(Vec::new(), difficulty).with_seed(7);
+            assert!(aimed.iter().all(|a| *a == again.aim(shot)));
+        }
+        let mut perfect = AiOpponent::new(Vec::new(), Difficulty::perfect()).with_seed(7);
+        assert_eq!(perfect.aim(shot), shot);
+    }
+
+    const FROM: Point2<f64> = Point2::new(0.0, 500.0);
+    const TO: Point2<f64> = Point2::new(300.0, 500.0);
+
+    /// Стена от x до x + 10 во всю высоту
+    struct Wall(f64);
+
+    impl Obstacles for Wall {
+        fn overlaps(&self, rect: &Rect) -> bool {
+            rect.right() > self.0 && rect.left() < self.0 + 10.0
+        }
+    }
+
+    #[test]
+    fn chosen_weapon_reaches_target() {
+        let ballistics = Ballistics::new(400.0, 0.0);
+        let weapons = vec![
+            // Не долетает: на ровной земле дальше v² / g = 100 не улетит
+            Weapon::new("камень", 200.0, 0.0, 10.0),
+            // Долетает, но взрыв заденет самого стрелка
+            Weapon::new("бомба", 1000.0, 0.0, 400.0),
+            Weapon::new("базука", 1000.0, 1.0, 20.0),
+        ];
+        let ai = AiOpponent::new(weapons, Difficulty::perfect());
+        let (weapon, shot) = ai.choose_weapon(&ballistics, FROM, TO, None).unwrap();
+        assert_eq!(weapon, 2);
+        let result = ballistics.simulate(FROM, shot, 1.0, TO, None);
+        assert!(result.closest <= 20.0, "промах на {}", result.closest);
+        // Если не долетает ничего, выбрать нечего
+        let ai = AiOpponent::new(vec![Weapon::new("камень", 200.0, 0.0, 10.0)],
+                                 Difficulty::perfect());
+        assert_eq!(ai.choose_weapon(&ballistics, FROM, TO, None).map(|(w, _)| w), None);
+    }
+
+    #[test]
+    fn opponent_walks_when_target_is_out_of_reach() {
+        let ballistics = Ballistics::new(400.0, 0.0);
+        // Дальность 320² / 400 = 256: с места не достать, а на 80 ближе уже можно
+        let weapons = vec![Weapon::new("граната", 320.0, 0.0, 10.0)];
+        let mut ai = AiOpponent::new(weapons.clone(), Difficulty::perfect()).with_walk(40.0, 200.0);
+        assert_eq!(ai.decide(&ballistics, FROM, TO, None), AiDecision::Walk {target_x: 80.0});
+        // Через стену не пройти, поэтому боец встаёт сразу за ней
+        assert_eq!(ai.decide(&ballistics, FROM, TO, Some(&Wall(75.0))),
+                   AiDecision::Walk {target_x: 120.0});
+        // Оттуда, куда он пришёл, он уже стреляет
+        let from = Point2::new(80.0, 500.0);
+        assert!(matches!(ai.decide(&ballistics, from, TO, None), AiDecision::Fire {weapon: 0, ..}));
+        // Если и ходить нельзя, ход пропускается
+        let mut still = AiOpponent::new(weapons, Difficulty::perfect()).with_walk(0.0, 0.0);
+        assert_eq!(still.decide(&ballistics, FROM, TO, None), AiDecision::Skip);
+    }
+
+    #[test]
+    fn walk_stops_at_target() {
+        let mut unit = Rect::new(Point2::new(0.0, 100.0), PhysicalSize::new(20.0, 20.0));
+        // Центр бойца в 10, идти до 45 со скоростью 100 пикселей в секунду
+        let mut steps = 0;
+        while !AiOpponent::walk(&mut unit, 45.0, 100.0, 100) {
+            steps += 1;
+            assert!(steps < 10, "боец не дошёл: {}", unit.left());
+        }
+        assert_eq!(steps, 3);
+        assert_eq!(unit.left() + unit.width() / 2.0, 45.0);
+        // Дошедший боец больше не двигается, а назад идёт так же
+        assert!(AiOpponent::walk(&mut unit, 45.0, 100.0, 100));
+        assert_eq!(unit.left(), 35.0);
+        assert!(!AiOpponent::walk(&mut unit, 0.0, 100.0, 100));
+        assert_eq!(unit.left(), 25.0);
+    }
+}
@@ -0,0 +1,17 @@
+/// Оружие с точки зрения компьютерного противника
+#[derive(Debug, Clone)]
+pub struct Weapon {
+    pub name: String,
+    /// Наибольшая начальная скорость снаряда, пиксели в секунду
+    pub max_speed: f64,
+    /// Насколько сильно ветер сносит снаряд, 0 - совсем не сносит
+    pub wind_factor: f64,
+    /// Радиус взрыва. Ближе этого к себе стрелять опасно
+    pub blast_radius: f64,
+}
+
+impl Weapon {
+    pub fn new(name: &str, max_speed: f64, wind_factor: f64, blast_radius: f64) -> Self {
+        Weapon {name: name.to_string(), max_speed, wind_factor, blast_radius}
+    }
+}
@@ -0,0 +1,42 @@
+use std::any::Any;
+use std::fmt::Debug;
+use crate::component::data::ComponentData;
+use crate::component::messages::ComponentMessage;
+use crate::graphics::Sprite;
+use crate::messaging::traits::Messaging;
+
+/// Позволяет превратить компонент обратно в его настоящий тип.
+/// Реализовано для всех типов сразу, самому реализовывать не нужно
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl <T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Компонент, который объявлен не в движке, а в самой игре.
+/// Кладётся в спрайт через Component::custom и работает наравне со встроенными:
+/// обновляется вместе со спрайтом, получает сообщения, сохраняется и загружается
+/// через ComponentRegistry.
+pub trait ComponentBehavior: Messaging<Message = ComponentMessage> + AsAny + Debug + 'static {
+    /// Имя типа компонента. По нему компонент находится при загрузке,
+    /// поэтому оно должно быть у каждого типа своё
+    fn kind(&self) -> &'static str;
+    /// Обновляет компонент, dt в миллисекундах
+    fn update(&mut self, dt: u32, host: &mut Sprite);
+    /// Состояние компонента для сохранения. None - компонент не сохраняется
+    fn save(&self) -> Option<ComponentData> {
+        None
+    }
+}
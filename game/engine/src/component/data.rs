@@ -0,0 +1,61 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Сохранённое состояние компонента: пары ключ = значение в порядке добавления.
+/// В строку записывается как "ключ=значение;ключ=значение",
+/// поэтому в ключах и значениях не должно быть ';' и '='
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComponentData {
+    values: Vec<(String, String)>,
+}
+
+impl ComponentData {
+    pub fn new() -> Self {
+        ComponentData::default()
+    }
+    /// Добавляет значение
+    pub fn with<T: Display>(mut self, key: &str, value: T) -> Self {
+        self.set(key, value);
+        self
+    }
+    /// Добавляет или заменяет значение
+    pub fn set<T: Display>(&mut self, key: &str, value: T) {
+        let value = value.to_string();
+        match self.values.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.values.push((key.to_string(), value)),
+        }
+    }
+    /// Возвращает значение, если оно есть и читается как T
+    pub fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get_str(key)?.parse().ok()
+    }
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.values.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+    pub fn values(&self) -> &Vec<(String, String)> {
+        &self.values
+    }
+    /// Читает данные из строки "ключ=значение;ключ=значение".
+    /// Возвращает None, если у какой-то пары нет '='
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut data = ComponentData::new();
+        for pair in s.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=')?;
+            data.set(key.trim(), value.trim());
+        }
+        Some(data)
+    }
+}
+
+impl Display for ComponentData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (key, value)) in self.values.iter().enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            write!(f, "{key}={value}")?;
+        }
+        Ok(())
+    }
+}
@@ -1,33 +1,34 @@
 use glium::buffer::BufferMode::Default;
-use queues::{IsQueue, Queue};
+use crate::messaging::queue::MessageQueue;
 use crate::component::Component;
 use crate::component::messages::ComponentMessage;
 use crate::graphics::Sprite;
 use crate::group::{Group, SomeGroup};
+use crate::group::storage::Storage;
 use crate::messaging::traits::Messaging;
 use crate::messaging::message::Message;
 
 #[derive(Debug)]
 pub struct ComponentsGroup {
     group: Group<Component>,
-    input_messages: Queue<Message>,
-    output_messages: Queue<Message>
+    input_messages: MessageQueue<Message>,
+    output_messages: MessageQueue<Message>
 }
 
 impl ComponentsGroup {
     pub fn new() -> Self {
         ComponentsGroup {
             group: Group::new(),
-            input_messages: Queue::new(),
-            output_messages: Queue::new()
+            input_messages: MessageQueue::new(),
+            output_messages: MessageQueue::new()
         }
     }
     /// Создаёт группу из вектора элементов
     pub fn from(elements: Vec<Component>) -> Self {
         ComponentsGroup {
             group: Group::from(elements),
-            input_messages: Queue::new(),
-            output_messages: Queue::new()
+            input_messages: MessageQueue::new(),
+            output_messages: MessageQueue::new()
         }
     }
     /// Обновляет каждый компонент в группе
@@ -35,16 +36,18 @@ impl ComponentsGroup {
         let mut output_messages = Vec::new();
         self.call(|mut c| {
             c = c.updated(dt, &mut host);
-            let messages_len = c.output_messages().size();
-            for i in 0..messages_len {
-                output_messages.push(c.output_messages().remove().unwrap());
+            c.advance_messages(dt);
+            while let Some(message) = c.output_messages().remove() {
+                output_messages.push(message);
             }
             c
         });
         self.call(|mut c| {
             for msg in &output_messages
             {
-                c.reveive_msg(msg.clone());
+                // Если очередь компонента переполнена, сообщение теряется,
+                // а очередь запоминает это в dropped
+                let _ = c.reveive_msg(*msg);
             }
             c.handle_messages();
             c
@@ -52,24 +55,44 @@ impl ComponentsGroup {
         return (self, host)
     }
 }
+impl Storage<Component> for ComponentsGroup {
+    fn get_elements_mut(&mut self) -> &mut Vec<Option<Component>> {
+        self.group.get_elements_mut()
+    }
+}
+
 impl SomeGroup<Component> for ComponentsGroup {
     fn get_elements(&self) -> &Vec<Option<Component>> {
         self.group.get_elements()
     }
-    fn get_elements_mut(&mut self) -> &mut Vec<Option<Component>> {
-        self.group.get_elements_mut()
+    fn push(&mut self, element: Component) {
+        self.group.push(element);
+    }
+    fn append(&mut self, elements: Vec<Component>) {
+        self.group.append(elements);
+    }
+    fn take_el(&mut self, i: usize) -> Option<Component> {
+        self.group.take_el(i)
     }
 }
 
 impl Messaging for ComponentsGroup {
     type Message = Message;
-    fn input_messages(&mut self) -> &mut Queue<Self::Message> {
+    fn input_messages(&mut self) -> &mut MessageQueue<Self::Message> {
         &mut self.input_messages
     }
-    fn output_messages(&mut self) -> &mut Queue<Self::Message> {
+    fn output_messages(&mut self) -> &mut MessageQueue<Self::Message> {
         &mut self.output_messages
     }
-    fn handle_message(&mut self, _message: Self::Message) {
-
+    /// Сообщение группе получает каждый её компонент.
+    /// Компоненты обработают его при следующем обновлении
+    fn handle_message(&mut self, message: Self::Message) {
+        match message {
+            Message::ComponentMessage(m) => {
+                for c in self.get_elements_mut().iter_mut().flatten() {
+                    let _ = c.reveive_msg(m);
+                }
+            }
+        }
     }
 }
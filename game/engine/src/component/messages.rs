@@ -1,6 +1,41 @@
+use crate::graphics::particles::ParticleMessage;
+use crate::messaging::queue::Coalesce;
 use crate::physics::messages::PhysicsMessage;
+use crate::sounds::messages::AudioMessage;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ComponentMessage {
-    PhysicsMessage(PhysicsMessage)
-}
\ No newline at end of file
+    PhysicsMessage(PhysicsMessage),
+    AudioMessage(AudioMessage),
+    ParticleMessage(ParticleMessage),
+    /// Сообщение для компонентов игры: тема и число
+    Custom(CustomMessage),
+}
+
+/// Сообщение, которое движок сам не понимает, а компоненты игры понимают.
+/// Например CustomMessage::new("fuse", 3000.0) может поджечь фитиль ракеты на 3 секунды
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CustomMessage {
+    pub topic: &'static str,
+    pub value: f64,
+}
+
+impl CustomMessage {
+    pub fn new(topic: &'static str, value: f64) -> Self {
+        CustomMessage {topic, value}
+    }
+}
+
+impl Coalesce for ComponentMessage {
+    /// Силы, которые пришли одновременно, складываются в одну
+    fn coalesce(&mut self, other: &Self) -> bool {
+        match (self, other) {
+            (ComponentMessage::PhysicsMessage(a), ComponentMessage::PhysicsMessage(b)) => {
+                a.force.0 += b.force.0;
+                a.force.1 += b.force.1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
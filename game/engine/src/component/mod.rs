@@ -1,46 +1,149 @@
+pub mod behavior;
+pub mod data;
 pub mod group;
 pub mod traits;
 pub mod messages;
+pub mod query;
+pub mod registry;
 
 use glium::glutin::event::VirtualKeyCode::C;
-use queues::Queue;
+use crate::messaging::queue::MessageQueue;
 pub use group::ComponentsGroup;
 use crate::component::messages::ComponentMessage;
 use crate::graphics::Sprite;
+use crate::graphics::particles::ParticleComponent;
 use crate::messaging::message::Message;
 use crate::messaging::traits::Messaging;
 use crate::misc_traits::update::Updatable;
 
+use crate::component::behavior::ComponentBehavior;
+use crate::component::data::ComponentData;
+use crate::component::traits::ComponentType;
 use crate::physics::components::PhysicsComponent;
+use crate::sounds::components::AudioEmitter;
 
 #[derive(Debug)]
 pub enum Component {
     PhysicsComponent(PhysicsComponent),
+    AudioEmitter(AudioEmitter),
+    ParticleComponent(ParticleComponent),
+    /// Компонент, объявленный в игре, а не в движке
+    Custom(Box<dyn ComponentBehavior>),
+}
+
+/// Реализует ComponentType для компонента, вариант Component которого называется так же
+macro_rules! component_type {
+    ($name:ident) => {
+        impl ComponentType for $name {
+            fn from_component(component: &Component) -> Option<&Self> {
+                match component {
+                    Component::$name(c) => Some(c),
+                    _ => None,
+                }
+            }
+            fn from_component_mut(component: &mut Component) -> Option<&mut Self> {
+                match component {
+                    Component::$name(c) => Some(c),
+                    _ => None,
+                }
+            }
+            fn from_owned(component: Component) -> Result<Self, Component> {
+                match component {
+                    Component::$name(c) => Ok(c),
+                    other => Err(other),
+                }
+            }
+        }
+    };
+}
+
+component_type!(PhysicsComponent);
+component_type!(AudioEmitter);
+component_type!(ParticleComponent);
+
+impl <T: ComponentBehavior> ComponentType for T {
+    fn from_component(component: &Component) -> Option<&Self> {
+        match component {
+            Component::Custom(c) => (**c).as_any().downcast_ref(),
+            _ => None,
+        }
+    }
+    fn from_component_mut(component: &mut Component) -> Option<&mut Self> {
+        match component {
+            Component::Custom(c) => (**c).as_any_mut().downcast_mut(),
+            _ => None,
+        }
+    }
+    fn from_owned(component: Component) -> Result<Self, Component> {
+        match component {
+            Component::Custom(c) if (*c).as_any().is::<T>() => {
+                Ok(*c.into_any().downcast().unwrap())
+            }
+            other => Err(other),
+        }
+    }
 }
 
 impl Component {
+    /// Оборачивает компонент игры, чтобы его можно было положить в спрайт
+    pub fn custom<T: ComponentBehavior>(component: T) -> Component {
+        Component::Custom(Box::new(component))
+    }
+    /// Имя типа компонента, по которому его загружает ComponentRegistry
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Component::PhysicsComponent(_) => PhysicsComponent::KIND,
+            Component::AudioEmitter(_) => AudioEmitter::KIND,
+            Component::ParticleComponent(_) => ParticleComponent::KIND,
+            Component::Custom(c) => c.kind(),
+        }
+    }
+    /// Состояние компонента для сохранения. None - компонент не сохраняется
+    pub fn save(&self) -> Option<ComponentData> {
+        match self {
+            Component::PhysicsComponent(p) => Some(p.save()),
+            Component::AudioEmitter(a) => Some(a.save()),
+            Component::ParticleComponent(_) => None,
+            Component::Custom(c) => c.save(),
+        }
+    }
     pub fn updated(mut self, dt:u32, host: &mut Sprite) -> Component {
         return match self {
             Component::PhysicsComponent(p) => Component::PhysicsComponent(p.updated(dt, host)),
+            Component::AudioEmitter(a) => Component::AudioEmitter(a.updated(dt, host)),
+            Component::ParticleComponent(p) => Component::ParticleComponent(p.updated(dt, host)),
+            Component::Custom(mut c) => {
+                c.update(dt, host);
+                Component::Custom(c)
+            }
         }
     }
 }
 
 impl Messaging for Component {
     type Message = ComponentMessage;
-    fn input_messages(&mut self) -> &mut Queue<Self::Message> {
+    fn input_messages(&mut self) -> &mut MessageQueue<Self::Message> {
         return match self {
             Component::PhysicsComponent(p) => p.input_messages(),
+            Component::AudioEmitter(a) => a.input_messages(),
+            Component::ParticleComponent(p) => p.input_messages(),
+            Component::Custom(c) => c.input_messages(),
         }
     }
-    fn output_messages(&mut self) -> &mut Queue<Self::Message> {
+    fn output_messages(&mut self) -> &mut MessageQueue<Self::Message> {
         return match self {
             Component::PhysicsComponent(p) => p.output_messages(),
+            Component::AudioEmitter(a) => a.output_messages(),
+            Component::ParticleComponent(p) => p.output_messages(),
+            Component::Custom(c) => c.output_messages(),
         }
     }
     fn handle_messages(&mut self) {
         match self {
             Component::PhysicsComponent(p) => p.handle_messages(),
+            Component::AudioEmitter(a) => a.handle_messages(),
+            Component::ParticleComponent(p) => p.handle_messages(),
+            Component::Custom(c) => c.handle_messages(),
         }
     }
     fn handle_message(&mut self, _message: Self::Message) {
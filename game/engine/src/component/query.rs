@@ -0,0 +1,60 @@
+use crate::component::traits::{ComponentType, Composite};
+use crate::graphics::Sprite;
+use crate::graphics::sprite::SpriteGroup;
+use crate::group::{Handle, SomeGroup};
+use crate::group::storage::Storage;
+
+/// Набор типов компонентов, которые должны быть у спрайта.
+/// Реализован для кортежей из одного, двух и трёх типов: (PhysicsComponent, AudioEmitter)
+pub trait ComponentQuery {
+    /// Ссылки на найденные компоненты
+    type Item<'a>;
+    /// Достаёт из спрайта все компоненты набора, если они у него есть
+    fn fetch(sprite: &Sprite) -> Option<Self::Item<'_>>;
+}
+
+impl <A: ComponentType> ComponentQuery for (A,) {
+    type Item<'a> = &'a A;
+    fn fetch(sprite: &Sprite) -> Option<Self::Item<'_>> {
+        sprite.get_component::<A>()
+    }
+}
+
+impl <A: ComponentType, B: ComponentType> ComponentQuery for (A, B) {
+    type Item<'a> = (&'a A, &'a B);
+    fn fetch(sprite: &Sprite) -> Option<Self::Item<'_>> {
+        Some((sprite.get_component::<A>()?, sprite.get_component::<B>()?))
+    }
+}
+
+impl <A: ComponentType, B: ComponentType, C: ComponentType> ComponentQuery for (A, B, C) {
+    type Item<'a> = (&'a A, &'a B, &'a C);
+    fn fetch(sprite: &Sprite) -> Option<Self::Item<'_>> {
+        Some((sprite.get_component::<A>()?, sprite.get_component::<B>()?,
+              sprite.get_component::<C>()?))
+    }
+}
+
+impl SpriteGroup {
+    /// Обходит спрайты, у которых есть все компоненты набора Q, вместе с этими компонентами.
+    /// Например query::<(PhysicsComponent, AudioEmitter)>()
+    pub fn query<Q: ComponentQuery>(&self) -> impl Iterator<Item = (Handle, Q::Item<'_>)> {
+        self.get_elements().iter().enumerate()
+            .filter_map(|(i, s)| {
+                let sprite = s.as_ref()?;
+                Some((self.handle_of(i)?, Q::fetch(sprite)?))
+            })
+    }
+    /// Обходит спрайты, у которых есть компонент типа T, и даёт этот компонент менять
+    pub fn query_mut<T: ComponentType>(&mut self) -> impl Iterator<Item = (Handle, &mut T)> {
+        let handles: Vec<Option<Handle>> = (0..self.get_elements().len())
+            .map(|i| self.handle_of(i))
+            .collect();
+        self.get_elements_mut().iter_mut().zip(handles)
+            .filter_map(|(s, h)| Some((h?, s.as_mut()?.get_component_mut::<T>()?)))
+    }
+    /// Ссылки на спрайты, у которых есть все компоненты набора Q
+    pub fn with_components<Q: ComponentQuery>(&self) -> Vec<Handle> {
+        self.query::<Q>().map(|(h, _)| h).collect()
+    }
+}
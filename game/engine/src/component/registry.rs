@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use crate::component::Component;
+use crate::component::data::ComponentData;
+use crate::physics::components::PhysicsComponent;
+use crate::sounds::components::AudioEmitter;
+
+/// Функция, которая создаёт компонент из сохранённых данных
+pub type ComponentLoader = fn(&ComponentData) -> Option<Component>;
+
+/// Знает, как загрузить компонент каждого типа по его имени.
+/// Встроенные компоненты зарегистрированы сразу, компоненты игры
+/// нужно зарегистрировать через register
+#[derive(Debug, Clone)]
+pub struct ComponentRegistry {
+    loaders: HashMap<String, ComponentLoader>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        let mut registry = ComponentRegistry {loaders: HashMap::new()};
+        registry.register(PhysicsComponent::KIND, PhysicsComponent::load);
+        registry.register(AudioEmitter::KIND, AudioEmitter::load);
+        registry
+    }
+    /// Регистрирует тип компонента. Имя должно совпадать с ComponentBehavior::kind
+    pub fn register(&mut self, kind: &str, loader: ComponentLoader) {
+        self.loaders.insert(kind.to_string(), loader);
+    }
+    pub fn is_registered(&self, kind: &str) -> bool {
+        self.loaders.contains_key(kind)
+    }
+    /// Создаёт компонент типа kind из данных. None, если тип не зарегистрирован
+    /// или данные не подходят
+    pub fn load(&self, kind: &str, data: &ComponentData) -> Option<Component> {
+        self.loaders.get(kind).and_then(|loader| loader(data))
+    }
+}
+
+impl Default for ComponentRegistry {
+    fn default() -> Self {
+        ComponentRegistry::new()
+    }
+}
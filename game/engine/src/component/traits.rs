@@ -1,5 +1,21 @@
 use crate::component::{Component, ComponentsGroup};
 use crate::group::SomeGroup;
+use crate::group::storage::Storage;
+
+/// Тип компонента, который можно достать из Component.
+/// Нужен, чтобы писать get_component::<PhysicsComponent>() вместо match
+pub trait ComponentType: Sized + 'static {
+    /// Возвращает компонент, если он этого типа
+    fn from_component(component: &Component) -> Option<&Self>;
+    /// Возвращает изменяемый компонент, если он этого типа
+    fn from_component_mut(component: &mut Component) -> Option<&mut Self>;
+    /// Забирает компонент, если он этого типа, а иначе возвращает его обратно
+    fn from_owned(component: Component) -> Result<Self, Component>;
+    /// Является ли компонент компонентом этого типа
+    fn is(component: &Component) -> bool {
+        Self::from_component(component).is_some()
+    }
+}
 
 pub trait Composite {
     fn get_components(&self) -> &Option<ComponentsGroup>;
@@ -26,5 +42,27 @@ pub trait Composite {
         }
         return self;
     }
-
-}
\ No newline at end of file
+    /// Возвращает первый компонент типа T
+    fn get_component<T: ComponentType>(&self) -> Option<&T> {
+        self.get_components().as_ref()?
+            .get_elements().iter().flatten()
+            .find_map(|c| T::from_component(c))
+    }
+    /// Возвращает первый компонент типа T, который можно менять
+    fn get_component_mut<T: ComponentType>(&mut self) -> Option<&mut T> {
+        self.get_components_mut().as_mut()?
+            .get_elements_mut().iter_mut().flatten()
+            .find_map(|c| T::from_component_mut(c))
+    }
+    /// Есть ли компонент типа T
+    fn has<T: ComponentType>(&self) -> bool {
+        self.get_component::<T>().is_some()
+    }
+    /// Убирает первый компонент типа T и возвращает его
+    fn remove<T: ComponentType>(&mut self) -> Option<T> {
+        let group = self.get_components_mut().as_mut()?;
+        let index = group.get_elements().iter()
+            .position(|c| c.as_ref().map(|c| T::is(c)).unwrap_or(false))?;
+        T::from_owned(group.take_el(index)?).ok()
+    }
+}
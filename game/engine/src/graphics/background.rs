@@ -0,0 +1,114 @@
+use glium::texture::SrgbTexture2d;
+use crate::graphics::traits::HasTexture;
+
+/// К какому краю видимой области прижимается слой фона по вертикали
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerticalAnchor {
+    /// Верх слоя совпадает с верхом экрана (небо, облака)
+    Top,
+    /// Слой по центру экрана
+    Center,
+    /// Низ слоя совпадает с низом экрана (горы, кусты)
+    Bottom,
+}
+
+/// Один слой параллакс фона.
+/// scroll_factor показывает, насколько слой двигается вместе с камерой:
+/// 0.0 - слой стоит на месте (далёкое небо), 1.0 - двигается как обычные спрайты,
+/// больше 1.0 - слой ближе к игроку, чем спрайты.
+#[derive(Debug)]
+pub struct ParallaxLayer {
+    texture: SrgbTexture2d,
+    scroll_factor: f64,
+    scale: f64,
+    offset: (f64, f64),
+    repeat_x: bool,
+    anchor: VerticalAnchor,
+}
+
+impl ParallaxLayer {
+    /// Создаёт слой из текстуры. scale это во сколько раз увеличить картинку
+    pub fn new(texture: SrgbTexture2d, scroll_factor: f64, scale: f64) -> Self {
+        ParallaxLayer {
+            texture,
+            scroll_factor,
+            scale,
+            offset: (0.0, 0.0),
+            repeat_x: false,
+            anchor: VerticalAnchor::Bottom,
+        }
+    }
+    /// Повторять ли картинку слоя по горизонтали, чтобы она заполняла весь экран
+    pub fn with_repeat(mut self, repeat_x: bool) -> Self {
+        self.repeat_x = repeat_x;
+        self
+    }
+    /// Устанавливает вертикальную привязку слоя
+    pub fn with_anchor(mut self, anchor: VerticalAnchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+    /// Сдвигает слой относительно его привязки
+    pub fn with_offset<X: Into<f64>, Y: Into<f64>>(mut self, x: X, y: Y) -> Self {
+        self.offset = (x.into(), y.into());
+        self
+    }
+    pub fn scroll_factor(&self) -> f64 {
+        self.scroll_factor
+    }
+    pub fn repeat_x(&self) -> bool {
+        self.repeat_x
+    }
+    pub fn anchor(&self) -> VerticalAnchor {
+        self.anchor
+    }
+    pub fn offset(&self) -> (f64, f64) {
+        self.offset
+    }
+    /// Размер слоя с учётом масштаба
+    pub fn size(&self) -> (f64, f64) {
+        let (width, height) = self.texture.dimensions();
+        (width as f64 * self.scale, height as f64 * self.scale)
+    }
+}
+
+impl HasTexture for ParallaxLayer {
+    type Texture = SrgbTexture2d;
+    fn get_texture(&self) -> &Self::Texture {
+        &self.texture
+    }
+    fn get_texture_mut(&mut self) -> &mut Self::Texture {
+        &mut self.texture
+    }
+}
+
+/// Фон уровня из нескольких слоёв. Слои рисуются в порядке добавления,
+/// поэтому сначала нужно добавлять самые дальние.
+#[derive(Debug)]
+pub struct ParallaxBackground {
+    layers: Vec<ParallaxLayer>,
+}
+
+impl ParallaxBackground {
+    pub fn new() -> Self {
+        ParallaxBackground {layers: Vec::new()}
+    }
+    /// Добавляет слой поверх уже добавленных
+    pub fn push(&mut self, layer: ParallaxLayer) {
+        self.layers.push(layer);
+    }
+    /// Возвращает фон с добавленным слоем
+    pub fn with_layer(mut self, layer: ParallaxLayer) -> Self {
+        self.push(layer);
+        self
+    }
+    pub fn layers(&self) -> &Vec<ParallaxLayer> {
+        &self.layers
+    }
+}
+
+impl Default for ParallaxBackground {
+    fn default() -> Self {
+        ParallaxBackground::new()
+    }
+}
@@ -0,0 +1,43 @@
+use cgmath::{Matrix4, Point2, Vector3};
+
+/// Камера - это точка мира, которая видна в левом верхнем углу виртуального экрана.
+/// Всё, что рисует SpriteManager, сдвигается на позицию камеры.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    position: Point2<f64>,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera {position: Point2::new(0.0, 0.0)}
+    }
+    /// Возвращает позицию камеры в игровых координатах
+    pub fn position(&self) -> Point2<f64> {
+        self.position
+    }
+    /// Ставит камеру в указанную позицию
+    pub fn move_to<X: Into<f64>, Y: Into<f64>>(&mut self, x: X, y: Y) {
+        self.position = Point2::new(x.into(), y.into());
+    }
+    /// Прибавляет к позиции камеры x и y
+    pub fn move_by<X: Into<f64>, Y: Into<f64>>(&mut self, x: X, y: Y) {
+        self.position.x += x.into();
+        self.position.y += y.into();
+    }
+    /// Ставит камеру так, чтобы точка target оказалась в центре экрана размера screen_size
+    pub fn look_at(&mut self, target: Point2<f64>, screen_size: (f64, f64)) {
+        let (width, height) = screen_size;
+        self.move_to(target.x - width / 2.0, target.y - height / 2.0);
+    }
+    /// Матрица, сдвигающая мир на позицию камеры. Умножается на проекцию справа.
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(Vector3::new(-self.position.x as f32,
+                                               -self.position.y as f32, 0.0))
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera::new()
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use crate::graphics::capture::{CaptureError, CaptureFormat};
+
+/// Сколько кадров может ждать кодирования. Если поток не успевает, новые кадры
+/// пропадают, а не копятся в памяти
+const QUEUE_FRAMES: usize = 32;
+/// Скорость квантования цветов GIF от 1 до 30. Чем больше, тем быстрее и хуже цвета
+const GIF_SPEED: i32 = 10;
+
+/// Кодирует кадры записи в отдельном потоке, чтобы не тормозить игру.
+/// GIF кодируется долго, поэтому кадры копятся в очереди потока
+pub struct Encoder {
+    /// None, когда кадров больше не будет и поток дописывает очередь
+    sender: Option<SyncSender<RgbaImage>>,
+    worker: JoinHandle<Result<(), CaptureError>>,
+}
+
+impl Encoder {
+    /// Начинает запись в path: файл GIF или папку для последовательности PNG.
+    /// fps нужен, чтобы GIF проигрывался с той же скоростью
+    pub fn start(path: &Path, format: CaptureFormat, fps: u32) -> Result<Self, CaptureError> {
+        let (sender, receiver) = mpsc::sync_channel::<RgbaImage>(QUEUE_FRAMES);
+        let worker = match format {
+            CaptureFormat::Gif => {
+                let file = BufWriter::new(File::create(path)?);
+                let mut encoder = GifEncoder::new_with_speed(file, GIF_SPEED);
+                encoder.set_repeat(Repeat::Infinite)?;
+                let delay = Delay::from_numer_denom_ms(1000, fps.max(1));
+                thread::spawn(move || {
+                    for image in receiver {
+                        encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+                    }
+                    Ok(())
+                })
+            }
+            CaptureFormat::PngSequence => {
+                fs::create_dir_all(path)?;
+                let dir = path.to_path_buf();
+                thread::spawn(move || {
+                    for (i, image) in receiver.into_iter().enumerate() {
+                        image.save(frame_path(&dir, i))?;
+                    }
+                    Ok(())
+                })
+            }
+        };
+        Ok(Encoder { sender: Some(sender), worker })
+    }
+    /// Отдаёт кадр потоку, не дожидаясь его. Возвращает false, если кадр пропал:
+    /// очередь полна, запись уже закрыта или поток упал. Ошибку потока вернёт finish
+    pub fn push(&self, image: RgbaImage) -> bool {
+        self.sender.as_ref().is_some_and(|sender| sender.try_send(image).is_ok())
+    }
+    /// Говорит потоку, что кадров больше не будет. Поток дописывает очередь
+    /// и закрывает файл, а узнать, что он закончил, можно через is_finished
+    pub fn close(&mut self) {
+        self.sender = None;
+    }
+    /// Закончил ли поток работу. Без close поток ждёт новых кадров и не заканчивает
+    pub fn is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+    /// Закрывает запись и ждёт, пока все кадры закодируются.
+    /// После is_finished возвращается сразу
+    pub fn finish(mut self) -> Result<(), CaptureError> {
+        self.close();
+        self.worker.join().map_err(|_| CaptureError::EncoderPanicked)?
+    }
+}
+
+/// Файл кадра номер i в последовательности PNG
+fn frame_path(dir: &Path, i: usize) -> PathBuf {
+    dir.join(format!("frame-{i:04}.png"))
+}
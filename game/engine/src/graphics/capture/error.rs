@@ -0,0 +1,55 @@
+use std::fmt;
+use glium::texture::TextureCreationError;
+
+/// Ошибка снимка экрана или записи
+#[derive(Debug)]
+pub enum CaptureError {
+    /// Файл или папку не получилось создать
+    Io(std::io::Error),
+    /// Картинку не получилось закодировать
+    Image(image::ImageError),
+    /// Видеокарта не смогла создать текстуру для копии кадра
+    Texture(TextureCreationError),
+    /// Копию кадра не получилось прочитать из видеокарты
+    Read(glium::buffer::ReadError),
+    /// Поток, который кодирует запись, завершился с паникой
+    EncoderPanicked,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::Io(e) => write!(f, "не удалось записать файл: {e}"),
+            CaptureError::Image(e) => write!(f, "не удалось закодировать картинку: {e}"),
+            CaptureError::Texture(e) => write!(f, "не удалось скопировать кадр: {e}"),
+            CaptureError::Read(e) => write!(f, "не удалось прочитать кадр: {e}"),
+            CaptureError::EncoderPanicked => write!(f, "запись прервалась с ошибкой"),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<std::io::Error> for CaptureError {
+    fn from(e: std::io::Error) -> Self {
+        CaptureError::Io(e)
+    }
+}
+
+impl From<image::ImageError> for CaptureError {
+    fn from(e: image::ImageError) -> Self {
+        CaptureError::Image(e)
+    }
+}
+
+impl From<TextureCreationError> for CaptureError {
+    fn from(e: TextureCreationError) -> Self {
+        CaptureError::Texture(e)
+    }
+}
+
+impl From<glium::buffer::ReadError> for CaptureError {
+    fn from(e: glium::buffer::ReadError) -> Self {
+        CaptureError::Read(e)
+    }
+}
@@ -0,0 +1,221 @@
+//! Снимки экрана и запись игры для отчётов об ошибках. ScreenCapture сохраняет
+//! снимки в PNG и записывает несколько секунд игры в GIF или в последовательность PNG.
+//! Кадры записи читаются из видеокарты с опозданием на один снятый кадр и кодируются
+//! в отдельном потоке, поэтому запись не останавливает игровой цикл.
+pub mod encoder;
+pub mod error;
+
+pub use encoder::Encoder;
+pub use error::CaptureError;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use glium::texture::pixel_buffer::PixelBuffer;
+use glium::texture::RawImage2d;
+use image::RgbaImage;
+use crate::graphics::sprite::SpriteManager;
+
+/// Во что записываются кадры
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureFormat {
+    /// Один анимированный GIF
+    Gif,
+    /// Папка с файлами frame-0000.png, frame-0001.png и так далее
+    PngSequence,
+}
+
+/// Куда и как сохранять снимки и записи
+#[derive(Debug, Clone)]
+pub struct CaptureSettings {
+    pub dir: PathBuf,
+    /// Сколько кадров в секунду записывать. Остальные кадры игры пропускаются
+    pub fps: u32,
+    pub format: CaptureFormat,
+}
+
+impl CaptureSettings {
+    /// Снимки и записи в папке dir, запись в GIF по 15 кадров в секунду
+    pub fn new(dir: &Path) -> Self {
+        CaptureSettings { dir: dir.to_path_buf(), fps: 15, format: CaptureFormat::Gif }
+    }
+    pub fn with_fps(mut self, fps: u32) -> Self {
+        self.fps = fps.max(1);
+        self
+    }
+    pub fn with_format(mut self, format: CaptureFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+/// Кадр, который копируется из видеокарты в память
+pub struct PendingFrame {
+    buffer: PixelBuffer<(u8, u8, u8, u8)>,
+}
+
+impl PendingFrame {
+    pub(crate) fn new(buffer: PixelBuffer<(u8, u8, u8, u8)>) -> Self {
+        PendingFrame { buffer }
+    }
+    /// Забирает картинку. Если видеокарта ещё не скопировала кадр, ждёт её
+    pub fn read(self) -> Result<RgbaImage, CaptureError> {
+        Ok(raw_to_image(self.buffer.read_as_texture_2d()?))
+    }
+}
+
+/// Картинка из прочитанной текстуры. OpenGL хранит строки снизу вверх,
+/// а в картинке первая строка это верх
+pub(crate) fn raw_to_image(raw: RawImage2d<u8>) -> RgbaImage {
+    let (width, height) = (raw.width, raw.height);
+    let image = RgbaImage::from_raw(width, height, raw.data.into_owned())
+        .unwrap_or_else(|| RgbaImage::new(width, height));
+    image::imageops::flip_vertical(&image)
+}
+
+/// Идущая запись
+struct Recording {
+    path: PathBuf,
+    encoder: Encoder,
+    /// Сколько кадров ещё снять
+    frames_left: u32,
+    /// Сколько миллисекунд прошло с последнего снятого кадра
+    since_last: u32,
+    pending: Option<PendingFrame>,
+}
+
+/// Снимки экрана и запись игры. update нужно звать каждый кадр после того,
+/// как всё нарисовано, но до frame.finish()
+pub struct ScreenCapture {
+    settings: CaptureSettings,
+    recording: Option<Recording>,
+    /// Законченные записи, которые поток ещё дописывает
+    finishing: Vec<(PathBuf, Encoder)>,
+}
+
+impl ScreenCapture {
+    pub fn new(settings: CaptureSettings) -> Self {
+        ScreenCapture { settings, recording: None, finishing: Vec::new() }
+    }
+    pub fn settings(&self) -> &CaptureSettings {
+        &self.settings
+    }
+    pub fn set_settings(&mut self, settings: CaptureSettings) {
+        self.settings = settings;
+    }
+    /// Сохраняет то, что нарисовано во фрейме, в PNG и возвращает путь к нему
+    pub fn screenshot(&self, manager: &SpriteManager, frame: &glium::Frame)
+        -> Result<PathBuf, CaptureError>
+    {
+        let image = manager.screenshot(frame)?;
+        fs::create_dir_all(&self.settings.dir)?;
+        let path = self.new_path("screenshot", "png");
+        image.save(&path)?;
+        Ok(path)
+    }
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+    /// Начинает запись длиной seconds секунд и возвращает путь, куда она пишется.
+    /// Если запись уже идёт, она сначала заканчивается
+    pub fn start_recording(&mut self, seconds: f32) -> Result<PathBuf, CaptureError> {
+        self.stop_recording()?;
+        fs::create_dir_all(&self.settings.dir)?;
+        let path = match self.settings.format {
+            CaptureFormat::Gif => self.new_path("recording", "gif"),
+            CaptureFormat::PngSequence => self.new_path("recording", ""),
+        };
+        let encoder = Encoder::start(&path, self.settings.format, self.settings.fps)?;
+        self.recording = Some(Recording {
+            path: path.clone(),
+            encoder,
+            frames_left: (seconds.max(0.0) * self.settings.fps as f32).ceil() as u32,
+            since_last: 0,
+            pending: None,
+        });
+        Ok(path)
+    }
+    /// Заканчивает запись раньше времени, не дожидаясь кодирования.
+    /// Когда запись допишется, её путь вернёт update
+    pub fn stop_recording(&mut self) -> Result<(), CaptureError> {
+        let mut recording = match self.recording.take() {
+            Some(r) => r,
+            None => return Ok(()),
+        };
+        let mut result = Ok(());
+        if let Some(pending) = recording.pending.take() {
+            match pending.read() {
+                Ok(image) => {
+                    recording.encoder.push(image);
+                }
+                Err(e) => result = Err(e),
+            }
+        }
+        // Запись закрывается, даже если последний кадр не прочитался
+        recording.encoder.close();
+        self.finishing.push((recording.path, recording.encoder));
+        result
+    }
+    /// Снимает кадр записи, если с прошлого снятого кадра прошло достаточно времени.
+    /// dt это время с прошлого вызова в миллисекундах.
+    /// Возвращает путь к записи, когда она дописана в файл
+    pub fn update(&mut self, manager: &SpriteManager, frame: &glium::Frame, dt: u32)
+        -> Result<Option<PathBuf>, CaptureError>
+    {
+        self.record_frame(manager, frame, dt)?;
+        let finished = self.finishing.iter().position(|(_, encoder)| encoder.is_finished());
+        match finished {
+            Some(i) => {
+                let (path, encoder) = self.finishing.remove(i);
+                encoder.finish()?;
+                Ok(Some(path))
+            }
+            None => Ok(None),
+        }
+    }
+    /// Заканчивает запись и ждёт, пока все записи допишутся. Нужно звать при выходе
+    /// из игры, иначе недописанные записи пропадут. Возвращает пути дописанных записей
+    pub fn finish(&mut self) -> Result<Vec<PathBuf>, CaptureError> {
+        self.stop_recording()?;
+        let mut paths = Vec::new();
+        for (path, encoder) in self.finishing.drain(..) {
+            encoder.finish()?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+    fn record_frame(&mut self, manager: &SpriteManager, frame: &glium::Frame, dt: u32)
+        -> Result<(), CaptureError>
+    {
+        let interval = 1000 / self.settings.fps.max(1);
+        let recording = match &mut self.recording {
+            Some(r) => r,
+            None => return Ok(()),
+        };
+        recording.since_last += dt;
+        if recording.pending.is_some() && recording.since_last < interval {
+            return Ok(());
+        }
+        recording.since_last = recording.since_last.saturating_sub(interval).min(interval);
+        // Прошлый кадр к этому времени уже скопирован, поэтому чтение не ждёт видеокарту.
+        // Если кодирование не успевает, кадр пропускается, а не копится в памяти
+        if let Some(pending) = recording.pending.take() {
+            recording.encoder.push(pending.read()?);
+        }
+        if recording.frames_left == 0 {
+            return self.stop_recording();
+        }
+        recording.frames_left -= 1;
+        recording.pending = Some(manager.read_frame_later(frame)?);
+        Ok(())
+    }
+    /// Новый путь в папке снимков. Имя содержит время, чтобы не перезаписать старые файлы
+    fn new_path(&self, prefix: &str, extension: &str) -> PathBuf {
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let mut path = self.settings.dir.join(format!("{prefix}-{millis}"));
+        path.set_extension(extension);
+        path
+    }
+}
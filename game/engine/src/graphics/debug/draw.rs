@@ -0,0 +1,173 @@
+use std::f64::consts::TAU;
+use cgmath::{InnerSpace, Point2, Vector2};
+use crate::graphics::Vertex;
+use crate::rect::Rectangular;
+use crate::Rect;
+
+/// Фигуры для отладки, которые рисуются за один вызов draw.
+/// Каждая фигура это треугольники одного цвета в координатах мира,
+/// толщина линий тоже в единицах мира. Фигуры копятся, пока их не нарисует
+/// SpriteManager::draw_shapes, поэтому каждый кадр их нужно собирать заново
+/// ```ignore
+/// shapes.clear();
+/// shapes.line(Point2::new(0.0, 0.0), Point2::new(100.0, 50.0), 2.0, [1.0, 0.0, 0.0, 1.0]);
+/// shapes.circle_outline(Point2::new(50.0, 50.0), 20.0, 1.0, [0.0, 1.0, 0.0, 1.0]);
+/// sprite_manager.draw_shapes(&shapes, &mut frame);
+/// ```
+#[derive(Clone, Default)]
+pub struct DebugDraw {
+    vertices: Vec<Vertex>,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        DebugDraw { vertices: Vec::new() }
+    }
+    /// Убирает все фигуры
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+    /// Вершины всех фигур, каждые три это один треугольник
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+    pub fn triangle(&mut self, a: Point2<f64>, b: Point2<f64>, c: Point2<f64>, color: [f32; 4]) {
+        for point in [a, b, c] {
+            self.vertices.push(Vertex { position: [point.x, point.y], tex_coords: [0.0, 0.0], color });
+        }
+    }
+    /// Отрезок толщины thickness. Концы продлены на половину толщины,
+    /// чтобы углы ломаных и рамок были закрыты
+    pub fn line(&mut self, from: Point2<f64>, to: Point2<f64>, thickness: f64, color: [f32; 4]) {
+        let direction = to - from;
+        if direction.magnitude2() == 0.0 {
+            return;
+        }
+        let along = direction.normalize() * thickness / 2.0;
+        let across = Vector2::new(-along.y, along.x);
+        let (from, to) = (from - along, to + along);
+        self.quad(from + across, to + across, to - across, from - across, color);
+    }
+    /// Отрезок со стрелкой на конце to, например скорость
+    pub fn arrow(&mut self, from: Point2<f64>, to: Point2<f64>, thickness: f64, color: [f32; 4]) {
+        let direction = to - from;
+        let length = direction.magnitude();
+        if length == 0.0 {
+            return;
+        }
+        let head = (thickness * 4.0).min(length);
+        let back = direction / length * head;
+        let across = Vector2::new(-back.y, back.x) / 2.0;
+        let base = to - back;
+        self.line(from, base, thickness, color);
+        self.triangle(to, base + across, base - across, color);
+    }
+    /// Залитый квадрат
+    pub fn rect(&mut self, rect: &Rect, color: [f32; 4]) {
+        let [left_top, right_top, right_bottom, left_bottom] = corners(rect);
+        self.quad(left_top, right_top, right_bottom, left_bottom, color);
+    }
+    /// Рамка квадрата. Линии идут по его краям, половина толщины снаружи
+    pub fn rect_outline(&mut self, rect: &Rect, thickness: f64, color: [f32; 4]) {
+        self.polygon_outline(&corners(rect), thickness, color);
+    }
+    /// Залитый круг
+    pub fn circle(&mut self, center: Point2<f64>, radius: f64, color: [f32; 4]) {
+        let points = circle_points(center, radius);
+        for (i, point) in points.iter().enumerate() {
+            self.triangle(center, *point, points[(i + 1) % points.len()], color);
+        }
+    }
+    pub fn circle_outline(&mut self, center: Point2<f64>, radius: f64, thickness: f64,
+                          color: [f32; 4]) {
+        self.polygon_outline(&circle_points(center, radius), thickness, color);
+    }
+    /// Залитый многоугольник. Он может быть невыпуклым, но стороны не должны пересекаться
+    pub fn polygon(&mut self, points: &[Point2<f64>], color: [f32; 4]) {
+        for [a, b, c] in triangulate(points) {
+            self.triangle(points[a], points[b], points[c], color);
+        }
+    }
+    /// Замкнутая ломаная через все точки
+    pub fn polygon_outline(&mut self, points: &[Point2<f64>], thickness: f64, color: [f32; 4]) {
+        for (i, point) in points.iter().enumerate() {
+            self.line(*point, points[(i + 1) % points.len()], thickness, color);
+        }
+    }
+    fn quad(&mut self, a: Point2<f64>, b: Point2<f64>, c: Point2<f64>, d: Point2<f64>,
+            color: [f32; 4]) {
+        self.triangle(a, b, c, color);
+        self.triangle(a, c, d, color);
+    }
+}
+
+/// Углы квадрата по часовой стрелке на экране начиная с левого верхнего
+fn corners(rect: &Rect) -> [Point2<f64>; 4] {
+    let top = rect.bottom() - rect.height();
+    [
+        Point2::new(rect.left(), top),
+        Point2::new(rect.right(), top),
+        Point2::new(rect.right(), rect.bottom()),
+        Point2::new(rect.left(), rect.bottom()),
+    ]
+}
+
+/// Точки на окружности. Чем больше круг, тем больше точек
+fn circle_points(center: Point2<f64>, radius: f64) -> Vec<Point2<f64>> {
+    let segments = (radius.abs().sqrt() * 4.0).clamp(12.0, 64.0) as usize;
+    (0..segments)
+        .map(|i| {
+            let angle = i as f64 / segments as f64 * TAU;
+            center + Vector2::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// Делит многоугольник на треугольники отрезанием ушей.
+/// Возвращает номера вершин каждого треугольника
+fn triangulate(points: &[Point2<f64>]) -> Vec<[usize; 3]> {
+    let cross = |a: Point2<f64>, b: Point2<f64>, c: Point2<f64>| {
+        (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+    };
+    // Знак площади говорит, в какую сторону обходятся вершины
+    let area: f64 = (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    let orientation = area.signum();
+    let mut left: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::new();
+    while left.len() > 3 {
+        let count = left.len();
+        let ear = (0..count).find(|&i| {
+            let (a, b, c) = (left[(i + count - 1) % count], left[i], left[(i + 1) % count]);
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+            if cross(pa, pb, pc) * orientation <= 0.0 {
+                return false;
+            }
+            // В ухе не должно быть других вершин
+            !left.iter()
+                .filter(|&&p| p != a && p != b && p != c)
+                .any(|&p| {
+                    let point = points[p];
+                    cross(pa, pb, point) * orientation >= 0.0
+                        && cross(pb, pc, point) * orientation >= 0.0
+                        && cross(pc, pa, point) * orientation >= 0.0
+                })
+        });
+        // Ушей нет только у многоугольника с пересекающимися сторонами,
+        // его остаток рисуется как получится
+        let i = ear.unwrap_or(0);
+        triangles.push([left[(i + count - 1) % count], left[i], left[(i + 1) % count]]);
+        left.remove(i);
+    }
+    if left.len() == 3 {
+        triangles.push([left[0], left[1], left[2]]);
+    }
+    triangles
+}
@@ -0,0 +1,8 @@
+//! Отладочное рисование: линии, квадраты, круги и многоугольники любого цвета,
+//! залитые и рамкой, и слой поверх игры, который ими показывает квадраты спрайтов,
+//! скорости, столкновения и сетку карты.
+pub mod draw;
+pub mod overlay;
+
+pub use draw::DebugDraw;
+pub use overlay::DebugOverlay;
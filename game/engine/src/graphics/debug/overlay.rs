@@ -0,0 +1,210 @@
+use cgmath::Point2;
+use glium::glutin::dpi::PhysicalSize;
+use crate::component::traits::Composite;
+use crate::graphics::debug::draw::DebugDraw;
+use crate::graphics::sprite::SpriteGroup;
+use crate::graphics::Sprite;
+use crate::group::SomeGroup;
+use crate::physics::components::PhysicsComponent;
+use crate::rect::Rectangular;
+use crate::tilemap::{TileGrid, Tilemap};
+use crate::Rect;
+
+const RECT_COLOR: [f32; 4] = [0.2, 1.0, 0.2, 0.9];
+const VELOCITY_COLOR: [f32; 4] = [1.0, 0.9, 0.1, 0.9];
+const CONTACT_COLOR: [f32; 4] = [1.0, 0.2, 0.2, 0.9];
+const CONTACT_FILL_COLOR: [f32; 4] = [1.0, 0.2, 0.2, 0.35];
+const GRID_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 0.35];
+const OCCUPIED_CELL_COLOR: [f32; 4] = [0.3, 0.6, 1.0, 0.2];
+
+/// Отладочный слой поверх игры: квадраты всех спрайтов, их скорости,
+/// пересечения квадратов и сетка тайлов карты. Клетки, которые задевает спрайт,
+/// подсвечены: именно их карта проверяет, когда ищет твёрдые тайлы под спрайтом.
+/// Пересечения это общие части квадратов спрайтов между собой и с твёрдыми тайлами.
+/// Физика их не запоминает, поэтому слой считает их сам по квадратам,
+/// а не показывает то, на что на самом деле наткнулась физика.
+/// Что именно показывать, задаётся полями show_*, а весь слой включается toggle
+/// ```ignore
+/// if input.is_key_pressed(VirtualKeyCode::F3) {
+///     overlay.toggle();
+/// }
+/// if overlay.is_enabled() {
+///     sprite_manager.draw_shapes(overlay.build(&sprites, Some(&tilemap)), &mut frame);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct DebugOverlay {
+    enabled: bool,
+    pub show_rects: bool,
+    pub show_velocities: bool,
+    pub show_contacts: bool,
+    pub show_grid: bool,
+    /// Стрелка скорости показывает, куда спрайт сместится за столько секунд
+    pub velocity_scale: f64,
+    /// Толщина линий в единицах мира
+    pub line_width: f64,
+    shapes: DebugDraw,
+}
+
+impl DebugOverlay {
+    /// Выключенный слой, который показывает всё
+    pub fn new() -> Self {
+        DebugOverlay {
+            enabled: false,
+            show_rects: true,
+            show_velocities: true,
+            show_contacts: true,
+            show_grid: true,
+            velocity_scale: 0.25,
+            line_width: 1.5,
+            shapes: DebugDraw::new(),
+        }
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+    /// Включает слой, если он выключен, и выключает, если включён
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+    /// Собирает фигуры слоя заново. Выключенный слой пустой
+    pub fn build(&mut self, sprites: &SpriteGroup, tilemap: Option<&Tilemap>) -> &DebugDraw {
+        self.shapes.clear();
+        if !self.enabled {
+            return &self.shapes;
+        }
+        let visible: Vec<&Sprite> = sprites.get_elements().iter().flatten()
+            .filter(|s| !s.is_hidden())
+            .collect();
+        if let (true, Some(tilemap)) = (self.show_grid, tilemap) {
+            self.grid(tilemap, &visible);
+        }
+        if self.show_contacts {
+            self.contacts(tilemap, &visible);
+        }
+        for sprite in &visible {
+            if self.show_rects {
+                self.shapes.rect_outline(sprite.get_rect(), self.line_width, RECT_COLOR);
+            }
+            let physics = sprite.get_component::<PhysicsComponent>();
+            if let (true, Some(physics)) = (self.show_velocities, physics) {
+                let center = center(sprite.get_rect());
+                let end = center + physics.velocity() * self.velocity_scale;
+                self.shapes.arrow(center, end, self.line_width, VELOCITY_COLOR);
+            }
+        }
+        &self.shapes
+    }
+    /// Фигуры, собранные последним build
+    pub fn shapes(&self) -> &DebugDraw {
+        &self.shapes
+    }
+    fn grid(&mut self, tilemap: &Tilemap, sprites: &[&Sprite]) {
+        let (columns, rows) = tilemap.size_in_tiles();
+        let (tile_w, tile_h) = tilemap.tileset().tile_size();
+        let (width, height) = ((columns as u32 * tile_w) as f64, (rows as u32 * tile_h) as f64);
+        for sprite in sprites {
+            let (columns, rows) = tilemap.cells_in(sprite.get_rect());
+            for row in rows {
+                for column in columns.clone() {
+                    self.shapes.rect(&tilemap.cell_rect(column, row), OCCUPIED_CELL_COLOR);
+                }
+            }
+        }
+        let thickness = self.line_width / 2.0;
+        for column in 0..=columns {
+            let x = (column as u32 * tile_w) as f64;
+            self.shapes.line(Point2::new(x, 0.0), Point2::new(x, height), thickness, GRID_COLOR);
+        }
+        for row in 0..=rows {
+            let y = (row as u32 * tile_h) as f64;
+            self.shapes.line(Point2::new(0.0, y), Point2::new(width, y), thickness, GRID_COLOR);
+        }
+    }
+    fn contacts(&mut self, tilemap: Option<&Tilemap>, sprites: &[&Sprite]) {
+        let rects = sprites.iter().map(|s| s.get_rect()).collect();
+        let overlaps = overlaps(rects, tilemap.map(Tilemap::grid));
+        for overlap in overlaps {
+            self.shapes.rect(&overlap, CONTACT_FILL_COLOR);
+            self.shapes.rect_outline(&overlap, self.line_width, CONTACT_COLOR);
+            self.shapes.circle(center(&overlap), self.line_width * 2.0, CONTACT_COLOR);
+        }
+    }
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        DebugOverlay::new()
+    }
+}
+
+fn center(rect: &Rect) -> Point2<f64> {
+    Point2::new(rect.left() + rect.width() / 2.0, rect.bottom() - rect.height() / 2.0)
+}
+
+/// Общие части квадратов между собой и с твёрдыми тайлами сетки.
+/// Квадраты перебираются слева направо, и каждый сравнивается только с теми,
+/// что начинаются левее его правого края
+fn overlaps(mut rects: Vec<&Rect>, grid: Option<TileGrid>) -> Vec<Rect> {
+    rects.sort_by(|a, b| a.left().total_cmp(&b.left()));
+    let mut overlaps = Vec::new();
+    for (i, a) in rects.iter().enumerate() {
+        for b in rects[i + 1..].iter().take_while(|b| b.left() < a.right()) {
+            overlaps.extend(intersection(a, b));
+        }
+        if let Some(grid) = grid {
+            overlaps.extend(grid.solid_cells_in(a).into_iter()
+                .filter_map(|(column, row)| intersection(a, &grid.cell_rect(column, row))));
+        }
+    }
+    overlaps
+}
+
+/// Общая часть двух квадратов, если они пересекаются
+fn intersection(a: &Rect, b: &Rect) -> Option<Rect> {
+    let left = a.left().max(b.left());
+    let right = a.right().min(b.right());
+    let top = (a.bottom() - a.height()).max(b.bottom() - b.height());
+    let bottom = a.bottom().min(b.bottom());
+    (left < right && top < bottom)
+        .then(|| Rect::new(Point2::new(left, bottom), PhysicalSize::new(right - left, bottom - top)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::tilemap::{MapDescription, TileFlags};
+
+    fn rect(left: f64, bottom: f64, width: f64, height: f64) -> Rect {
+        Rect::new(Point2::new(left, bottom), PhysicalSize::new(width, height))
+    }
+
+    #[test]
+    fn overlapping_rects_and_solid_tiles_are_found() {
+        let a = rect(0.0, 10.0, 10.0, 10.0);
+        let b = rect(5.0, 15.0, 10.0, 10.0);
+        // c левее всех по списку, но правее по миру и ни с кем не пересекается
+        let c = rect(40.0, 10.0, 5.0, 5.0);
+        let d = rect(9.0, 30.0, 2.0, 2.0);
+        assert_eq!(overlaps(vec![&c, &b, &d, &a], None), vec![rect(5.0, 10.0, 5.0, 5.0)]);
+        // Касание краями это не пересечение
+        assert!(overlaps(vec![&a, &rect(10.0, 10.0, 5.0, 5.0)], None).is_empty());
+        let description = MapDescription::parse("\
+tileset = ground.png
+tile_size = 10, 10
+solid = 1
+[layer ground]
+0,0,0,0,0
+1,1,1,1,1
+").unwrap();
+        let flags: HashMap<u32, TileFlags> = [(1, TileFlags {solid: true})].into();
+        let grid = TileGrid::new(description.tile_size, &description.layers, &flags);
+        // Квадрат c на 2 единицы уходит в землю
+        let c = rect(40.0, 12.0, 5.0, 5.0);
+        assert_eq!(overlaps(vec![&c], Some(grid)), vec![rect(40.0, 12.0, 5.0, 2.0)]);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod style;
+pub mod ui;
+
+pub use style::Style;
+pub use ui::{Ui, UiCommand};
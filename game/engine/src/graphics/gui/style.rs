@@ -0,0 +1,37 @@
+/// Цвета и размеры виджетов. Цвета в RGBA от 0 до 1
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    pub panel: [f32; 4],
+    pub widget: [f32; 4],
+    pub widget_hovered: [f32; 4],
+    pub widget_pressed: [f32; 4],
+    pub accent: [f32; 4],
+    pub focus: [f32; 4],
+    pub text: [f32; 4],
+    /// Высота одной строки виджетов
+    pub row_height: f64,
+    /// Отступ от края панели до виджетов
+    pub padding: f64,
+    /// Расстояние между виджетами
+    pub spacing: f64,
+    /// Толщина рамки вокруг виджета в фокусе
+    pub focus_thickness: f64,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            panel: [0.1, 0.1, 0.12, 0.85],
+            widget: [0.25, 0.25, 0.3, 1.0],
+            widget_hovered: [0.35, 0.35, 0.42, 1.0],
+            widget_pressed: [0.18, 0.18, 0.22, 1.0],
+            accent: [0.95, 0.55, 0.15, 1.0],
+            focus: [1.0, 1.0, 1.0, 1.0],
+            text: [1.0, 1.0, 1.0, 1.0],
+            row_height: 28.0,
+            padding: 8.0,
+            spacing: 4.0,
+            focus_thickness: 2.0,
+        }
+    }
+}
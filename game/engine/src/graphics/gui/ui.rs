@@ -0,0 +1,362 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use cgmath::Point2;
+use glium::glutin::dpi::PhysicalSize;
+use glium::glutin::event::{MouseButton, VirtualKeyCode};
+use crate::Rect;
+use crate::graphics::gui::style::Style;
+use crate::graphics::text::{Text, VerticalAlign};
+use crate::input::Input;
+use crate::rect::Rectangular;
+
+/// То, что Ui просит нарисовать. Координаты в пикселях виртуального экрана,
+/// камера на них не влияет.
+#[derive(Debug, Clone)]
+pub enum UiCommand {
+    /// Залитый цветом квадрат
+    Rect { rect: Rect, color: [f32; 4] },
+    Text(Text),
+}
+
+/// Область, в которой виджеты раскладываются сверху вниз
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    left: f64,
+    width: f64,
+    cursor: f64,
+}
+
+/// Интерфейс в стиле immediate mode: виджеты не хранятся между кадрами,
+/// а каждый кадр заново вызываются функции виджетов, которые сразу говорят,
+/// нажали ли на них. Между кадрами помнятся только наведение, нажатие и фокус.
+///
+/// Каждый кадр нужно вызвать begin, затем виджеты, затем end, а после этого
+/// отрисовать commands через SpriteManager::draw_ui.
+/// Подписи виджетов внутри одной панели должны быть уникальными, по ним виджеты различаются.
+#[derive(Debug)]
+pub struct Ui {
+    style: Style,
+    commands: Vec<UiCommand>,
+    layouts: Vec<Layout>,
+    panel_index: u64,
+    mouse: Point2<f64>,
+    mouse_down: bool,
+    mouse_pressed: bool,
+    mouse_released: bool,
+    keys: Vec<VirtualKeyCode>,
+    hot: Option<u64>,
+    active: Option<u64>,
+    focus: Option<u64>,
+    focus_order: Vec<u64>,
+    previous_focus_order: Vec<u64>,
+}
+
+impl Ui {
+    pub fn new(style: Style) -> Self {
+        Ui {
+            style,
+            commands: Vec::new(),
+            layouts: Vec::new(),
+            panel_index: 0,
+            mouse: Point2::new(0.0, 0.0),
+            mouse_down: false,
+            mouse_pressed: false,
+            mouse_released: false,
+            keys: Vec::new(),
+            hot: None,
+            active: None,
+            focus: None,
+            focus_order: Vec::new(),
+            previous_focus_order: Vec::new(),
+        }
+    }
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+    pub fn style_mut(&mut self) -> &mut Style {
+        &mut self.style
+    }
+    /// Начинает новый кадр. screen_size это размер виртуального экрана,
+    /// виджеты вне панелей раскладываются по нему.
+    pub fn begin(&mut self, input: &Input, screen_size: PhysicalSize<u32>) {
+        self.commands.clear();
+        self.layouts.clear();
+        self.layouts.push(Layout {
+            left: self.style.padding,
+            width: screen_size.width as f64 - 2.0 * self.style.padding,
+            cursor: self.style.padding,
+        });
+        self.panel_index = 0;
+        self.hot = None;
+        self.mouse = input.mouse_position();
+        self.mouse_down = input.is_button_down(MouseButton::Left);
+        self.mouse_pressed = input.is_button_pressed(MouseButton::Left);
+        self.mouse_released = input.is_button_released(MouseButton::Left);
+        self.keys = [VirtualKeyCode::Tab, VirtualKeyCode::Return, VirtualKeyCode::Space,
+                     VirtualKeyCode::Left, VirtualKeyCode::Right, VirtualKeyCode::Escape]
+            .into_iter()
+            .filter(|k| input.is_key_pressed(*k))
+            .collect();
+        if self.key(VirtualKeyCode::Escape) {
+            self.focus = None;
+        }
+        if self.key(VirtualKeyCode::Tab) {
+            self.move_focus(input.modifiers().shift());
+        }
+    }
+    /// Заканчивает кадр
+    pub fn end(&mut self) {
+        if !self.mouse_down {
+            self.active = None;
+        }
+        if self.mouse_pressed && self.hot.is_none() {
+            self.focus = None;
+        }
+        self.previous_focus_order = std::mem::take(&mut self.focus_order);
+    }
+    /// Что нужно нарисовать в этом кадре
+    pub fn commands(&self) -> &Vec<UiCommand> {
+        &self.commands
+    }
+    /// Находится ли мышь над каким-нибудь виджетом или панелью.
+    /// Если да, то игре не стоит обрабатывать клик сама.
+    pub fn wants_mouse(&self) -> bool {
+        self.hot.is_some() || self.active.is_some()
+    }
+    /// Панель с фоном. Виджеты внутри f раскладываются сверху вниз внутри rect
+    pub fn panel<F: FnOnce(&mut Ui)>(&mut self, rect: Rect, f: F) {
+        self.panel_index += 1;
+        let id = self.id("panel");
+        if Ui::contains(&rect, self.mouse) {
+            self.hot = self.hot.or(Some(id));
+        }
+        self.fill(rect, self.style.panel);
+        self.layouts.push(Layout {
+            left: rect.left() + self.style.padding,
+            width: rect.width() - 2.0 * self.style.padding,
+            cursor: rect.bottom() - rect.height() + self.style.padding,
+        });
+        f(self);
+        self.layouts.pop();
+    }
+    /// Надпись
+    pub fn label(&mut self, text: &str) {
+        let rect = self.next_rect();
+        self.text(text, rect);
+    }
+    /// Кнопка. Возвращает true, если на неё нажали мышью, либо Enter/пробелом в фокусе
+    pub fn button(&mut self, label: &str) -> bool {
+        let rect = self.next_rect();
+        let id = self.id(label);
+        let (hovered, held, clicked) = self.interact(id, &rect);
+        let color = if held {
+            self.style.widget_pressed
+        } else if hovered {
+            self.style.widget_hovered
+        } else {
+            self.style.widget
+        };
+        self.fill(rect, color);
+        self.focus_frame(id, rect);
+        self.text(label, Rect::new(Point2::new(rect.left() + self.style.padding, rect.bottom()),
+                                   PhysicalSize::new(rect.width(), rect.height())));
+        clicked
+    }
+    /// Флажок. Возвращает true, если значение поменялось
+    pub fn checkbox(&mut self, label: &str, value: &mut bool) -> bool {
+        let rect = self.next_rect();
+        let id = self.id(label);
+        let (hovered, _, clicked) = self.interact(id, &rect);
+        let size = rect.height() - 2.0 * self.style.spacing;
+        let bx = Rect::new(Point2::new(rect.left(), rect.bottom() - self.style.spacing),
+                           PhysicalSize::new(size, size));
+        self.fill(bx, if hovered {self.style.widget_hovered} else {self.style.widget});
+        if clicked {
+            *value = !*value;
+        }
+        if *value {
+            let inset = size / 4.0;
+            self.fill(Rect::new(Point2::new(bx.left() + inset, bx.bottom() - inset),
+                                PhysicalSize::new(size - 2.0 * inset, size - 2.0 * inset)),
+                      self.style.accent);
+        }
+        self.focus_frame(id, bx);
+        let offset = size + self.style.padding;
+        self.text(label, Rect::new(Point2::new(rect.left() + offset, rect.bottom()),
+                                   PhysicalSize::new(rect.width() - offset, rect.height())));
+        clicked
+    }
+    /// Ползунок со значением от min до max. Возвращает true, если значение поменялось.
+    /// С клавиатуры значение меняется стрелками влево и вправо.
+    /// Знаков после запятой показывается столько, чтобы был виден шаг стрелок:
+    /// у громкости от 0 до 1 это 0.05, а у значений до 100 хватает целых
+    pub fn slider(&mut self, label: &str, value: &mut f64, min: f64, max: f64) -> bool {
+        self.label(&format!("{label}: {:.*}", slider_decimals(min, max), value));
+        let rect = self.next_rect();
+        let id = self.id(label);
+        let (hovered, held, _) = self.interact(id, &rect);
+        let old = *value;
+        if held && rect.width() > 0.0 {
+            let fraction = ((self.mouse.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+            *value = min + fraction * (max - min);
+        }
+        if self.focus == Some(id) {
+            let step = (max - min) / 20.0;
+            if self.key(VirtualKeyCode::Left) {
+                *value -= step;
+            }
+            if self.key(VirtualKeyCode::Right) {
+                *value += step;
+            }
+        }
+        *value = value.clamp(min.min(max), max.max(min));
+        let track_height = rect.height() / 3.0;
+        let track = Rect::new(Point2::new(rect.left(), rect.bottom() - track_height),
+                              PhysicalSize::new(rect.width(), track_height));
+        self.fill(track, if hovered || held {self.style.widget_hovered} else {self.style.widget});
+        let fraction = if max != min {(*value - min) / (max - min)} else {0.0};
+        let knob_width = rect.height() / 2.0;
+        let knob = Rect::new(
+            Point2::new(rect.left() + fraction * (rect.width() - knob_width), rect.bottom()),
+            PhysicalSize::new(knob_width, rect.height()));
+        self.fill(knob, self.style.accent);
+        self.focus_frame(id, rect);
+        *value != old
+    }
+    /// Полоска заполненности, например здоровье или сила выстрела. fraction от 0 до 1
+    pub fn progress_bar(&mut self, fraction: f64, color: [f32; 4], text: Option<&str>) {
+        let rect = self.next_rect();
+        self.fill(rect, self.style.widget);
+        let filled = Rect::new(rect.position(),
+                               PhysicalSize::new(rect.width() * fraction.clamp(0.0, 1.0),
+                                                 rect.height()));
+        self.fill(filled, color);
+        if let Some(text) = text {
+            self.text(text, Rect::new(
+                Point2::new(rect.left() + self.style.padding, rect.bottom()),
+                PhysicalSize::new(rect.width(), rect.height())));
+        }
+    }
+    /// Пустое место высотой height
+    pub fn space(&mut self, height: f64) {
+        if let Some(layout) = self.layouts.last_mut() {
+            layout.cursor += height;
+        }
+    }
+    /// Выдаёт квадрат под следующий виджет и сдвигает раскладку вниз
+    fn next_rect(&mut self) -> Rect {
+        let height = self.style.row_height;
+        let spacing = self.style.spacing;
+        let layout = self.layouts.last_mut().expect("Ui::begin не был вызван");
+        let rect = Rect::new(Point2::new(layout.left, layout.cursor + height),
+                             PhysicalSize::new(layout.width, height));
+        layout.cursor += height + spacing;
+        rect
+    }
+    /// Обрабатывает мышь и клавиатуру для виджета.
+    /// Возвращает (мышь над виджетом, виджет зажат, виджет нажали)
+    fn interact(&mut self, id: u64, rect: &Rect) -> (bool, bool, bool) {
+        self.focus_order.push(id);
+        let hovered = Ui::contains(rect, self.mouse);
+        if hovered {
+            self.hot = Some(id);
+            if self.mouse_pressed {
+                self.active = Some(id);
+                self.focus = Some(id);
+            }
+        }
+        let held = self.active == Some(id) && self.mouse_down;
+        let clicked = (self.active == Some(id) && self.mouse_released && hovered)
+            || (self.focus == Some(id)
+                && (self.key(VirtualKeyCode::Return) || self.key(VirtualKeyCode::Space)));
+        (hovered, held, clicked)
+    }
+    /// Переводит фокус на следующий (или предыдущий) виджет прошлого кадра
+    fn move_focus(&mut self, backwards: bool) {
+        let order = &self.previous_focus_order;
+        if order.is_empty() {
+            return;
+        }
+        let current = self.focus.and_then(|f| order.iter().position(|id| *id == f));
+        let next = match (current, backwards) {
+            (None, false) => 0,
+            (None, true) => order.len() - 1,
+            (Some(i), false) => (i + 1) % order.len(),
+            (Some(i), true) => (i + order.len() - 1) % order.len(),
+        };
+        self.focus = Some(order[next]);
+    }
+    fn id(&self, label: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.panel_index.hash(&mut hasher);
+        label.hash(&mut hasher);
+        hasher.finish()
+    }
+    fn key(&self, key: VirtualKeyCode) -> bool {
+        self.keys.contains(&key)
+    }
+    fn fill(&mut self, rect: Rect, color: [f32; 4]) {
+        self.commands.push(UiCommand::Rect {rect, color});
+    }
+    fn text(&mut self, text: &str, rect: Rect) {
+        self.commands.push(UiCommand::Text(
+            Text::new(text, rect.width(), rect.height())
+                .with_position(rect.left(), rect.bottom())
+                .with_vertical_align(VerticalAlign::Middle)
+                .with_wrap(false)
+                .with_color(self.style.text)));
+    }
+    /// Рамка вокруг виджета в фокусе
+    fn focus_frame(&mut self, id: u64, rect: Rect) {
+        if self.focus != Some(id) {
+            return;
+        }
+        let t = self.style.focus_thickness;
+        let (left, bottom) = (rect.left() - t, rect.bottom() + t);
+        let (width, height) = (rect.width() + 2.0 * t, rect.height() + 2.0 * t);
+        let color = self.style.focus;
+        self.fill(Rect::new(Point2::new(left, bottom - height + t),
+                            PhysicalSize::new(width, t)), color);
+        self.fill(Rect::new(Point2::new(left, bottom), PhysicalSize::new(width, t)), color);
+        self.fill(Rect::new(Point2::new(left, bottom), PhysicalSize::new(t, height)), color);
+        self.fill(Rect::new(Point2::new(left + width - t, bottom),
+                            PhysicalSize::new(t, height)), color);
+    }
+    fn contains(rect: &Rect, point: Point2<f64>) -> bool {
+        point.x >= rect.left() && point.x <= rect.right()
+            && point.y <= rect.bottom() && point.y >= rect.bottom() - rect.height()
+    }
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Ui::new(Style::default())
+    }
+}
+
+/// Сколько знаков после запятой нужно, чтобы отличать значения ползунка
+/// от min до max: две значащие цифры на весь диапазон, но не больше трёх знаков
+fn slider_decimals(min: f64, max: f64) -> usize {
+    let range = (max - min).abs();
+    if range == 0.0 || !range.is_finite() {
+        return 0;
+    }
+    (2.0 - range.log10().floor()).clamp(0.0, 3.0) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slider_decimals_follow_range() {
+        assert_eq!(slider_decimals(0.0, 1.0), 2);
+        assert_eq!(slider_decimals(0.0, 10.0), 1);
+        assert_eq!(slider_decimals(0.0, 100.0), 0);
+        assert_eq!(slider_decimals(-500.0, 500.0), 0);
+        assert_eq!(slider_decimals(0.0, 0.1), 3);
+        assert_eq!(slider_decimals(0.0, 0.001), 3);
+        assert_eq!(slider_decimals(1.0, 0.0), 2);
+        assert_eq!(slider_decimals(5.0, 5.0), 0);
+    }
+}
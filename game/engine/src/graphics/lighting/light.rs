@@ -0,0 +1,237 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use cgmath::Point2;
+use glium::glutin::dpi::PhysicalSize;
+use glium::texture::TextureCreationError;
+use crate::parse::{self, key_value, parse_number, parse_numbers, parse_rgb, syntax, Line};
+use crate::programs::ProgramError;
+use crate::tilemap::Tilemap;
+use crate::Rect;
+
+/// Ошибка освещения: чтение настроек сцены или создание того, чем оно рисуется
+#[derive(Debug)]
+pub enum LightingError {
+    /// Файл не получилось прочитать
+    Io(std::io::Error),
+    /// Ошибка в самом файле, line это номер строки начиная с 1
+    Syntax { line: usize, reason: String },
+    /// Программа освещения не собралась
+    Program(ProgramError),
+    /// Видеокарта не смогла создать текстуру для света
+    Texture(TextureCreationError),
+}
+
+impl fmt::Display for LightingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LightingError::Io(e) => write!(f, "не удалось прочитать освещение: {e}"),
+            LightingError::Syntax { line, reason } => write!(f, "строка {line}: {reason}"),
+            LightingError::Program(e) => write!(f, "{e}"),
+            LightingError::Texture(e) => write!(f, "не удалось создать текстуру света: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for LightingError {}
+
+impl From<std::io::Error> for LightingError {
+    fn from(e: std::io::Error) -> Self {
+        LightingError::Io(e)
+    }
+}
+
+impl From<(usize, String)> for LightingError {
+    fn from((line, reason): (usize, String)) -> Self {
+        LightingError::Syntax { line, reason }
+    }
+}
+
+impl From<ProgramError> for LightingError {
+    fn from(e: ProgramError) -> Self {
+        LightingError::Program(e)
+    }
+}
+
+impl From<TextureCreationError> for LightingError {
+    fn from(e: TextureCreationError) -> Self {
+        LightingError::Texture(e)
+    }
+}
+
+/// Как свет расходится от источника
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    /// Во все стороны
+    Point,
+    /// Конусом. direction это угол в радианах, 0 вправо, PI/2 вниз,
+    /// angle это половина ширины конуса
+    Spot { direction: f64, angle: f64 },
+}
+
+/// Источник света
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub position: Point2<f64>,
+    pub kind: LightKind,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Дальше radius свет не достаёт
+    pub radius: f64,
+    /// Как быстро свет гаснет к краю: 1 равномерно, больше 1 быстрее у источника
+    pub falloff: f32,
+    /// Высота источника над сценой. Влияет только на спрайты с картой нормалей
+    pub height: f32,
+    /// Отбрасывает ли свет тени от земли
+    pub shadows: bool,
+}
+
+impl Light {
+    /// Белый точечный свет с тенями
+    pub fn point(position: Point2<f64>, radius: f64) -> Self {
+        Light {
+            position,
+            kind: LightKind::Point,
+            color: [1.0, 1.0, 1.0],
+            intensity: 1.0,
+            radius,
+            falloff: 2.0,
+            height: 50.0,
+            shadows: true,
+        }
+    }
+    /// Белый свет конусом с тенями
+    pub fn spot(position: Point2<f64>, radius: f64, direction: f64, angle: f64) -> Self {
+        Light { kind: LightKind::Spot { direction, angle }, ..Light::point(position, radius) }
+    }
+    pub fn with_color(mut self, color: [f32; 3]) -> Self {
+        self.color = color;
+        self
+    }
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+    pub fn with_falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+    pub fn with_height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+    pub fn with_shadows(mut self, shadows: bool) -> Self {
+        self.shadows = shadows;
+        self
+    }
+    /// Квадрат, в котором свет виден
+    pub fn bounds(&self) -> Rect {
+        let size = self.radius * 2.0;
+        Rect::new(Point2::new(self.position.x - self.radius, self.position.y + self.radius),
+                  PhysicalSize::new(size, size))
+    }
+}
+
+/// Освещение сцены: общий свет ambient, который есть везде, источники света
+/// и земля, которая отбрасывает тени. Без источников сцена умножается на ambient
+#[derive(Debug, Clone)]
+pub struct Lighting {
+    pub ambient: [f32; 3],
+    pub lights: Vec<Light>,
+    /// Квадраты земли, за которыми тень
+    pub occluders: Vec<Rect>,
+}
+
+impl Lighting {
+    pub fn new(ambient: [f32; 3]) -> Self {
+        Lighting { ambient, lights: Vec::new(), occluders: Vec::new() }
+    }
+    pub fn with_light(mut self, light: Light) -> Self {
+        self.lights.push(light);
+        self
+    }
+    pub fn add_light(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+    /// Тени отбрасывают твёрдые тайлы карты. Старые квадраты земли заменяются
+    pub fn set_terrain(&mut self, tilemap: &Tilemap) {
+        let (width, height) = tilemap.size_in_tiles();
+        let (tile_w, tile_h) = tilemap.tileset().tile_size();
+        let area = Rect::new(Point2::new(0.0, (height as u32 * tile_h) as f64),
+                             PhysicalSize::new((width as u32 * tile_w) as f64,
+                                               (height as u32 * tile_h) as f64));
+        self.occluders = tilemap.solid_cells_in(&area).into_iter()
+            .map(|(column, row)| tilemap.cell_rect(column, row))
+            .collect();
+    }
+    /// Разбирает освещение сцены. Сначала идёт `ambient = r, g, b`, потом источники:
+    /// каждый начинается строкой `[point]` или `[spot]`, после неё идут его настройки.
+    /// Углы записываются в градусах. Строки, начинающиеся с `#`, игнорируются.
+    /// ```text
+    /// ambient = 0.15, 0.15, 0.3
+    /// [point]
+    /// position = 300, 500
+    /// radius = 250
+    /// color = 1.0, 0.8, 0.5
+    /// [spot]
+    /// position = 600, 100
+    /// radius = 400
+    /// direction = 90
+    /// angle = 25
+    /// shadows = false
+    /// ```
+    pub fn parse(source: &str) -> Result<Lighting, LightingError> {
+        let mut lighting = Lighting::new([1.0, 1.0, 1.0]);
+        for line in parse::lines(source) {
+            let (n, line) = match line? {
+                (n, Line::Section(header)) => {
+                    let origin = Point2::new(0.0, 0.0);
+                    let light = match header {
+                        "point" => Light::point(origin, 100.0),
+                        "spot" => Light::spot(origin, 100.0, 0.0, std::f64::consts::FRAC_PI_4),
+                        _ => return Err(syntax(n, "ожидалось [point] или [spot]").into()),
+                    };
+                    lighting.add_light(light);
+                    continue;
+                }
+                (n, Line::Text(line)) => (n, line),
+            };
+            let (key, value) = key_value(line, n)?;
+            match lighting.lights.last_mut() {
+                Some(light) => set_light(light, key, value, n)?,
+                None if key == "ambient" => lighting.ambient = parse_rgb(value, n)?,
+                None => return Err(syntax(n, &format!("неизвестный ключ {key}")).into()),
+            }
+        }
+        Ok(lighting)
+    }
+    /// Читает освещение сцены из файла
+    pub fn load(path: &Path) -> Result<Lighting, LightingError> {
+        Lighting::parse(&fs::read_to_string(path)?)
+    }
+}
+
+fn set_light(light: &mut Light, key: &str, value: &str, line: usize) -> Result<(), LightingError> {
+    match (key, &mut light.kind) {
+        ("position", _) => match parse_numbers(value, line)?.as_slice() {
+            [x, y] => light.position = Point2::new(*x, *y),
+            _ => return Err(syntax(line, "position должен быть парой чисел").into()),
+        },
+        ("radius", _) => light.radius = parse_number(value, line)?,
+        ("color", _) => light.color = parse_rgb(value, line)?,
+        ("intensity", _) => light.intensity = parse_number(value, line)?,
+        ("falloff", _) => light.falloff = parse_number(value, line)?,
+        ("height", _) => light.height = parse_number(value, line)?,
+        ("shadows", _) => light.shadows = match value {
+            "true" => true,
+            "false" => false,
+            _ => return Err(syntax(line, "shadows должен быть true или false").into()),
+        },
+        ("direction", LightKind::Spot { direction, .. }) =>
+            *direction = parse_number::<f64>(value, line)?.to_radians(),
+        ("angle", LightKind::Spot { angle, .. }) =>
+            *angle = parse_number::<f64>(value, line)?.to_radians(),
+        (other, _) => return Err(syntax(line, &format!("неизвестный ключ {other}")).into()),
+    }
+    Ok(())
+}
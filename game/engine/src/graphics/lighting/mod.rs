@@ -0,0 +1,9 @@
+//! Освещение ночных уровней: точечные источники и источники конусом с цветом,
+//! радиусом и затуханием, общий свет сцены, карты нормалей спрайтов и тени от земли.
+//! Свет складывается в карту света, которая умножается на нарисованные спрайты.
+pub mod light;
+pub mod renderer;
+pub mod shaders;
+
+pub use light::{Light, LightKind, Lighting, LightingError};
+pub use renderer::LightRenderer;
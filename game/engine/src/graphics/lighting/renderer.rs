@@ -0,0 +1,219 @@
+use glium::backend::Facade;
+use glium::index::PrimitiveType;
+use glium::texture::{MipmapsOption, Texture2d, TextureCreationError, UncompressedFloatFormat};
+use glium::uniforms::{SamplerWrapFunction, Uniforms};
+use glium::{uniform, Blend, BlendingFunction, DrawParameters, IndexBuffer, LinearBlendingFactor,
+            Surface, VertexBuffer};
+use crate::graphics::lighting::light::{LightKind, Lighting, LightingError};
+use crate::graphics::lighting::shaders::{self, COMPOSITE_PROGRAM, LIGHT_PROGRAM, NORMALS_PROGRAM,
+    OCCLUDER_PROGRAM};
+use crate::graphics::render::{frame_tex_coords, quad_vertices, WHITE};
+use crate::graphics::sprite::{SpriteGroup, SpriteManager};
+use crate::graphics::traits::{FrameList, HasTexture, Layered};
+use crate::graphics::{Sprite, Vertex};
+use crate::group::SomeGroup;
+use crate::programs::ProgramManager;
+use crate::rect::Rectangular;
+
+/// Текстуры размера окна, в которые рисуются проходы освещения
+struct Buffers {
+    size: (u32, u32),
+    /// Нормали спрайтов с картой нормалей. Где прозрачно, нормалей нет
+    normals: Texture2d,
+    /// Белым нарисована земля, которая отбрасывает тени
+    occluders: Texture2d,
+    /// Сколько света пришло в каждую точку экрана
+    light_map: Texture2d,
+}
+
+impl Buffers {
+    fn new<F: Facade>(display: &F, size: (u32, u32)) -> Result<Self, TextureCreationError> {
+        let (width, height) = (size.0.max(1), size.1.max(1));
+        let texture = || Texture2d::empty_with_format(
+            display, UncompressedFloatFormat::U8U8U8U8, MipmapsOption::NoMipmap, width, height);
+        Ok(Buffers { size, normals: texture()?, occluders: texture()?, light_map: texture()? })
+    }
+}
+
+/// Рисует освещение сцены поверх спрайтов. Свет всех источников складывается
+/// в карту света, которая потом умножается на то, что уже нарисовано.
+/// Спрайты с картой нормалей освещаются с той стороны, где источник,
+/// а земля из Lighting::occluders отбрасывает тени.
+/// ```ignore
+/// let mut scene = post.target();
+/// sprite_manager.draw_sprites(&sprites, &mut scene);
+/// lights.render(&programs, &sprite_manager, &lighting, &sprites, &mut scene);
+/// ```
+pub struct LightRenderer {
+    buffers: Buffers,
+    /// Квадрат на весь экран для последнего прохода
+    vertices: VertexBuffer<Vertex>,
+    indices: IndexBuffer<u16>,
+}
+
+impl LightRenderer {
+    /// Собирает программы проходов в programs и создаёт текстуры размера окна
+    pub fn new<F: Facade>(display: &F, programs: &mut ProgramManager)
+        -> Result<Self, LightingError>
+    {
+        shaders::register(programs, display)?;
+        let size = display.get_context().get_framebuffer_dimensions();
+        let vb_data = [
+            Vertex { position: [-1.0, 1.0], tex_coords: [0.0, 1.0], color: WHITE },
+            Vertex { position: [1.0, 1.0], tex_coords: [1.0, 1.0], color: WHITE },
+            Vertex { position: [-1.0, -1.0], tex_coords: [0.0, 0.0], color: WHITE },
+            Vertex { position: [1.0, -1.0], tex_coords: [1.0, 0.0], color: WHITE },
+        ];
+        Ok(LightRenderer {
+            buffers: Buffers::new(display, size)?,
+            vertices: VertexBuffer::new(display, &vb_data).unwrap(),
+            indices: IndexBuffer::new(display, PrimitiveType::TrianglesList, &[0u16, 1, 2, 1, 3, 2])
+                .unwrap(),
+        })
+    }
+    /// Пересоздаёт текстуры, если размер окна поменялся
+    pub fn resize<F: Facade>(&mut self, display: &F, size: (u32, u32))
+        -> Result<(), TextureCreationError>
+    {
+        if self.buffers.size != size {
+            self.buffers = Buffers::new(display, size)?;
+        }
+        Ok(())
+    }
+    /// Освещает то, что уже нарисовано в target. target должен быть размера окна,
+    /// как кадр или поверхность PostProcessor::target. Если программ освещения
+    /// нет в programs, ничего не рисуется
+    pub fn render<S: Surface>(&self, programs: &ProgramManager, manager: &SpriteManager,
+                              lighting: &Lighting, sprites: &SpriteGroup, target: &mut S) {
+        let (Some(normals_program), Some(occluder_program), Some(light_program),
+             Some(composite_program)) = (programs.get(NORMALS_PROGRAM),
+                                         programs.get(OCCLUDER_PROGRAM),
+                                         programs.get(LIGHT_PROGRAM),
+                                         programs.get(COMPOSITE_PROGRAM)) else {
+            return;
+        };
+        let display = manager.display();
+        let projection = manager.projection();
+        let game_area = manager.viewport().gl_rect();
+        let inside = DrawParameters { viewport: game_area, ..Default::default() };
+
+        let mut normals = self.buffers.normals.as_surface();
+        normals.clear_color(0.0, 0.0, 0.0, 0.0);
+        let mut lit: Vec<&Sprite> = sprites.get_elements().iter().flatten()
+            .filter(|s| !s.is_hidden())
+            .collect();
+        lit.sort_by_key(|s| s.get_layer());
+        for sprite in lit {
+            let Some(normal_map) = sprite.normal_map() else { continue };
+            let tex_coords = frame_tex_coords(sprite.get_frames(), sprite.current_frame());
+            let uniforms = uniform! {
+                projection: projection,
+                tex: sprite.get_texture(),
+                normal_map: normal_map.as_ref(),
+            };
+            let vb_data = quad_vertices(sprite.get_rect(), tex_coords, WHITE);
+            draw_quads(display, &vb_data, normals_program, &uniforms, &inside, &mut normals);
+        }
+
+        let mut occluders = self.buffers.occluders.as_surface();
+        occluders.clear_color(0.0, 0.0, 0.0, 0.0);
+        let vb_data: Vec<Vertex> = lighting.occluders.iter()
+            .flat_map(|rect| quad_vertices(rect, [0.0, 0.0, 1.0, 1.0], WHITE))
+            .collect();
+        let uniforms = uniform! { projection: projection };
+        draw_quads(display, &vb_data, occluder_program, &uniforms, &inside, &mut occluders);
+
+        let [r, g, b] = lighting.ambient;
+        let mut light_map = self.buffers.light_map.as_surface();
+        light_map.clear_color(r, g, b, 1.0);
+        let additive = DrawParameters {
+            viewport: game_area,
+            blend: Blend {
+                color: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::One,
+                    destination: LinearBlendingFactor::One,
+                },
+                alpha: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::One,
+                    destination: LinearBlendingFactor::One,
+                },
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            },
+            ..Default::default()
+        };
+        let (width, height) = self.buffers.size;
+        let viewport = match game_area {
+            Some(area) => [area.left as f32, area.bottom as f32, area.width as f32,
+                           area.height as f32],
+            None => [0.0, 0.0, width as f32, height as f32],
+        };
+        let clamp = SamplerWrapFunction::Clamp;
+        for light in &lighting.lights {
+            let (spot_direction, spot_cos) = match light.kind {
+                LightKind::Point => ([1.0, 0.0], -1.0),
+                LightKind::Spot { direction, angle } =>
+                    ([direction.cos() as f32, direction.sin() as f32], angle.cos() as f32),
+            };
+            let uniforms = uniform! {
+                projection: projection,
+                normals: self.buffers.normals.sampled().wrap_function(clamp),
+                occluders: self.buffers.occluders.sampled().wrap_function(clamp),
+                resolution: [width as f32, height as f32],
+                viewport: viewport,
+                center: [light.position.x as f32, light.position.y as f32],
+                light_color: light.color,
+                intensity: light.intensity,
+                falloff: light.falloff,
+                height: light.height,
+                shadows: if light.shadows { 1.0f32 } else { 0.0 },
+                spot_direction: spot_direction,
+                spot_cos: spot_cos,
+            };
+            let vb_data = quad_vertices(&light.bounds(), [0.0, 0.0, 1.0, 1.0], WHITE);
+            draw_quads(display, &vb_data, light_program, &uniforms, &additive, &mut light_map);
+        }
+
+        // Цвет сцены умножается на свет, прозрачность сцены остаётся как была
+        let multiply = DrawParameters {
+            blend: Blend {
+                color: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::DestinationColor,
+                    destination: LinearBlendingFactor::Zero,
+                },
+                alpha: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::Zero,
+                    destination: LinearBlendingFactor::One,
+                },
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            },
+            ..Default::default()
+        };
+        let identity: [[f32; 4]; 4] = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let uniforms = uniform! {
+            projection: identity,
+            light_map: self.buffers.light_map.sampled().wrap_function(clamp),
+        };
+        target.draw(&self.vertices, &self.indices, composite_program, &uniforms, &multiply)
+            .unwrap();
+    }
+}
+
+/// Рисует квадраты, каждые четыре вершины это один квадрат
+fn draw_quads<F: Facade, U: Uniforms, S: Surface>(display: &F, vb_data: &[Vertex],
+                                                  program: &glium::Program, uniforms: &U,
+                                                  parameters: &DrawParameters, target: &mut S) {
+    if vb_data.is_empty() {
+        return;
+    }
+    let ib_data: Vec<u32> = (0..vb_data.len() as u32 / 4)
+        .flat_map(|q| [0, 1, 2, 1, 3, 2].map(|i| q * 4 + i))
+        .collect();
+    let vertices = VertexBuffer::new(display, vb_data).unwrap();
+    let indices = IndexBuffer::new(display, PrimitiveType::TrianglesList, &ib_data).unwrap();
+    target.draw(&vertices, &indices, program, uniforms, parameters).unwrap();
+}
@@ -0,0 +1,128 @@
+use glium::backend::Facade;
+use crate::programs::{ProgramError, ProgramManager};
+
+pub const NORMALS_PROGRAM: &str = "light_normals";
+pub const OCCLUDER_PROGRAM: &str = "light_occluder";
+pub const LIGHT_PROGRAM: &str = "light_source";
+pub const COMPOSITE_PROGRAM: &str = "light_composite";
+
+/// Собирает программы проходов освещения в ProgramManager
+pub fn register<F>(programs: &mut ProgramManager, display: &F) -> Result<(), ProgramError>
+where F: Facade
+{
+    for (name, fragment) in [
+        (NORMALS_PROGRAM, NORMALS_SHADER),
+        (OCCLUDER_PROGRAM, OCCLUDER_SHADER),
+        (LIGHT_PROGRAM, LIGHT_SHADER),
+        (COMPOSITE_PROGRAM, COMPOSITE_SHADER),
+    ] {
+        programs.compile(display, name, VERTEX_SHADER, fragment)?;
+    }
+    Ok(())
+}
+
+/// Как у спрайтов, только без цвета вершины
+const VERTEX_SHADER: &str = r#"
+    #version 140
+    in vec2 position;
+    in vec2 tex_coords;
+    uniform mat4 projection;
+    out vec2 v_tex_coords;
+    void main() {
+        v_tex_coords = tex_coords;
+        gl_Position = projection * vec4(position, 0.0, 1.0);
+    }
+"#;
+
+const NORMALS_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform sampler2D normal_map;
+    void main() {
+        // Transparent pixels keep the normals of whatever is below the sprite.
+        if (texture(tex, v_tex_coords).a < 0.5) {
+            discard;
+        }
+        // Alpha marks pixels that have a normal at all.
+        color = vec4(texture(normal_map, v_tex_coords).rgb, 1.0);
+    }
+"#;
+
+const OCCLUDER_SHADER: &str = r#"
+    #version 140
+    out vec4 color;
+    void main() {
+        color = vec4(1.0);
+    }
+"#;
+
+const LIGHT_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform mat4 projection;
+    uniform sampler2D normals;
+    uniform sampler2D occluders;
+    // Size of the light map and the letterboxed game area inside it, in pixels.
+    uniform vec2 resolution;
+    uniform vec4 viewport;
+    uniform vec2 center;
+    uniform vec3 light_color;
+    uniform float intensity;
+    uniform float falloff;
+    uniform float height;
+    uniform float shadows;
+    // Direction of a spot light in the world, and the cosine of half its cone.
+    // Point lights have spot_cos = -1.
+    uniform vec2 spot_direction;
+    uniform float spot_cos;
+    const int SHADOW_STEPS = 48;
+    vec2 to_window(vec2 world) {
+        vec4 clip = projection * vec4(world, 0.0, 1.0);
+        return viewport.xy + (clip.xy * 0.5 + 0.5) * viewport.zw;
+    }
+    void main() {
+        // Position inside the light square from -1 to 1, y goes down like in the world.
+        vec2 local = vec2(v_tex_coords.x * 2.0 - 1.0, 1.0 - v_tex_coords.y * 2.0);
+        float distance = length(local);
+        if (distance >= 1.0) {
+            discard;
+        }
+        float light = pow(1.0 - distance, falloff) * intensity;
+        if (spot_cos > -1.0 && distance > 0.0) {
+            float cosine = dot(local / distance, spot_direction);
+            light *= smoothstep(spot_cos, mix(spot_cos, 1.0, 0.2), cosine);
+        }
+        vec2 fragment = gl_FragCoord.xy;
+        vec2 source = to_window(center);
+        vec4 normal = texture(normals, fragment / resolution);
+        if (normal.a > 0.5) {
+            vec3 n = normalize(normal.rgb * 2.0 - 1.0);
+            vec3 to_light = normalize(vec3(source - fragment, height));
+            light *= max(dot(n, to_light), 0.0);
+        }
+        // Terrain itself is lit, only what is behind it from the light is in shadow.
+        if (shadows > 0.5 && texture(occluders, fragment / resolution).a < 0.5) {
+            for (int i = 1; i < SHADOW_STEPS; i++) {
+                vec2 point = mix(fragment, source, float(i) / float(SHADOW_STEPS));
+                if (texture(occluders, point / resolution).a > 0.5) {
+                    light = 0.0;
+                    break;
+                }
+            }
+        }
+        color = vec4(light_color * light, 1.0);
+    }
+"#;
+
+const COMPOSITE_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D light_map;
+    void main() {
+        color = vec4(texture(light_map, v_tex_coords).rgb, 1.0);
+    }
+"#;
@@ -0,0 +1,145 @@
+use std::rc::Rc;
+use glium::texture::SrgbTexture2d;
+use glium::uniforms::{UniformValue, Uniforms};
+use crate::programs::{DISSOLVE_PROGRAM, FLASH_PROGRAM, OUTLINE_PROGRAM};
+
+/// Значение uniform-параметра материала
+#[derive(Debug, Clone)]
+pub enum MaterialValue {
+    Float(f32),
+    Vec2([f32; 2]),
+    /// Цвет или любые четыре числа
+    Vec4([f32; 4]),
+    /// Дополнительная текстура, например шум или карта нормалей
+    Texture(Rc<SrgbTexture2d>),
+}
+
+impl MaterialValue {
+    fn as_uniform(&self) -> UniformValue<'_> {
+        match self {
+            MaterialValue::Float(v) => UniformValue::Float(*v),
+            MaterialValue::Vec2(v) => UniformValue::Vec2(*v),
+            MaterialValue::Vec4(v) => UniformValue::Vec4(*v),
+            MaterialValue::Texture(t) => UniformValue::SrgbTexture2d(t, None),
+        }
+    }
+}
+
+/// Материал это имя программы из ProgramManager и её параметры.
+/// Кроме параметров материала программа всегда получает projection, tex, tint
+/// и time, время в секундах из SpriteManager. Параметры, которых нет в программе,
+/// не используются. Если параметр не того типа, что в шейдере, программа не рисует,
+/// и SpriteManager рисует такие спрайты программой по умолчанию
+#[derive(Debug, Clone)]
+pub struct Material {
+    program: String,
+    uniforms: Vec<(String, MaterialValue)>,
+}
+
+impl Material {
+    pub fn new(program: &str) -> Self {
+        Material { program: program.to_string(), uniforms: Vec::new() }
+    }
+    /// Спрайт залит цветом color на долю amount от 0 до 1
+    pub fn flash(color: [f32; 4], amount: f32) -> Self {
+        Material::new(FLASH_PROGRAM)
+            .with_color("effect_color", color)
+            .with_float("amount", amount)
+    }
+    /// Обводка цвета color толщиной thickness пикселей текстуры
+    pub fn outline(color: [f32; 4], thickness: f32) -> Self {
+        Material::new(OUTLINE_PROGRAM)
+            .with_color("effect_color", color)
+            .with_float("thickness", thickness)
+    }
+    /// Спрайт рассыпался на долю amount от 0 до 1, край светится цветом edge_color
+    pub fn dissolve(amount: f32, edge_color: [f32; 4]) -> Self {
+        Material::new(DISSOLVE_PROGRAM)
+            .with_color("effect_color", edge_color)
+            .with_float("amount", amount)
+    }
+    /// Имя программы, которой рисуется материал
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+    pub fn get(&self, name: &str) -> Option<&MaterialValue> {
+        self.uniforms.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+    /// Задаёт параметр. Старое значение параметра с тем же именем заменяется
+    pub fn set(&mut self, name: &str, value: MaterialValue) {
+        match self.uniforms.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value,
+            None => self.uniforms.push((name.to_string(), value)),
+        }
+    }
+    pub fn set_float(&mut self, name: &str, value: f32) {
+        self.set(name, MaterialValue::Float(value));
+    }
+    pub fn with_float(mut self, name: &str, value: f32) -> Self {
+        self.set_float(name, value);
+        self
+    }
+    pub fn with_vec2(mut self, name: &str, value: [f32; 2]) -> Self {
+        self.set(name, MaterialValue::Vec2(value));
+        self
+    }
+    pub fn with_color(mut self, name: &str, color: [f32; 4]) -> Self {
+        self.set(name, MaterialValue::Vec4(color));
+        self
+    }
+    pub fn with_texture(mut self, name: &str, texture: Rc<SrgbTexture2d>) -> Self {
+        self.set(name, MaterialValue::Texture(texture));
+        self
+    }
+}
+
+/// Вспышка спрайта: он заливается цветом, который за duration миллисекунд сходит на нет
+#[derive(Debug, Clone, Copy)]
+pub struct Flash {
+    pub color: [f32; 4],
+    pub duration: u32,
+    left: u32,
+}
+
+impl Flash {
+    pub fn new(color: [f32; 4], duration: u32) -> Self {
+        Flash { color, duration, left: duration }
+    }
+    /// Вспышка белым, как при попадании
+    pub fn white(duration: u32) -> Self {
+        Flash::new([1.0, 1.0, 1.0, 1.0], duration)
+    }
+    /// Уменьшает оставшееся время. Возвращает false, когда вспышка закончилась
+    pub fn update(&mut self, dt: u32) -> bool {
+        self.left = self.left.saturating_sub(dt);
+        self.left > 0
+    }
+    /// Насколько сильно спрайт залит цветом сейчас, от 0 до 1
+    pub fn amount(&self) -> f32 {
+        self.left as f32 / self.duration.max(1) as f32
+    }
+    pub fn material(&self) -> Material {
+        Material::flash(self.color, self.amount())
+    }
+}
+
+/// Все uniform-параметры для рисования материалом
+pub(crate) struct MaterialUniforms<'m> {
+    pub projection: [[f32; 4]; 4],
+    pub texture: &'m SrgbTexture2d,
+    pub tint: [f32; 4],
+    pub time: f32,
+    pub material: &'m Material,
+}
+
+impl Uniforms for MaterialUniforms<'_> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut output: F) {
+        output("projection", UniformValue::Mat4(self.projection));
+        output("tex", UniformValue::SrgbTexture2d(self.texture, None));
+        output("tint", UniformValue::Vec4(self.tint));
+        output("time", UniformValue::Float(self.time));
+        for (name, value) in &self.material.uniforms {
+            output(name, value.as_uniform());
+        }
+    }
+}
@@ -1,20 +1,39 @@
 use glium::implement_vertex;
 
 pub use sprite::sprite_obj::Sprite;
+pub use viewport::{ScalePolicy, Viewport};
+pub use camera::Camera;
+pub use background::{ParallaxBackground, ParallaxLayer, VerticalAnchor};
+pub use material::{Flash, Material, MaterialValue};
 
+pub mod background;
+pub mod camera;
+pub mod capture;
+pub mod debug;
 pub mod gui;
+pub mod lighting;
+pub mod material;
+pub mod particles;
+pub mod postprocess;
+pub mod render;
 pub mod sprite;
+pub mod text;
 pub mod traits;
+pub mod viewport;
 
 #[derive(Clone, Copy)]
-/// Вершина которая описывается двумя значениями x и y
+/// Вершина которая описывается двумя значениями x и y,
+/// а также координатами текстуры, которая в этой вершине рисуется,
+/// и цветом, на который текстура умножается
 pub struct Vertex {
     // The fields in Vertex are usually there
     // to be passed into the shader file.
     pub position: [f64; 2],
+    pub tex_coords: [f32; 2],
+    pub color: [f32; 4],
 }
 
 // This line implements the Vertex using a macro inside glium.
 // Don't forget to include all of the fields as parameters otherwise
 // glium won't pass those into the shader.
-implement_vertex!(Vertex, position);
+implement_vertex!(Vertex, position, tex_coords, color);
@@ -0,0 +1,83 @@
+use cgmath::Point2;
+use crate::messaging::queue::MessageQueue;
+use crate::component::Component;
+use crate::component::messages::ComponentMessage;
+use crate::graphics::Sprite;
+use crate::graphics::particles::emitter::{ParticleEmitter, ParticleSettings};
+use crate::graphics::particles::messages::ParticleMessage;
+use crate::messaging::traits::Messaging;
+use crate::misc_traits::update::Updatable;
+use crate::rect::Rectangular;
+
+/// Компонент, который выпускает частицы из центра своего спрайта,
+/// например дымный след за ракетой. Частицы рисует SpriteManager::draw вместе со спрайтом.
+#[derive(Debug)]
+pub struct ParticleComponent {
+    time_elapsed: u32,
+    // Излучатель большой (в нём генератор случайных чисел), поэтому лежит в куче
+    emitter: Box<ParticleEmitter>,
+    input_messages: MessageQueue<ComponentMessage>,
+    output_messages: MessageQueue<ComponentMessage>,
+}
+
+impl ParticleComponent {
+    /// Имя типа компонента
+    pub const KIND: &'static str = "particles";
+    pub fn new(settings: ParticleSettings) -> Component {
+        ParticleComponent::from_emitter(ParticleEmitter::new(settings))
+    }
+    /// Компонент с уже настроенным излучателем
+    pub fn from_emitter(emitter: ParticleEmitter) -> Component {
+        Component::ParticleComponent(ParticleComponent {
+            time_elapsed: 0,
+            emitter: Box::new(emitter),
+            input_messages: MessageQueue::new(),
+            output_messages: MessageQueue::new(),
+        })
+    }
+    pub fn emitter(&self) -> &ParticleEmitter {
+        &self.emitter
+    }
+    pub fn emitter_mut(&mut self) -> &mut ParticleEmitter {
+        &mut self.emitter
+    }
+}
+
+impl Updatable for ParticleComponent {
+    type Host = Sprite;
+    fn get_time_elapsed(&self) -> u32 {
+        self.time_elapsed
+    }
+    fn get_time_elapsed_mut(&mut self) -> &mut u32 {
+        &mut self.time_elapsed
+    }
+    fn get_delay(&self) -> u32 {
+        1
+    }
+    fn updated_internal(mut self, mult: u32, host: &mut Self::Host) -> Self {
+        let center = Point2::new(host.left() + host.width() / 2.0,
+                                 host.bottom() - host.height() / 2.0);
+        self.emitter.set_position(center);
+        self.emitter.update(mult * self.get_delay());
+        self
+    }
+}
+
+impl Messaging for ParticleComponent {
+    type Message = ComponentMessage;
+    fn input_messages(&mut self) -> &mut MessageQueue<Self::Message> {
+        &mut self.input_messages
+    }
+    fn output_messages(&mut self) -> &mut MessageQueue<Self::Message> {
+        &mut self.output_messages
+    }
+    fn handle_message(&mut self, message: Self::Message) {
+        if let ComponentMessage::ParticleMessage(m) = message {
+            match m {
+                ParticleMessage::Burst(count) => self.emitter.burst(count),
+                ParticleMessage::SetEmitting(emitting) => self.emitter.set_emitting(emitting),
+                ParticleMessage::Wind(wind) => self.emitter.set_wind(wind),
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+/// Что-то, что можно плавно смешивать: числа, цвета
+pub trait Lerp: Copy {
+    /// Значение между self (t = 0) и other (t = 1)
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for [f32; 4] {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        [0, 1, 2, 3].map(|i| self[i].lerp(other[i], t))
+    }
+}
+
+/// Изменение значения за время жизни частицы.
+/// Точки это пары (доля жизни от 0 до 1, значение), между ними значение меняется линейно.
+#[derive(Debug, Clone)]
+pub struct Curve<T: Lerp> {
+    points: Vec<(f32, T)>,
+}
+
+impl<T: Lerp> Curve<T> {
+    /// Значение, которое не меняется
+    pub fn constant(value: T) -> Self {
+        Curve {points: vec![(0.0, value)]}
+    }
+    /// Значение, которое меняется от start до end
+    pub fn linear(start: T, end: T) -> Self {
+        Curve {points: vec![(0.0, start), (1.0, end)]}
+    }
+    /// Добавляет точку. Точки могут добавляться в любом порядке
+    pub fn with_point(mut self, t: f32, value: T) -> Self {
+        self.points.push((t.clamp(0.0, 1.0), value));
+        self.points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self
+    }
+    /// Значение в момент t (доля жизни от 0 до 1)
+    pub fn sample(&self, t: f32) -> T {
+        let first = self.points[0];
+        if t <= first.0 {
+            return first.1;
+        }
+        for pair in self.points.windows(2) {
+            let ((t0, a), (t1, b)) = (pair[0], pair[1]);
+            if t <= t1 {
+                let k = if t1 > t0 {(t - t0) / (t1 - t0)} else {1.0};
+                return a.lerp(b, k);
+            }
+        }
+        self.points[self.points.len() - 1].1
+    }
+}
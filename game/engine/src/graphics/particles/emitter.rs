@@ -0,0 +1,204 @@
+use cgmath::{Point2, Vector2};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::graphics::particles::curve::Curve;
+
+/// Настройки излучателя частиц. Расстояния в пикселях, время в миллисекундах,
+/// скорости в пикселях в секунду, углы в радианах. Ось y направлена вниз,
+/// поэтому угол -PI/2 это вверх.
+#[derive(Debug, Clone)]
+pub struct ParticleSettings {
+    /// Сколько частиц появляется за секунду, пока излучатель включён
+    pub spawn_rate: f64,
+    /// Наименьшее и наибольшее время жизни частицы
+    pub lifetime: (u32, u32),
+    /// Наименьшая и наибольшая начальная скорость
+    pub speed: (f64, f64),
+    /// Направление, в котором вылетают частицы
+    pub direction: f64,
+    /// Ширина конуса вылета: частицы летят в пределах direction ± spread / 2
+    pub spread: f64,
+    /// Ускорение свободного падения для частиц (дым можно сделать отрицательным)
+    pub gravity: f64,
+    /// Насколько сильно ветер сносит частицы, 0 - совсем не сносит
+    pub wind_influence: f64,
+    /// Замедление частиц, доля скорости, теряемая за секунду
+    pub drag: f64,
+    /// Размер частицы за время жизни
+    pub size: Curve<f32>,
+    /// Цвет частицы за время жизни
+    pub color: Curve<[f32; 4]>,
+    /// Прозрачность частицы за время жизни, умножается на альфу цвета
+    pub alpha: Curve<f32>,
+    /// Больше этого числа частиц у излучателя не бывает
+    pub max_particles: usize,
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        ParticleSettings {
+            spawn_rate: 30.0,
+            lifetime: (500, 1000),
+            speed: (20.0, 60.0),
+            direction: -std::f64::consts::FRAC_PI_2,
+            spread: std::f64::consts::FRAC_PI_4,
+            gravity: 0.0,
+            wind_influence: 1.0,
+            drag: 0.0,
+            size: Curve::linear(6.0, 12.0),
+            color: Curve::constant([1.0, 1.0, 1.0, 1.0]),
+            alpha: Curve::linear(1.0, 0.0),
+            max_particles: 500,
+        }
+    }
+}
+
+/// Одна частица. Позиция это центр частицы в координатах мира
+#[derive(Debug, Clone, Copy)]
+pub struct Particle {
+    pub position: Point2<f64>,
+    pub velocity: Vector2<f64>,
+    pub age: u32,
+    pub lifetime: u32,
+}
+
+impl Particle {
+    /// Доля прожитой жизни от 0 до 1
+    pub fn progress(&self) -> f32 {
+        if self.lifetime == 0 {
+            return 1.0;
+        }
+        (self.age as f32 / self.lifetime as f32).min(1.0)
+    }
+}
+
+/// Излучатель частиц. Частицы живут в координатах мира, поэтому если излучатель
+/// двигается, за ним остаётся след.
+#[derive(Debug)]
+pub struct ParticleEmitter {
+    settings: ParticleSettings,
+    particles: Vec<Particle>,
+    position: Point2<f64>,
+    emitting: bool,
+    /// Сколько частиц "накопилось" с прошлого кадра, но ещё не появилось
+    spawn_accumulator: f64,
+    wind: f64,
+    rng: StdRng,
+}
+
+impl ParticleEmitter {
+    pub fn new(settings: ParticleSettings) -> Self {
+        ParticleEmitter {
+            settings,
+            particles: Vec::new(),
+            position: Point2::new(0.0, 0.0),
+            emitting: true,
+            spawn_accumulator: 0.0,
+            wind: 0.0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+    /// Излучатель с заданным seed, чтобы частицы получались одинаковыми
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+    /// Излучатель, который сам частицы не выпускает, только через burst
+    pub fn with_emitting(mut self, emitting: bool) -> Self {
+        self.emitting = emitting;
+        self
+    }
+    pub fn settings(&self) -> &ParticleSettings {
+        &self.settings
+    }
+    pub fn settings_mut(&mut self) -> &mut ParticleSettings {
+        &mut self.settings
+    }
+    pub fn particles(&self) -> &Vec<Particle> {
+        &self.particles
+    }
+    pub fn position(&self) -> Point2<f64> {
+        self.position
+    }
+    /// Двигает точку, из которой вылетают частицы
+    pub fn set_position(&mut self, position: Point2<f64>) {
+        self.position = position;
+    }
+    pub fn is_emitting(&self) -> bool {
+        self.emitting
+    }
+    /// Включает или выключает постоянное появление частиц
+    pub fn set_emitting(&mut self, emitting: bool) {
+        self.emitting = emitting;
+    }
+    /// Скорость ветра, пиксели в секунду за секунду по x
+    pub fn set_wind(&mut self, wind: f64) {
+        self.wind = wind;
+    }
+    /// Выключен и все частицы уже исчезли
+    pub fn is_finished(&self) -> bool {
+        !self.emitting && self.particles.is_empty()
+    }
+    /// Выпускает сразу count частиц, например при взрыве
+    pub fn burst(&mut self, count: usize) {
+        for _ in 0..count {
+            self.spawn();
+        }
+    }
+    /// Обновляет частицы, dt в миллисекундах
+    pub fn update(&mut self, dt: u32) {
+        let seconds = dt as f64 / 1000.0;
+        let acceleration = Vector2::new(self.wind * self.settings.wind_influence,
+                                        self.settings.gravity);
+        let drag = (1.0 - self.settings.drag * seconds).max(0.0);
+        for p in self.particles.iter_mut() {
+            p.age += dt;
+            p.velocity = (p.velocity + acceleration * seconds) * drag;
+            p.position += p.velocity * seconds;
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+        if self.emitting {
+            self.spawn_accumulator += self.settings.spawn_rate * seconds;
+            while self.spawn_accumulator >= 1.0 {
+                self.spawn_accumulator -= 1.0;
+                self.spawn();
+            }
+        }
+    }
+    /// Размер и цвет частицы в текущий момент её жизни
+    pub fn appearance(&self, particle: &Particle) -> (f32, [f32; 4]) {
+        let t = particle.progress();
+        let mut color = self.settings.color.sample(t);
+        color[3] *= self.settings.alpha.sample(t);
+        (self.settings.size.sample(t), color)
+    }
+    fn spawn(&mut self) {
+        if self.particles.len() >= self.settings.max_particles {
+            return;
+        }
+        let s = &self.settings;
+        let half_spread = s.spread / 2.0;
+        let angle = s.direction + if half_spread > 0.0 {
+            self.rng.gen_range(-half_spread..=half_spread)
+        } else {
+            0.0
+        };
+        let speed = range(&mut self.rng, s.speed.0, s.speed.1);
+        let (min_life, max_life) = s.lifetime;
+        let lifetime = if max_life > min_life {
+            self.rng.gen_range(min_life..=max_life)
+        } else {
+            min_life
+        };
+        self.particles.push(Particle {
+            position: self.position,
+            velocity: Vector2::new(angle.cos(), angle.sin()) * speed,
+            age: 0,
+            lifetime,
+        });
+    }
+}
+
+fn range(rng: &mut StdRng, min: f64, max: f64) -> f64 {
+    if max > min {rng.gen_range(min..=max)} else {min}
+}
@@ -0,0 +1,10 @@
+/// Сообщения для ParticleComponent
+#[derive(Debug, Clone, Copy)]
+pub enum ParticleMessage {
+    /// Выпустить сразу столько частиц
+    Burst(usize),
+    /// Включить или выключить постоянное появление частиц
+    SetEmitting(bool),
+    /// Поменять ветер, который сносит частицы
+    Wind(f64),
+}
@@ -0,0 +1,9 @@
+mod component;
+pub mod curve;
+pub mod emitter;
+pub mod messages;
+
+pub use component::ParticleComponent;
+pub use curve::Curve;
+pub use emitter::{Particle, ParticleEmitter, ParticleSettings};
+pub use messages::ParticleMessage;
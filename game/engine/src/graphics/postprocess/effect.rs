@@ -0,0 +1,177 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use crate::parse::{self, key_value, parse_number, parse_rgba, syntax, Line};
+
+/// Ошибка чтения настроек постобработки
+#[derive(Debug)]
+pub enum PostError {
+    /// Файл не получилось прочитать
+    Io(std::io::Error),
+    /// Ошибка в самом файле, line это номер строки начиная с 1
+    Syntax { line: usize, reason: String },
+}
+
+impl fmt::Display for PostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PostError::Io(e) => write!(f, "не удалось прочитать настройки постобработки: {e}"),
+            PostError::Syntax { line, reason } => write!(f, "строка {line}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for PostError {}
+
+impl From<std::io::Error> for PostError {
+    fn from(e: std::io::Error) -> Self {
+        PostError::Io(e)
+    }
+}
+
+impl From<(usize, String)> for PostError {
+    fn from((line, reason): (usize, String)) -> Self {
+        PostError::Syntax { line, reason }
+    }
+}
+
+/// Один проход постобработки по всему экрану
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostEffect {
+    /// brightness прибавляется к цвету, contrast растягивает его от серого,
+    /// saturation 0 делает картинку чёрно-белой, а tint умножается на цвет
+    ColorGrading { brightness: f32, contrast: f32, saturation: f32, tint: [f32; 4] },
+    /// Затемнение к краям экрана. Внутри radius (доля половины диагонали)
+    /// экран не темнеет, дальше темнеет до strength
+    Vignette { strength: f32, radius: f32 },
+    /// Свечение ярких мест, например взрывов. Места ярче threshold размываются
+    /// и прибавляются к картинке с силой intensity
+    Bloom { threshold: f32, intensity: f32 },
+    /// Старый телевизор: тёмные полосы строк силой scanlines и выпуклый экран
+    Crt { scanlines: f32, curvature: f32 },
+    /// Экран заливается цветом color на долю amount
+    Fade { color: [f32; 4], amount: f32 },
+}
+
+impl PostEffect {
+    /// Эффект по имени секции с настройками по умолчанию, при которых он почти незаметен
+    pub fn by_name(name: &str) -> Option<PostEffect> {
+        Some(match name {
+            "color_grading" => PostEffect::ColorGrading {
+                brightness: 0.0, contrast: 1.0, saturation: 1.0, tint: [1.0; 4],
+            },
+            "vignette" => PostEffect::Vignette { strength: 0.5, radius: 0.75 },
+            "bloom" => PostEffect::Bloom { threshold: 0.8, intensity: 1.0 },
+            "crt" => PostEffect::Crt { scanlines: 0.25, curvature: 0.05 },
+            "fade" => PostEffect::Fade { color: [0.0, 0.0, 0.0, 1.0], amount: 0.0 },
+            _ => return None,
+        })
+    }
+    /// Разбирает цепочку эффектов. Каждый эффект начинается строкой `[имя]`,
+    /// после неё идут его настройки `ключ = значение`. Эффекты применяются в том
+    /// порядке, в котором записаны, один эффект можно записать несколько раз.
+    /// Строки, начинающиеся с `#`, игнорируются.
+    /// ```text
+    /// [bloom]
+    /// threshold = 0.7
+    /// intensity = 1.5
+    /// [color_grading]
+    /// saturation = 0.8
+    /// tint = 1.0, 0.9, 0.8, 1.0
+    /// [vignette]
+    /// strength = 0.4
+    /// ```
+    pub fn parse_chain(source: &str) -> Result<Vec<PostEffect>, PostError> {
+        let mut effects: Vec<PostEffect> = Vec::new();
+        for line in parse::lines(source) {
+            let (n, line) = match line? {
+                (n, Line::Section(name)) => {
+                    let effect = PostEffect::by_name(name)
+                        .ok_or_else(|| syntax(n, &format!("неизвестный эффект {name}")))?;
+                    effects.push(effect);
+                    continue;
+                }
+                (n, Line::Text(line)) => (n, line),
+            };
+            let effect = effects.last_mut()
+                .ok_or_else(|| syntax(n, "настройка до первого [имя эффекта]"))?;
+            let (key, value) = key_value(line, n)?;
+            effect.set(key, value, n)?;
+        }
+        Ok(effects)
+    }
+    /// Читает цепочку эффектов из файла
+    pub fn load_chain(path: &Path) -> Result<Vec<PostEffect>, PostError> {
+        PostEffect::parse_chain(&fs::read_to_string(path)?)
+    }
+    fn set(&mut self, key: &str, value: &str, line: usize) -> Result<(), PostError> {
+        match (self, key) {
+            (PostEffect::ColorGrading { brightness, .. }, "brightness") =>
+                *brightness = parse_number(value, line)?,
+            (PostEffect::ColorGrading { contrast, .. }, "contrast") =>
+                *contrast = parse_number(value, line)?,
+            (PostEffect::ColorGrading { saturation, .. }, "saturation") =>
+                *saturation = parse_number(value, line)?,
+            (PostEffect::ColorGrading { tint, .. }, "tint") => *tint = parse_rgba(value, line)?,
+            (PostEffect::Vignette { strength, .. }, "strength") =>
+                *strength = parse_number(value, line)?,
+            (PostEffect::Vignette { radius, .. }, "radius") => *radius = parse_number(value, line)?,
+            (PostEffect::Bloom { threshold, .. }, "threshold") =>
+                *threshold = parse_number(value, line)?,
+            (PostEffect::Bloom { intensity, .. }, "intensity") =>
+                *intensity = parse_number(value, line)?,
+            (PostEffect::Crt { scanlines, .. }, "scanlines") =>
+                *scanlines = parse_number(value, line)?,
+            (PostEffect::Crt { curvature, .. }, "curvature") =>
+                *curvature = parse_number(value, line)?,
+            (PostEffect::Fade { color, .. }, "color") => *color = parse_rgba(value, line)?,
+            (PostEffect::Fade { amount, .. }, "amount") => *amount = parse_number(value, line)?,
+            (_, other) => return Err(syntax(line, &format!("неизвестный ключ {other}")).into()),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_keeps_order_and_settings() {
+        let chain = PostEffect::parse_chain("\
+# свечение взрывов
+[bloom]
+threshold = 0.7
+intensity = 1.5
+
+[color_grading]
+saturation = 0.8
+tint = 1.0, 0.9, 0.8
+[vignette]
+[bloom]
+").unwrap();
+        assert_eq!(chain, vec![
+            PostEffect::Bloom { threshold: 0.7, intensity: 1.5 },
+            PostEffect::ColorGrading {
+                brightness: 0.0, contrast: 1.0, saturation: 0.8, tint: [1.0, 0.9, 0.8, 1.0],
+            },
+            PostEffect::by_name("vignette").unwrap(),
+            PostEffect::by_name("bloom").unwrap(),
+        ]);
+        assert!(PostEffect::parse_chain("# пусто\n\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn chain_errors_point_at_their_line() {
+        let line = |source: &str| match PostEffect::parse_chain(source) {
+            Err(PostError::Syntax { line, reason }) => (line, reason),
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(line("[bloom]\n\n[blur]").0, 3);
+        assert_eq!(line("# настройки\nstrength = 1").0, 2);
+        assert_eq!(line("[vignette]\nthreshold = 1"),
+                   (2, "неизвестный ключ threshold".to_string()));
+        assert_eq!(line("[crt]\nscanlines = много").0, 2);
+        assert_eq!(line("[fade]\namount").0, 2);
+    }
+}
@@ -0,0 +1,108 @@
+/// Затемнение экрана для переходов между сценами. Экран плавно заливается цветом
+/// или плавно проявляется из него
+#[derive(Debug, Clone, Copy)]
+pub struct ScreenFade {
+    color: [f32; 4],
+    from: f32,
+    to: f32,
+    duration: u32,
+    elapsed: u32,
+}
+
+impl ScreenFade {
+    /// Экран не затемнён
+    pub fn new() -> Self {
+        ScreenFade { color: [0.0, 0.0, 0.0, 1.0], from: 0.0, to: 0.0, duration: 0, elapsed: 0 }
+    }
+    /// Экран за duration миллисекунд заливается цветом color
+    pub fn fade_out(&mut self, color: [f32; 4], duration: u32) {
+        self.start(color, 1.0, duration);
+    }
+    /// Экран за duration миллисекунд проявляется из того цвета, которым залит
+    pub fn fade_in(&mut self, duration: u32) {
+        self.start(self.color, 0.0, duration);
+    }
+    /// Сразу заливает экран цветом, например перед fade_in при запуске игры
+    pub fn cover(&mut self, color: [f32; 4]) {
+        self.color = color;
+        self.from = 1.0;
+        self.to = 1.0;
+        self.duration = 0;
+        self.elapsed = 0;
+    }
+    pub fn update(&mut self, dt: u32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+    /// Насколько экран залит цветом сейчас, от 0 до 1
+    pub fn amount(&self) -> f32 {
+        if self.duration == 0 {
+            return self.to;
+        }
+        let t = self.elapsed as f32 / self.duration as f32;
+        self.from + (self.to - self.from) * t
+    }
+    pub fn color(&self) -> [f32; 4] {
+        self.color
+    }
+    /// Идёт ли сейчас переход
+    pub fn is_fading(&self) -> bool {
+        self.elapsed < self.duration
+    }
+    /// Переход начинается с того затемнения, которое есть сейчас, поэтому
+    /// прерванный переход не даёт скачка
+    fn start(&mut self, color: [f32; 4], to: f32, duration: u32) {
+        self.from = self.amount();
+        self.color = color;
+        self.to = to;
+        self.duration = duration;
+        self.elapsed = 0;
+    }
+}
+
+impl Default for ScreenFade {
+    fn default() -> Self {
+        ScreenFade::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fade_out_and_in_move_amount_over_time() {
+        let mut fade = ScreenFade::new();
+        assert_eq!(fade.amount(), 0.0);
+        assert!(!fade.is_fading());
+        fade.fade_out([1.0, 0.0, 0.0, 1.0], 100);
+        assert!(fade.is_fading());
+        fade.update(25);
+        assert_eq!(fade.amount(), 0.25);
+        fade.update(1000);
+        assert_eq!(fade.amount(), 1.0);
+        assert!(!fade.is_fading());
+        // Экран проявляется из того же цвета, которым был залит
+        fade.fade_in(200);
+        fade.update(50);
+        assert_eq!(fade.amount(), 0.75);
+        assert_eq!(fade.color(), [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn interrupted_fade_starts_from_current_amount() {
+        let mut fade = ScreenFade::new();
+        fade.cover([0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(fade.amount(), 1.0);
+        assert!(!fade.is_fading());
+        fade.fade_in(100);
+        fade.update(60);
+        assert!((fade.amount() - 0.4).abs() < 1e-6);
+        fade.fade_out([1.0, 1.0, 1.0, 1.0], 100);
+        assert!((fade.amount() - 0.4).abs() < 1e-6);
+        fade.update(50);
+        assert!((fade.amount() - 0.7).abs() < 1e-6);
+        // Переход нулевой длины сразу приходит к концу
+        fade.fade_in(0);
+        assert_eq!(fade.amount(), 0.0);
+    }
+}
@@ -0,0 +1,11 @@
+//! Постобработка кадра: сцена рисуется в текстуру, а потом проходит через цепочку
+//! эффектов на весь экран (цветокоррекция, виньетка, свечение, старый телевизор,
+//! затемнение). Цепочку эффектов для сцены можно прочитать из файла.
+pub mod effect;
+pub mod fade;
+pub mod processor;
+pub mod shaders;
+
+pub use effect::{PostEffect, PostError};
+pub use fade::ScreenFade;
+pub use processor::{PostProcessError, PostProcessor};
@@ -0,0 +1,248 @@
+use std::fmt;
+use glium::backend::Facade;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::index::PrimitiveType;
+use glium::texture::{MipmapsOption, Texture2d, TextureCreationError, UncompressedFloatFormat};
+use glium::uniforms::{SamplerWrapFunction, Uniforms};
+use glium::{uniform, IndexBuffer, Surface, VertexBuffer};
+use crate::graphics::postprocess::effect::PostEffect;
+use crate::graphics::postprocess::fade::ScreenFade;
+use crate::graphics::postprocess::shaders::{self, BLOOM_COMBINE_PROGRAM, BLOOM_EXTRACT_PROGRAM,
+    BLUR_PROGRAM, COLOR_GRADING_PROGRAM, COPY_PROGRAM, CRT_PROGRAM, FADE_PROGRAM,
+    VIGNETTE_PROGRAM};
+use crate::graphics::Vertex;
+use crate::programs::{ProgramError, ProgramManager};
+
+/// Ошибка создания постобработки
+#[derive(Debug)]
+pub enum PostProcessError {
+    /// Программа прохода не собралась
+    Program(ProgramError),
+    /// Видеокарта не смогла создать текстуру для кадра
+    Texture(TextureCreationError),
+}
+
+impl fmt::Display for PostProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PostProcessError::Program(e) => write!(f, "{e}"),
+            PostProcessError::Texture(e) => write!(f, "не удалось создать текстуру кадра: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PostProcessError {}
+
+impl From<ProgramError> for PostProcessError {
+    fn from(e: ProgramError) -> Self {
+        PostProcessError::Program(e)
+    }
+}
+
+impl From<TextureCreationError> for PostProcessError {
+    fn from(e: TextureCreationError) -> Self {
+        PostProcessError::Texture(e)
+    }
+}
+
+/// Текстуры, в которые рисуется кадр и промежуточные проходы
+struct Targets {
+    size: (u32, u32),
+    scene: Texture2d,
+    ping: Texture2d,
+    pong: Texture2d,
+    /// Свечение считается в половинном размере, так быстрее и размытие шире
+    bloom: [Texture2d; 2],
+}
+
+impl Targets {
+    fn new<F: Facade>(display: &F, size: (u32, u32)) -> Result<Self, TextureCreationError> {
+        let (width, height) = (size.0.max(1), size.1.max(1));
+        let texture = |width: u32, height: u32| Texture2d::empty_with_format(
+            display, UncompressedFloatFormat::U8U8U8U8, MipmapsOption::NoMipmap, width, height);
+        let (half_width, half_height) = ((width / 2).max(1), (height / 2).max(1));
+        Ok(Targets {
+            size,
+            scene: texture(width, height)?,
+            ping: texture(width, height)?,
+            pong: texture(width, height)?,
+            bloom: [texture(half_width, half_height)?, texture(half_width, half_height)?],
+        })
+    }
+}
+
+/// Постобработка: сцена рисуется не в окно, а в текстуру, а потом эта текстура
+/// проходит через цепочку эффектов на весь экран и попадает в окно.
+/// Поверх всех эффектов рисуется затемнение для переходов между сценами.
+/// ```ignore
+/// post.resize(&display, display.get_framebuffer_dimensions())?;
+/// let mut scene = post.target();
+/// sprite_manager.draw_sprites(&sprites, &mut scene);
+/// post.apply(&programs, &mut frame);
+/// ```
+pub struct PostProcessor {
+    effects: Vec<PostEffect>,
+    fade: ScreenFade,
+    targets: Targets,
+    vertices: VertexBuffer<Vertex>,
+    indices: IndexBuffer<u16>,
+}
+
+impl PostProcessor {
+    /// Собирает программы проходов в programs и создаёт текстуры размера окна
+    pub fn new<F: Facade>(display: &F, programs: &mut ProgramManager, effects: Vec<PostEffect>)
+        -> Result<Self, PostProcessError>
+    {
+        shaders::register(programs, display)?;
+        let size = display.get_context().get_framebuffer_dimensions();
+        // Квадрат на весь экран, низ текстуры внизу экрана
+        let white = [1.0, 1.0, 1.0, 1.0];
+        let vb_data = [
+            Vertex { position: [-1.0, 1.0], tex_coords: [0.0, 1.0], color: white },
+            Vertex { position: [1.0, 1.0], tex_coords: [1.0, 1.0], color: white },
+            Vertex { position: [-1.0, -1.0], tex_coords: [0.0, 0.0], color: white },
+            Vertex { position: [1.0, -1.0], tex_coords: [1.0, 0.0], color: white },
+        ];
+        Ok(PostProcessor {
+            effects,
+            fade: ScreenFade::new(),
+            targets: Targets::new(display, size)?,
+            vertices: VertexBuffer::new(display, &vb_data).unwrap(),
+            indices: IndexBuffer::new(display, PrimitiveType::TrianglesList, &[0u16, 1, 2, 1, 3, 2])
+                .unwrap(),
+        })
+    }
+    pub fn effects(&self) -> &[PostEffect] {
+        &self.effects
+    }
+    /// Эффекты можно менять на ходу, например при смене сцены
+    pub fn effects_mut(&mut self) -> &mut Vec<PostEffect> {
+        &mut self.effects
+    }
+    pub fn set_effects(&mut self, effects: Vec<PostEffect>) {
+        self.effects = effects;
+    }
+    pub fn fade(&self) -> &ScreenFade {
+        &self.fade
+    }
+    pub fn fade_mut(&mut self) -> &mut ScreenFade {
+        &mut self.fade
+    }
+    /// Двигает затемнение на dt миллисекунд
+    pub fn update(&mut self, dt: u32) {
+        self.fade.update(dt);
+    }
+    /// Пересоздаёт текстуры, если размер окна поменялся
+    pub fn resize<F: Facade>(&mut self, display: &F, size: (u32, u32))
+        -> Result<(), TextureCreationError>
+    {
+        if self.targets.size != size {
+            self.targets = Targets::new(display, size)?;
+        }
+        Ok(())
+    }
+    /// Поверхность, на которой нужно рисовать сцену
+    pub fn target(&self) -> SimpleFrameBuffer<'_> {
+        self.targets.scene.as_surface()
+    }
+    /// Прогоняет нарисованную сцену через эффекты и рисует результат в frame.
+    /// Проходы, программ которых нет в programs или которые не смогли нарисовать,
+    /// пропускаются: следующий эффект получает картинку до пропущенного
+    pub fn apply<S: Surface>(&self, programs: &ProgramManager, frame: &mut S) {
+        let mut passes = self.effects.clone();
+        if self.fade.amount() > 0.0 {
+            passes.push(PostEffect::Fade { color: self.fade.color(), amount: self.fade.amount() });
+        }
+        let buffers = [&self.targets.ping, &self.targets.pong];
+        // Промежуточные проходы рисуют в тот буфер, из которого сейчас не читают
+        let mut next = 0;
+        let mut source = &self.targets.scene;
+        for (i, effect) in passes.iter().enumerate() {
+            if i + 1 == passes.len() {
+                if !self.run(programs, effect, source, frame) {
+                    self.copy(programs, source, frame);
+                }
+                return;
+            }
+            let target = buffers[next];
+            if self.run(programs, effect, source, &mut target.as_surface()) {
+                source = target;
+                next = 1 - next;
+            }
+        }
+        self.copy(programs, source, frame);
+    }
+    /// Один эффект из source в target. Возвращает false, если эффект не нарисовался
+    fn run<S: Surface>(&self, programs: &ProgramManager, effect: &PostEffect, source: &Texture2d,
+                       target: &mut S) -> bool {
+        let tex = source.sampled().wrap_function(SamplerWrapFunction::Clamp);
+        match *effect {
+            PostEffect::ColorGrading { brightness, contrast, saturation, tint } => {
+                let uniforms = uniform! {
+                    tex: tex, brightness: brightness, contrast: contrast,
+                    saturation: saturation, tint: tint,
+                };
+                self.pass(programs, COLOR_GRADING_PROGRAM, &uniforms, target)
+            }
+            PostEffect::Vignette { strength, radius } => {
+                let uniforms = uniform! { tex: tex, strength: strength, radius: radius };
+                self.pass(programs, VIGNETTE_PROGRAM, &uniforms, target)
+            }
+            PostEffect::Bloom { threshold, intensity } => {
+                let [first, second] = &self.targets.bloom;
+                let (width, height) = first.dimensions();
+                let uniforms = uniform! { tex: tex, threshold: threshold };
+                let blur = |from: &Texture2d, to: &Texture2d, direction: [f32; 2]| {
+                    let uniforms = uniform! {
+                        tex: from.sampled().wrap_function(SamplerWrapFunction::Clamp),
+                        direction: direction,
+                    };
+                    self.pass(programs, BLUR_PROGRAM, &uniforms, &mut to.as_surface())
+                };
+                // Без свечения не рисуется и смешивание, иначе к кадру прибавится
+                // свечение прошлого кадра
+                let glow = self.pass(programs, BLOOM_EXTRACT_PROGRAM, &uniforms,
+                                     &mut first.as_surface())
+                    && blur(first, second, [1.0 / width as f32, 0.0])
+                    && blur(second, first, [0.0, 1.0 / height as f32]);
+                if !glow {
+                    return false;
+                }
+                let uniforms = uniform! {
+                    tex: tex,
+                    bloom: first.sampled().wrap_function(SamplerWrapFunction::Clamp),
+                    intensity: intensity,
+                };
+                self.pass(programs, BLOOM_COMBINE_PROGRAM, &uniforms, target)
+            }
+            PostEffect::Crt { scanlines, curvature } => {
+                let (width, height) = self.targets.size;
+                let uniforms = uniform! {
+                    tex: tex, scanlines: scanlines, curvature: curvature,
+                    resolution: [width as f32, height as f32],
+                };
+                self.pass(programs, CRT_PROGRAM, &uniforms, target)
+            }
+            PostEffect::Fade { color, amount } => {
+                let uniforms = uniform! { tex: tex, fade_color: color, amount: amount };
+                self.pass(programs, FADE_PROGRAM, &uniforms, target)
+            }
+        }
+    }
+    /// Рисует source в target без изменений
+    fn copy<S: Surface>(&self, programs: &ProgramManager, source: &Texture2d, target: &mut S) {
+        let uniforms = uniform! { tex: source.sampled() };
+        self.pass(programs, COPY_PROGRAM, &uniforms, target);
+    }
+    /// Рисует квадрат на весь target программой name. Возвращает false,
+    /// если программы нет или она не смогла нарисовать
+    fn pass<S: Surface, U: Uniforms>(&self, programs: &ProgramManager, name: &str, uniforms: &U,
+                                     target: &mut S) -> bool {
+        match programs.get(name) {
+            Some(program) => target
+                .draw(&self.vertices, &self.indices, program, uniforms, &Default::default())
+                .is_ok(),
+            None => false,
+        }
+    }
+}
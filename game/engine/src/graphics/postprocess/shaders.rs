@@ -0,0 +1,167 @@
+use glium::backend::Facade;
+use crate::programs::{ProgramError, ProgramManager};
+
+pub const COPY_PROGRAM: &str = "post_copy";
+pub const COLOR_GRADING_PROGRAM: &str = "post_color_grading";
+pub const VIGNETTE_PROGRAM: &str = "post_vignette";
+pub const BLOOM_EXTRACT_PROGRAM: &str = "post_bloom_extract";
+pub const BLUR_PROGRAM: &str = "post_blur";
+pub const BLOOM_COMBINE_PROGRAM: &str = "post_bloom_combine";
+pub const CRT_PROGRAM: &str = "post_crt";
+pub const FADE_PROGRAM: &str = "post_fade";
+
+/// Собирает программы проходов постобработки в ProgramManager
+pub fn register<F>(programs: &mut ProgramManager, display: &F) -> Result<(), ProgramError>
+where F: Facade
+{
+    for (name, fragment) in [
+        (COPY_PROGRAM, COPY_SHADER),
+        (COLOR_GRADING_PROGRAM, COLOR_GRADING_SHADER),
+        (VIGNETTE_PROGRAM, VIGNETTE_SHADER),
+        (BLOOM_EXTRACT_PROGRAM, BLOOM_EXTRACT_SHADER),
+        (BLUR_PROGRAM, BLUR_SHADER),
+        (BLOOM_COMBINE_PROGRAM, BLOOM_COMBINE_SHADER),
+        (CRT_PROGRAM, CRT_SHADER),
+        (FADE_PROGRAM, FADE_SHADER),
+    ] {
+        programs.compile(display, name, VERTEX_SHADER, fragment)?;
+    }
+    Ok(())
+}
+
+/// Квадрат уже задан в координатах экрана от -1 до 1, поэтому проекции нет
+const VERTEX_SHADER: &str = r#"
+    #version 140
+    in vec2 position;
+    in vec2 tex_coords;
+    out vec2 v_tex_coords;
+    void main() {
+        v_tex_coords = tex_coords;
+        gl_Position = vec4(position, 0.0, 1.0);
+    }
+"#;
+
+const COPY_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D tex;
+    void main() {
+        color = texture(tex, v_tex_coords);
+    }
+"#;
+
+const COLOR_GRADING_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform float brightness;
+    uniform float contrast;
+    uniform float saturation;
+    uniform vec4 tint;
+    void main() {
+        vec4 texel = texture(tex, v_tex_coords);
+        vec3 rgb = (texel.rgb - 0.5) * contrast + 0.5 + brightness;
+        float luma = dot(rgb, vec3(0.299, 0.587, 0.114));
+        rgb = mix(vec3(luma), rgb, saturation) * tint.rgb;
+        color = vec4(clamp(rgb, 0.0, 1.0), texel.a);
+    }
+"#;
+
+const VIGNETTE_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform float strength;
+    uniform float radius;
+    void main() {
+        vec4 texel = texture(tex, v_tex_coords);
+        // Distance from the center, 1.0 in the corners.
+        float distance = length(v_tex_coords - 0.5) / 0.7071;
+        float shade = smoothstep(radius, 1.0, distance) * strength;
+        color = vec4(texel.rgb * (1.0 - shade), texel.a);
+    }
+"#;
+
+const BLOOM_EXTRACT_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform float threshold;
+    void main() {
+        vec4 texel = texture(tex, v_tex_coords);
+        float luma = dot(texel.rgb, vec3(0.299, 0.587, 0.114));
+        color = vec4(texel.rgb * smoothstep(threshold, 1.0, luma), 1.0);
+    }
+"#;
+
+const BLUR_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D tex;
+    // One texel along the blur direction.
+    uniform vec2 direction;
+    void main() {
+        float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+        vec3 sum = texture(tex, v_tex_coords).rgb * weights[0];
+        for (int i = 1; i < 5; i++) {
+            sum += texture(tex, v_tex_coords + direction * float(i)).rgb * weights[i];
+            sum += texture(tex, v_tex_coords - direction * float(i)).rgb * weights[i];
+        }
+        color = vec4(sum, 1.0);
+    }
+"#;
+
+const BLOOM_COMBINE_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform sampler2D bloom;
+    uniform float intensity;
+    void main() {
+        vec4 texel = texture(tex, v_tex_coords);
+        vec3 glow = texture(bloom, v_tex_coords).rgb * intensity;
+        color = vec4(min(texel.rgb + glow, 1.0), texel.a);
+    }
+"#;
+
+const CRT_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform float scanlines;
+    uniform float curvature;
+    uniform vec2 resolution;
+    void main() {
+        // Bends the picture as if it were on a convex screen.
+        vec2 centered = v_tex_coords * 2.0 - 1.0;
+        centered *= 1.0 + curvature * dot(centered.yx, centered.yx);
+        vec2 coords = centered * 0.5 + 0.5;
+        if (coords.x < 0.0 || coords.x > 1.0 || coords.y < 0.0 || coords.y > 1.0) {
+            color = vec4(0.0, 0.0, 0.0, 1.0);
+            return;
+        }
+        vec4 texel = texture(tex, coords);
+        float line = 0.5 + 0.5 * sin(coords.y * resolution.y * 3.14159);
+        color = vec4(texel.rgb * (1.0 - scanlines * line), texel.a);
+    }
+"#;
+
+const FADE_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform vec4 fade_color;
+    uniform float amount;
+    void main() {
+        vec4 texel = texture(tex, v_tex_coords);
+        color = vec4(mix(texel.rgb, fade_color.rgb, amount * fade_color.a), texel.a);
+    }
+"#;
@@ -0,0 +1,34 @@
+use glium::texture::SrgbTexture2d;
+use glium::Surface;
+use crate::graphics::material::Material;
+use crate::graphics::render::Renderer;
+use crate::graphics::sprite::SpriteManager;
+use crate::graphics::Vertex;
+
+/// Рисует через SpriteManager во фрейм, с его камерой, областью вывода и программами
+pub struct FrameRenderer<'f, 'm, S: Surface> {
+    manager: &'f SpriteManager<'m>,
+    frame: &'f mut S,
+}
+
+impl<'f, 'm, S: Surface> FrameRenderer<'f, 'm, S> {
+    pub fn new(manager: &'f SpriteManager<'m>, frame: &'f mut S) -> Self {
+        FrameRenderer { manager, frame }
+    }
+}
+
+impl<S: Surface> Renderer for FrameRenderer<'_, '_, S> {
+    type Texture = SrgbTexture2d;
+    fn clear(&mut self, color: [f32; 4]) {
+        let [r, g, b, a] = color;
+        self.manager.clear(self.frame, r, g, b, a);
+    }
+    fn draw_quads(&mut self, vertices: &[Vertex], texture: &SrgbTexture2d, tint: [f32; 4]) {
+        self.manager.draw_quads(vertices, texture, tint, false, self.manager.projection(),
+                                self.frame);
+    }
+    fn draw_quads_with_material(&mut self, vertices: &[Vertex], texture: &SrgbTexture2d,
+                                material: &Material) {
+        self.manager.draw_quads_with_material(vertices, texture, material, self.frame);
+    }
+}
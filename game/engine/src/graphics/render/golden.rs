@@ -0,0 +1,94 @@
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use image::RgbaImage;
+
+/// Переменная окружения, с которой check_golden перезаписывает эталоны
+pub const UPDATE_GOLDEN_VAR: &str = "UPDATE_GOLDEN";
+
+/// Чем отличаются две картинки одного размера
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDiff {
+    /// Сколько пикселей отличается больше допуска
+    pub different_pixels: usize,
+    /// Самая большая разница одного канала
+    pub max_difference: u8,
+}
+
+/// Ошибка сравнения картинки с эталоном
+#[derive(Debug)]
+pub enum GoldenError {
+    /// Эталона нет. Картинка записана в actual, её можно проверить и переименовать
+    Missing { actual: PathBuf },
+    /// Размер картинки не совпадает с эталоном
+    SizeMismatch { expected: (u32, u32), actual: (u32, u32) },
+    /// Картинка отличается от эталона. Она записана в actual
+    Mismatch { diff: ImageDiff, actual: PathBuf },
+    /// Эталон не получилось прочитать или картинку записать
+    Image(image::ImageError),
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoldenError::Missing { actual } =>
+                write!(f, "эталона нет, картинка записана в {}", actual.display()),
+            GoldenError::SizeMismatch { expected, actual } =>
+                write!(f, "размер {}x{} вместо {}x{}", actual.0, actual.1, expected.0, expected.1),
+            GoldenError::Mismatch { diff, actual } =>
+                write!(f, "отличается {} пикселей, до {} в канале, картинка записана в {}",
+                       diff.different_pixels, diff.max_difference, actual.display()),
+            GoldenError::Image(e) => write!(f, "не удалось прочитать или записать картинку: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {}
+
+impl From<image::ImageError> for GoldenError {
+    fn from(e: image::ImageError) -> Self {
+        GoldenError::Image(e)
+    }
+}
+
+/// Сравнивает картинки одного размера. Пиксель считается другим, если хотя бы
+/// один канал отличается больше чем на tolerance. None, если размеры разные
+pub fn compare(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> Option<ImageDiff> {
+    if expected.dimensions() != actual.dimensions() {
+        return None;
+    }
+    let mut diff = ImageDiff { different_pixels: 0, max_difference: 0 };
+    for (e, a) in expected.pixels().zip(actual.pixels()) {
+        let max = e.0.iter().zip(a.0).map(|(e, a)| e.abs_diff(a)).max().unwrap_or(0);
+        diff.max_difference = diff.max_difference.max(max);
+        if max > tolerance {
+            diff.different_pixels += 1;
+        }
+    }
+    Some(diff)
+}
+
+/// Сравнивает картинку с эталоном из файла PNG. Если картинка отличается или эталона
+/// нет, она записывается рядом с эталоном в файл `имя.actual.png`.
+/// С переменной окружения UPDATE_GOLDEN эталон перезаписывается картинкой
+pub fn check_golden(actual: &RgbaImage, golden: &Path, tolerance: u8) -> Result<(), GoldenError> {
+    if env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        actual.save(golden)?;
+        return Ok(());
+    }
+    let actual_path = golden.with_extension("actual.png");
+    if !golden.is_file() {
+        actual.save(&actual_path)?;
+        return Err(GoldenError::Missing { actual: actual_path });
+    }
+    let expected = image::open(golden)?.to_rgba8();
+    let diff = compare(&expected, actual, tolerance).ok_or(GoldenError::SizeMismatch {
+        expected: expected.dimensions(),
+        actual: actual.dimensions(),
+    })?;
+    if diff.different_pixels > 0 {
+        actual.save(&actual_path)?;
+        return Err(GoldenError::Mismatch { diff, actual: actual_path });
+    }
+    Ok(())
+}
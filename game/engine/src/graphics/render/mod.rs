@@ -0,0 +1,215 @@
+//! Рисование квадратов с текстурами без привязки к конкретному способу рисования.
+//! FrameRenderer рисует через OpenGL в кадр окна, а SoftwareRenderer рисует на
+//! процессоре в картинку, поэтому его можно использовать в тестах без окна.
+//! Квадраты спрайтов для обоих считает sprite_quads, а по слоям их рисует draw_layered.
+pub mod frame;
+pub mod golden;
+pub mod software;
+
+pub use frame::FrameRenderer;
+pub use golden::{check_golden, compare, GoldenError, ImageDiff};
+pub use software::{ImageCache, RenderError, SoftwareRenderer};
+
+use std::borrow::Cow;
+use std::path::Path;
+use cgmath::Point2;
+use glium::glutin::dpi::PhysicalSize;
+use crate::graphics::material::Material;
+use crate::graphics::particles::ParticleEmitter;
+use crate::graphics::text::layout::layout;
+use crate::graphics::text::{Font, Text};
+use crate::graphics::traits::{FrameList, Layered};
+use crate::graphics::Vertex;
+use crate::misc_traits::named::Named;
+use crate::Rect;
+use crate::rect::Rectangular;
+
+/// Белый цвет, при котором текстура рисуется как есть
+pub const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// То, на чём можно рисовать квадраты с текстурами
+pub trait Renderer {
+    /// Текстура, которую понимает этот способ рисования
+    type Texture: ?Sized;
+    /// Заливает всё изображение цветом
+    fn clear(&mut self, color: [f32; 4]);
+    /// Рисует квадраты текстуры. Каждые четыре вершины это один квадрат,
+    /// вершины идут как в quad_vertices. Текстура умножается на цвет вершины и на tint,
+    /// а результат смешивается с тем, что уже нарисовано, по прозрачности
+    fn draw_quads(&mut self, vertices: &[Vertex], texture: &Self::Texture, tint: [f32; 4]);
+    /// Рисует квадраты текстуры материалом. Кто не умеет рисовать материал,
+    /// рисует квадраты как есть
+    fn draw_quads_with_material(&mut self, vertices: &[Vertex], texture: &Self::Texture,
+                                _material: &Material) {
+        self.draw_quads(vertices, texture, WHITE);
+    }
+}
+
+/// То, что рисуется как спрайт: кадр текстуры в своём квадрате на своём слое,
+/// материалом и с частицами под ним. Это Sprite, а тесты без окна могут
+/// рисовать так же свои спрайты, у которых нет текстуры в видеокарте
+pub trait Drawable: Rectangular + Layered + FrameList + Named {
+    fn is_hidden(&self) -> bool;
+    /// Номер кадра анимации, как в frame_tex_coords
+    fn current_frame(&self) -> u32;
+    /// Файл текстуры. По нему SoftwareRenderer находит картинку
+    fn texture_path(&self) -> Option<&Path>;
+    /// Материал, которым спрайт рисуется сейчас. Вспышка важнее материала
+    fn current_material(&self) -> Option<Cow<'_, Material>>;
+    /// Излучатели, частицы которых рисуются под спрайтом
+    fn particle_emitters(&self) -> Vec<&ParticleEmitter>;
+}
+
+/// Откуда берётся текстура квадратов
+pub enum QuadSource<'s, S> {
+    /// Текстура спрайта
+    Sprite(&'s S),
+    /// Белая текстура, квадраты одного цвета
+    White,
+    /// Текстура букв шрифта
+    Font(&'s Font),
+}
+
+impl<S> Clone for QuadSource<'_, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for QuadSource<'_, S> {}
+
+/// Квадраты одной текстуры на одном слое, которые рисуются за раз
+pub struct QuadBatch<'s, S> {
+    pub layer: u32,
+    pub source: QuadSource<'s, S>,
+    pub vertices: Vec<Vertex>,
+    pub tint: [f32; 4],
+    pub material: Option<Cow<'s, Material>>,
+}
+
+/// Квадраты видимых спрайтов: сначала частицы спрайта, потом сам спрайт
+/// текущим кадром и материалом
+pub fn sprite_quads<'s, S, I>(sprites: I) -> Vec<QuadBatch<'s, S>>
+    where S: Drawable + 's,
+          I: IntoIterator<Item = &'s S>
+{
+    let mut batches = Vec::new();
+    for sprite in sprites.into_iter().filter(|s| !s.is_hidden()) {
+        let layer = sprite.get_layer();
+        for emitter in sprite.particle_emitters() {
+            batches.push(QuadBatch {
+                layer,
+                source: QuadSource::White,
+                vertices: particle_vertices(emitter),
+                tint: WHITE,
+                material: None,
+            });
+        }
+        let tex_coords = frame_tex_coords(sprite.get_frames(), sprite.current_frame());
+        batches.push(QuadBatch {
+            layer,
+            source: QuadSource::Sprite(sprite),
+            vertices: quad_vertices(sprite.get_rect(), tex_coords, WHITE).to_vec(),
+            tint: WHITE,
+            material: sprite.current_material(),
+        });
+    }
+    batches
+}
+
+/// Квадраты текста на его слое: сначала обводка, потом сам текст
+pub fn text_quads<'s, S>(text: &Text, font: &'s Font) -> Vec<QuadBatch<'s, S>> {
+    let area = (text.width() as f32, text.height() as f32);
+    let glyphs = layout(font.metrics(), text.content(), area, text.wrap(),
+                        text.align(), text.vertical_align());
+    let origin = (text.left(), text.bottom() - text.height());
+    let quads = |dx: f64, dy: f64| glyphs.iter().flat_map(move |g| {
+        let (width, height) = (g.width as f64, g.height as f64);
+        let rect = Rect::new(
+            Point2::new(origin.0 + g.x as f64 + dx, origin.1 + g.y as f64 + dy + height),
+            PhysicalSize::new(width, height));
+        quad_vertices(&rect, g.tex_coords, WHITE)
+    });
+    let batch = |vertices, tint| QuadBatch {
+        layer: text.get_layer(),
+        source: QuadSource::Font(font),
+        vertices,
+        tint,
+        material: None,
+    };
+    let mut batches = Vec::new();
+    if let Some((color, thickness)) = text.outline() {
+        let t = thickness as f64;
+        let mut vertices = Vec::new();
+        for (dx, dy) in [(-t, -t), (0.0, -t), (t, -t), (-t, 0.0),
+                         (t, 0.0), (-t, t), (0.0, t), (t, t)] {
+            vertices.extend(quads(dx, dy));
+        }
+        batches.push(batch(vertices, color));
+    }
+    batches.push(batch(quads(0.0, 0.0).collect(), text.color()));
+    batches
+}
+
+/// Квадраты всех частиц излучателя. Каждая частица это квадрат её размера и цвета
+/// с центром в её позиции
+pub fn particle_vertices(emitter: &ParticleEmitter) -> Vec<Vertex> {
+    let mut vertices = Vec::with_capacity(emitter.particles().len() * 4);
+    for particle in emitter.particles() {
+        let (size, color) = emitter.appearance(particle);
+        let size = size as f64;
+        let rect = Rect::new(
+            Point2::new(particle.position.x - size / 2.0, particle.position.y + size / 2.0),
+            PhysicalSize::new(size, size));
+        vertices.extend_from_slice(&quad_vertices(&rect, [0.0, 0.0, 1.0, 1.0], color));
+    }
+    vertices
+}
+
+/// Четыре вершины квадрата, текстура которого умножается на color.
+/// tex_coords это (левый, нижний, правый, верхний)
+pub fn quad_vertices(rect: &Rect, tex_coords: [f32; 4], color: [f32; 4]) -> [Vertex; 4] {
+    let [u0, v0, u1, v1] = tex_coords;
+    let (left, right) = (rect.left(), rect.right());
+    let (bottom, top) = (rect.bottom(), rect.bottom() - rect.height());
+    [
+        Vertex { position: [left, top], tex_coords: [u0, v1], color },
+        Vertex { position: [right, top], tex_coords: [u1, v1], color },
+        Vertex { position: [left, bottom], tex_coords: [u0, v0], color },
+        Vertex { position: [right, bottom], tex_coords: [u1, v0], color },
+    ]
+}
+
+/// Координаты текстуры кадра анимации. Картинка делится на frames.0 кадров по
+/// горизонтали и frames.1 по вертикали. Как и у спрайтов, кадры считаются с единицы
+/// слева направо и сверху вниз, кадр 0 это тоже первый кадр.
+/// Номер больше числа кадров идёт по кругу
+pub fn frame_tex_coords(frames: (u32, u32), frame: u32) -> [f32; 4] {
+    let (columns, rows) = (frames.0.max(1), frames.1.max(1));
+    let frame = (frame.max(1) - 1) % (columns * rows);
+    let (column, row) = ((frame % columns) as f32, (frame / columns) as f32);
+    let (width, height) = (1.0 / columns as f32, 1.0 / rows as f32);
+    [column * width, 1.0 - (row + 1.0) * height, (column + 1.0) * width, 1.0 - row * height]
+}
+
+/// Рисует квадраты по слоям: сначала нижние слои, потом верхние.
+/// Квадраты одного слоя рисуются в том порядке, в котором пришли.
+/// texture находит текстуру квадратов, квадраты без текстуры не рисуются
+pub fn draw_layered<'s, 't, R, S, F>(renderer: &mut R, mut batches: Vec<QuadBatch<'s, S>>,
+                                     texture: F)
+    where R: Renderer,
+          R::Texture: 't,
+          F: Fn(QuadSource<'s, S>) -> Option<&'t R::Texture>
+{
+    batches.sort_by_key(|batch| batch.layer);
+    for batch in batches {
+        let texture = match texture(batch.source) {
+            Some(texture) => texture,
+            None => continue,
+        };
+        match &batch.material {
+            Some(material) => renderer.draw_quads_with_material(&batch.vertices, texture, material),
+            None => renderer.draw_quads(&batch.vertices, texture, batch.tint),
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use image::{Rgba, RgbaImage};
+use crate::graphics::material::{Material, MaterialValue};
+use crate::graphics::render::{draw_layered, sprite_quads, Drawable, QuadSource, Renderer,
+                              WHITE};
+use crate::graphics::sprite::SpriteManager;
+use crate::graphics::Vertex;
+use crate::programs::FLASH_PROGRAM;
+
+/// Ошибка рисования спрайтов на процессоре
+#[derive(Debug)]
+pub enum RenderError {
+    /// Неизвестно, из какого файла загружать текстуру спрайта
+    NoTexture(Option<String>),
+    /// Картинку текстуры не получилось загрузить
+    Image(image::ImageError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::NoTexture(name) =>
+                write!(f, "у спрайта {} нет файла текстуры", name.as_deref().unwrap_or("без имени")),
+            RenderError::Image(e) => write!(f, "не удалось загрузить текстуру: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<image::ImageError> for RenderError {
+    fn from(e: image::ImageError) -> Self {
+        RenderError::Image(e)
+    }
+}
+
+/// Картинки текстур по путям к их файлам. Картинку можно положить сюда заранее,
+/// тогда файл не нужен: так тесты рисуют спрайты с текстурами, созданными в коде
+#[derive(Debug, Default)]
+pub struct ImageCache {
+    images: HashMap<PathBuf, RgbaImage>,
+}
+
+impl ImageCache {
+    pub fn new() -> Self {
+        ImageCache::default()
+    }
+    /// Запоминает картинку для указанного пути
+    pub fn insert(&mut self, path: &Path, image: RgbaImage) {
+        self.images.insert(path.to_path_buf(), image);
+    }
+    pub fn get(&self, path: &Path) -> Option<&RgbaImage> {
+        self.images.get(path)
+    }
+    /// Возвращает картинку, загружая её из файла, если её ещё нет
+    pub fn load(&mut self, path: &Path) -> Result<&RgbaImage, RenderError> {
+        if !self.images.contains_key(path) {
+            self.images.insert(path.to_path_buf(), image::open(path)?.to_rgba8());
+        }
+        Ok(&self.images[path])
+    }
+}
+
+/// Рисует на процессоре в картинку так же, как SpriteManager рисует в окно:
+/// по слоям, с кадрами анимации и смешиванием по прозрачности.
+/// Текстуры выбираются без сглаживания, по ближайшему пикселю
+#[derive(Debug, Clone)]
+pub struct SoftwareRenderer {
+    target: RgbaImage,
+    projection: [[f32; 4]; 4],
+}
+
+impl SoftwareRenderer {
+    /// Прозрачная картинка указанного размера. Мир рисуется без сдвига камеры,
+    /// один пиксель мира это один пиксель картинки
+    pub fn new(width: u32, height: u32) -> Self {
+        SoftwareRenderer {
+            target: RgbaImage::new(width, height),
+            projection: SpriteManager::perspective_default(width, height),
+        }
+    }
+    /// Возвращает рисовальщик с другой проекцией, например SpriteManager::projection
+    pub fn with_projection(mut self, projection: [[f32; 4]; 4]) -> Self {
+        self.set_projection(projection);
+        self
+    }
+    pub fn set_projection(&mut self, projection: [[f32; 4]; 4]) {
+        self.projection = projection;
+    }
+    pub fn image(&self) -> &RgbaImage {
+        &self.target
+    }
+    pub fn into_image(self) -> RgbaImage {
+        self.target
+    }
+    /// Копия того, что сейчас нарисовано
+    pub fn screenshot(&self) -> RgbaImage {
+        self.target.clone()
+    }
+    /// Рисует спрайты по слоям так же, как SpriteManager::draw_sprites: с частицами
+    /// и материалами. Из материалов на процессоре умеет только вспышка, остальные
+    /// спрайты рисуются как есть. Текстуры берутся из cache по путям, с которых
+    /// спрайты загружены. Скрытые спрайты не рисуются
+    pub fn draw_sprites<'s, S, I>(&mut self, sprites: I, cache: &mut ImageCache)
+        -> Result<(), RenderError>
+        where S: Drawable + 's,
+              I: IntoIterator<Item = &'s S>
+    {
+        let batches = sprite_quads(sprites);
+        for batch in &batches {
+            if let QuadSource::Sprite(sprite) = batch.source {
+                let path = sprite.texture_path()
+                    .ok_or_else(|| RenderError::NoTexture(sprite.get_name().clone()))?;
+                cache.load(path)?;
+            }
+        }
+        let white = RgbaImage::from_pixel(1, 1, Rgba([255; 4]));
+        draw_layered(self, batches, |source| match source {
+            QuadSource::Sprite(sprite) => cache.get(sprite.texture_path()?),
+            QuadSource::White => Some(&white),
+            // Буквы шрифта есть только в видеокарте
+            QuadSource::Font(_) => None,
+        });
+        Ok(())
+    }
+    /// Точка мира в пикселях картинки
+    fn to_pixels(&self, position: [f64; 2]) -> (f32, f32) {
+        let m = &self.projection;
+        let (x, y) = (position[0] as f32, position[1] as f32);
+        let clip_x = m[0][0] * x + m[1][0] * y + m[3][0];
+        let clip_y = m[0][1] * x + m[1][1] * y + m[3][1];
+        let (width, height) = self.target.dimensions();
+        ((clip_x + 1.0) / 2.0 * width as f32, (1.0 - clip_y) / 2.0 * height as f32)
+    }
+    /// Рисует треугольник. Пиксель рисуется, если его центр внутри треугольника.
+    /// Центр на левой или верхней стороне тоже считается внутри, поэтому на общей
+    /// стороне двух треугольников квадрата пиксели не рисуются дважды
+    fn draw_triangle(&mut self, vertices: [&Vertex; 3], texture: &RgbaImage, tint: [f32; 4],
+                     fill: Option<Fill>) {
+        let mut points = vertices.map(|v| self.to_pixels(v.position));
+        let mut vertices = vertices;
+        let edge = |a: (f32, f32), b: (f32, f32), p: (f32, f32)|
+            (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0);
+        let mut area = edge(points[0], points[1], points[2]);
+        if area == 0.0 {
+            return;
+        }
+        if area < 0.0 {
+            points.swap(1, 2);
+            vertices.swap(1, 2);
+            area = -area;
+        }
+        let top_left = |a: (f32, f32), b: (f32, f32)|
+            (a.1 == b.1 && b.0 > a.0) || b.1 < a.1;
+        let edges = [(points[1], points[2]), (points[2], points[0]), (points[0], points[1])];
+        let (width, height) = self.target.dimensions();
+        let min_x = points.iter().map(|p| p.0).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+        let min_y = points.iter().map(|p| p.1).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+        let max_x = points.iter().map(|p| p.0).fold(f32::MIN, f32::max).ceil()
+            .min(width as f32) as u32;
+        let max_y = points.iter().map(|p| p.1).fold(f32::MIN, f32::max).ceil()
+            .min(height as f32) as u32;
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = (x as f32 + 0.5, y as f32 + 0.5);
+                let mut weights = [0.0; 3];
+                let inside = edges.iter().zip(weights.iter_mut()).all(|((a, b), w)| {
+                    *w = edge(*a, *b, p);
+                    *w > 0.0 || (*w == 0.0 && top_left(*a, *b))
+                });
+                if !inside {
+                    continue;
+                }
+                let weights = weights.map(|w| w / area);
+                let mix = |f: &dyn Fn(&Vertex) -> f32|
+                    vertices.iter().zip(weights).map(|(v, w)| f(v) * w).sum::<f32>();
+                let (u, v) = (mix(&|v| v.tex_coords[0]), mix(&|v| v.tex_coords[1]));
+                let texel = sample(texture, u, v);
+                let mut color = [0.0; 4];
+                for (i, c) in color.iter_mut().enumerate() {
+                    *c = texel[i] as f32 / 255.0 * mix(&|v| v.color[i]) * tint[i];
+                }
+                if let Some(fill) = fill {
+                    fill.apply(&mut color);
+                }
+                blend(self.target.get_pixel_mut(x, y), color);
+            }
+        }
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    type Texture = RgbaImage;
+    fn clear(&mut self, color: [f32; 4]) {
+        let pixel = Rgba(color.map(to_byte));
+        self.target.pixels_mut().for_each(|p| *p = pixel);
+    }
+    fn draw_quads(&mut self, vertices: &[Vertex], texture: &RgbaImage, tint: [f32; 4]) {
+        self.draw_filled(vertices, texture, tint, None);
+    }
+    /// Вспышка рисуется как программой FLASH_PROGRAM, остальные материалы как есть
+    fn draw_quads_with_material(&mut self, vertices: &[Vertex], texture: &RgbaImage,
+                                material: &Material) {
+        self.draw_filled(vertices, texture, WHITE, Fill::from_material(material));
+    }
+}
+
+impl SoftwareRenderer {
+    fn draw_filled(&mut self, vertices: &[Vertex], texture: &RgbaImage, tint: [f32; 4],
+                   fill: Option<Fill>) {
+        if texture.width() == 0 || texture.height() == 0 {
+            return;
+        }
+        for quad in vertices.chunks_exact(4) {
+            self.draw_triangle([&quad[0], &quad[1], &quad[2]], texture, tint, fill);
+            self.draw_triangle([&quad[1], &quad[3], &quad[2]], texture, tint, fill);
+        }
+    }
+}
+
+/// Заливка цветом из материала вспышки
+#[derive(Debug, Clone, Copy)]
+struct Fill {
+    color: [f32; 4],
+    amount: f32,
+}
+
+impl Fill {
+    fn from_material(material: &Material) -> Option<Fill> {
+        if material.program() != FLASH_PROGRAM {
+            return None;
+        }
+        let color = match material.get("effect_color") {
+            Some(MaterialValue::Vec4(color)) => *color,
+            _ => return None,
+        };
+        let amount = match material.get("amount") {
+            Some(MaterialValue::Float(amount)) => *amount,
+            _ => return None,
+        };
+        Some(Fill { color, amount })
+    }
+    /// Как в шейдере вспышки: заливается только цвет, прозрачность остаётся
+    fn apply(&self, color: &mut [f32; 4]) {
+        let amount = self.amount * self.color[3];
+        for (c, fill) in color.iter_mut().zip(self.color).take(3) {
+            *c += (fill - *c) * amount;
+        }
+    }
+}
+
+/// Пиксель текстуры по её координатам. Низ текстуры это v = 0, как в OpenGL
+fn sample(texture: &RgbaImage, u: f32, v: f32) -> Rgba<u8> {
+    let (width, height) = texture.dimensions();
+    let x = (u * width as f32).floor().clamp(0.0, width as f32 - 1.0) as u32;
+    let y = ((1.0 - v) * height as f32).floor().clamp(0.0, height as f32 - 1.0) as u32;
+    *texture.get_pixel(x, y)
+}
+
+/// Смешивает цвет с пикселем так же, как SpriteManager::draw_parameters_default
+fn blend(pixel: &mut Rgba<u8>, color: [f32; 4]) {
+    let alpha = color[3];
+    for (channel, c) in pixel.0.iter_mut().zip(color) {
+        let old = *channel as f32 / 255.0;
+        *channel = to_byte(c * alpha + old * (1.0 - alpha));
+    }
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
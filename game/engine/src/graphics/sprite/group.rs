@@ -1,34 +1,101 @@
+use std::collections::HashMap;
 use crate::graphics::Sprite;
-use crate::group::{Group, SomeGroup};
+use crate::group::{Commands, Group, Handle, SomeGroup};
+use crate::group::storage::Storage;
 use crate::misc_traits::named::Named;
 
-/// Как обычная группа, только для спрайтов и со специальными для них методами
+/// Как обычная группа, только для спрайтов и со специальными для них методами.
+/// Спрайты с именами можно быстро найти по имени. Имя спрайта, который уже лежит
+/// в группе, меняется через rename, иначе группа не узнает новое имя
 #[derive(Debug)]
 pub struct SpriteGroup {
-    group: Group<Sprite>
+    group: Group<Sprite>,
+    /// Ссылки на спрайты с каждым именем в порядке добавления
+    names: HashMap<String, Vec<Handle>>,
 }
 
 impl SpriteGroup {
     pub fn new() -> Self {
         SpriteGroup {
-            group: Group::new()
+            group: Group::new(),
+            names: HashMap::new(),
         }
     }
     /// Создаёт группу из вектора элементов
     pub fn from(elements: Vec<Sprite>) -> Self {
-        SpriteGroup {group: Group::from(elements)}
+        let mut group = SpriteGroup::new();
+        group.append(elements);
+        group
     }
-    /// Ищет спрайт по имени
-    pub fn find(&self, name: &str) -> Option<usize>{
-        let elements = self.get_elements();
-        for i in 0..self.get_elements().len() {
-            let element = if let Some(sprite)= &elements[i] {sprite} else {continue};
-            let sprite_name = if let Some(n) = element.get_name() {n} else {continue};
-            if sprite_name.as_str() == name {
-                return Some(i);
-            }
+    /// Кладёт спрайт в группу и возвращает ссылку на него
+    pub fn insert(&mut self, sprite: Sprite) -> Handle {
+        let name = sprite.get_name().clone();
+        let handle = self.group.insert(sprite);
+        if let Some(name) = name {
+            self.names.entry(name).or_default().push(handle);
+        }
+        handle
+    }
+    /// Убирает спрайт из группы и возвращает его
+    pub fn remove(&mut self, handle: Handle) -> Option<Sprite> {
+        let sprite = self.group.remove(handle)?;
+        if let Some(name) = sprite.get_name() {
+            self.forget_name(name, handle);
+        }
+        Some(sprite)
+    }
+    /// Меняет имя спрайта в группе. None убирает имя.
+    /// Возвращает false, если такого спрайта в группе нет
+    pub fn rename(&mut self, handle: Handle, name: Option<&str>) -> bool {
+        let sprite = match self.group.get_by_handle_mut(handle) {
+            Some(s) => s,
+            None => return false,
+        };
+        let old = std::mem::replace(sprite.get_name_mut(), name.map(String::from));
+        if let Some(old) = old {
+            self.forget_name(&old, handle);
+        }
+        if let Some(name) = name {
+            self.names.entry(name.to_string()).or_default().push(handle);
+        }
+        true
+    }
+    /// Есть ли ещё в группе спрайт, на который указывает ссылка
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.group.contains(handle)
+    }
+    /// Ссылка на спрайт под указанным индексом
+    pub fn handle_of(&self, index: usize) -> Option<Handle> {
+        self.group.handle_of(index)
+    }
+    /// Ссылки на все спрайты группы
+    pub fn handles(&self) -> Vec<Handle> {
+        self.group.handles()
+    }
+    /// Возвращает спрайт по ссылке
+    pub fn get_by_handle(&self, handle: Handle) -> Option<&Sprite> {
+        self.group.get_by_handle(handle)
+    }
+    /// Возвращает изменяемый спрайт по ссылке
+    pub fn get_by_handle_mut(&mut self, handle: Handle) -> Option<&mut Sprite> {
+        self.group.get_by_handle_mut(handle)
+    }
+    /// Применяет отложенные изменения и возвращает ссылки на новые спрайты
+    pub fn apply(&mut self, commands: &mut Commands<Sprite>) -> Vec<Handle> {
+        let (despawn, spawn) = commands.take();
+        for handle in despawn {
+            self.remove(handle);
         }
-        return None;
+        spawn.into_iter().map(|s| self.insert(s)).collect()
+    }
+    /// Ищет спрайт по имени и возвращает ссылку на него.
+    /// Если спрайтов с таким именем несколько, находится добавленный раньше
+    pub fn find_handle(&self, name: &str) -> Option<Handle> {
+        self.names.get(name)?.first().copied()
+    }
+    /// Ищет спрайт по имени
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.find_handle(name).map(|h| h.index())
     }
     /// Ищет спрайт по имени и возвращает Option<&Sprite>
     pub fn find_get(&self, name: &str) -> Option<&Sprite> {
@@ -48,19 +115,42 @@ impl SpriteGroup {
     }
     /// Ищет спрайт по имени и возвращает Option<Sprite>
     pub fn find_take(&mut self, name: &str) -> Option<Sprite> {
-        let i = self.find(name);
-        match i {
-            Some(i) => self.take_el(i),
+        let handle = self.find_handle(name);
+        match handle {
+            Some(h) => self.remove(h),
             None => None,
         }
     }
+    fn forget_name(&mut self, name: &str, handle: Handle) {
+        if let Some(handles) = self.names.get_mut(name) {
+            handles.retain(|h| *h != handle);
+            if handles.is_empty() {
+                self.names.remove(name);
+            }
+        }
+    }
+}
+
+impl Storage<Sprite> for SpriteGroup {
+    fn get_elements_mut(&mut self) -> &mut Vec<Option<Sprite>> {
+        self.group.get_elements_mut()
+    }
 }
 
 impl SomeGroup<Sprite> for SpriteGroup {
     fn get_elements(&self) -> &Vec<Option<Sprite>> {
         self.group.get_elements()
     }
-    fn get_elements_mut(&mut self) -> &mut Vec<Option<Sprite>> {
-        self.group.get_elements_mut()
+    fn push(&mut self, element: Sprite) {
+        self.insert(element);
+    }
+    fn append(&mut self, elements: Vec<Sprite>) {
+        for element in elements {
+            self.insert(element);
+        }
+    }
+    fn take_el(&mut self, i: usize) -> Option<Sprite> {
+        let handle = self.handle_of(i)?;
+        self.remove(handle)
     }
 }
@@ -1,28 +1,140 @@
 use glium::glutin::dpi::PhysicalSize;
+use glium::glutin::window::Fullscreen;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
 use std::path::Path;
+use std::rc::Rc;
 use cgmath::{Matrix4, Point2};
-use glium::{BlendingFunction, LinearBlendingFactor, Surface, uniform};
+use glium::{BlendingFunction, DrawError, LinearBlendingFactor, Surface, uniform};
 
 use crate::component::group::ComponentsGroup;
-use crate::graphics::{Sprite, Vertex};
+use crate::group::SomeGroup;
+use std::fs;
+use glium::texture::SrgbTexture2d;
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, Uniforms};
+use crate::graphics::material::{Material, MaterialUniforms};
+use crate::programs::{ProgramManager, DEFAULT_PROGRAM};
+use crate::graphics::{Camera, ScalePolicy, Sprite, Vertex, Viewport};
+use crate::graphics::background::{ParallaxBackground, ParallaxLayer, VerticalAnchor};
 use crate::loader::TextureLoader;
 use crate::Rect;
 use crate::rect::Rectangular;
 use crate::graphics::traits::HasTexture;
+use crate::tilemap::{MapDescription, Tilemap, TilemapError};
+use crate::graphics::text::{Font, FontError, Text};
+use crate::graphics::text::font::DEFAULT_CHARSET;
+use crate::graphics::gui::{Ui, UiCommand};
+use crate::graphics::particles::ParticleEmitter;
+use crate::graphics::debug::DebugDraw;
+use crate::physics::trajectory::Trajectory;
+use crate::component::registry::ComponentRegistry;
+use crate::graphics::sprite::SpriteGroup;
+use crate::save::{GameState, SaveError, SpriteState};
+use crate::graphics::render::{draw_layered, particle_vertices, quad_vertices, sprite_quads,
+                              text_quads, FrameRenderer, QuadBatch, QuadSource, WHITE};
+use glium::texture::{MipmapsOption, Texture2d, TextureCreationError, UncompressedFloatFormat};
+use crate::graphics::capture::{raw_to_image, PendingFrame};
+use image::RgbaImage;
 
-impl SpriteManager<'_> {
+impl<'a> SpriteManager<'a> {
     /// Создаёт новый экземпляр SpriteManager из ссылки на дисплей, прогромму для рисования,
     /// также требует параметры экрана.
-    pub fn from<'a>(display: &'a glium::Display, program: &'a glium::Program,
+    /// screen_width и screen_height это виртуальное разрешение игры, оно не меняется
+    /// при изменении размера окна. По умолчанию используется ScalePolicy::Letterbox.
+    pub fn from(display: &'a glium::Display, program: &'a glium::Program,
     screen_width: u32, screen_height: u32) -> SpriteManager<'a> {
-        SpriteManager {
+        let (window_width, window_height) = display.get_framebuffer_dimensions();
+        let mut manager = SpriteManager {
             display,
             program,
-            perspective: SpriteManager::perspective_default(screen_width, screen_height),
+            viewport: Viewport::new(PhysicalSize::new(screen_width, screen_height),
+                                    PhysicalSize::new(window_width, window_height),
+                                    ScalePolicy::Letterbox),
+            camera: Camera::new(),
+            white: SrgbTexture2d::new(display,
+                glium::texture::RawImage2d::from_raw_rgba(vec![255u8; 4], (1, 1))).unwrap(),
             draw_parameters: SpriteManager::draw_parameters_default(),
             screen_size: PhysicalSize::new(screen_width, screen_height),
+            programs: None,
+            time: 0.0,
+            broken_programs: RefCell::new(HashSet::new()),
+        };
+        manager.draw_parameters.viewport = manager.viewport.gl_rect();
+        manager
+    }
+    /// Возвращает SpriteManager, который рисует материалы спрайтов программами из programs.
+    /// Без этого и для неизвестных программ спрайты рисуются программой по умолчанию
+    pub fn with_programs(mut self, programs: &'a ProgramManager) -> Self {
+        self.programs = Some(programs);
+        self
+    }
+    /// Прибавляет dt миллисекунд ко времени, которое получают материалы
+    pub fn advance_time(&mut self, dt: u32) {
+        self.time += dt as f32 / 1000.0;
+    }
+    /// Время в секундах, которое получают материалы
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+    /// Возвращает SpriteManager с указанным способом подгонки картинки под окно
+    pub fn with_scale_policy(mut self, policy: ScalePolicy) -> Self {
+        self.set_scale_policy(policy);
+        self
+    }
+    /// Меняет способ подгонки картинки под окно
+    pub fn set_scale_policy(&mut self, policy: ScalePolicy) {
+        self.viewport.set_policy(policy);
+        self.draw_parameters.viewport = self.viewport.gl_rect();
+    }
+    /// Нужно вызывать при WindowEvent::Resized и WindowEvent::ScaleFactorChanged
+    pub fn resize(&mut self, window_size: PhysicalSize<u32>) {
+        self.viewport.resize(window_size);
+        self.draw_parameters.viewport = self.viewport.gl_rect();
+    }
+    /// Проекция вместе со сдвигом камеры
+    pub fn projection(&self) -> [[f32; 4]; 4] {
+        let matrix = Matrix4::from(self.viewport.projection()) * self.camera.view_matrix();
+        Into::<[[f32; 4]; 4]>::into(matrix)
+    }
+    /// Возвращает неизменяемую ссылку на камеру
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+    /// Возвращает изменяемую ссылку на камеру
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+    /// Дисплей, на котором создаются текстуры и буферы
+    pub fn display(&self) -> &'a glium::Display {
+        self.display
+    }
+    /// Возвращает неизменяемую ссылку на Viewport
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+    /// Возвращает виртуальное разрешение игры
+    pub fn screen_size(&self) -> PhysicalSize<u32> {
+        self.screen_size
+    }
+    /// Переключает окно между полноэкранным и оконным режимом
+    pub fn toggle_fullscreen(&self) {
+        let gl_window = self.display.gl_window();
+        let window = gl_window.window();
+        match window.fullscreen() {
+            Some(_) => window.set_fullscreen(None),
+            None => window.set_fullscreen(Some(Fullscreen::Borderless(None))),
         }
     }
+    /// Возвращает true, если окно сейчас в полноэкранном режиме
+    pub fn is_fullscreen(&self) -> bool {
+        self.display.gl_window().window().fullscreen().is_some()
+    }
+    /// Заливает игровую область указанным цветом, а полосы по краям (для Letterbox) чёрным
+    pub fn clear(&self, frame: &mut impl Surface, r: f32, g: f32, b: f32, a: f32) {
+        frame.clear_color(0.0, 0.0, 0.0, 1.0);
+        frame.clear(self.viewport.gl_rect().as_ref(), Some((r, g, b, a)), false, None, None);
+    }
     /// Создаёт спрайт, загружая его картинку из файла по указанному пути
     /// аттрибуты width и height это ширина и высота спрайта
     pub fn build_sprite(&self, path:&Path, scale: f64)
@@ -35,7 +147,7 @@ impl SpriteManager<'_> {
             _ => None,
         };
         Sprite::new(rect, texture, name, 1, 1, 1, 1,
-                    None, false)
+                    None, false).with_texture_path(path)
     }
     /// Создаёт новый спрайт, передавая ему все необходимые данные.
     pub fn new_sprite(&self, path:&Path, name: Option<String>, frames_h: u32, frames_v: u32,
@@ -45,6 +157,12 @@ impl SpriteManager<'_> {
         let texture = TextureLoader::load_rgba_texture(path, self.display);
         let rect = Rect::from_scaled(texture.dimensions(), scale);
         Sprite::new(rect, texture, name, frames_h, frames_v, _cur_frame, layer, components, _hidden)
+            .with_texture_path(path)
+    }
+    /// Загружает карту нормалей для спрайта, см. Sprite::with_normal_map.
+    /// Одну карту могут использовать несколько спрайтов
+    pub fn build_normal_map(&self, path: &Path) -> Rc<Texture2d> {
+        Rc::new(TextureLoader::load_normal_map(path, self.display))
     }
     /// Создаёт новый спрайт для фона
     pub fn build_bg(&self, path:&Path) -> Sprite {
@@ -52,10 +170,336 @@ impl SpriteManager<'_> {
         let rect = Rect::new(Point2::new(0.0, 0.0),
         PhysicalSize::new(self.screen_size.width as f64, self.screen_size.height as f64));
         Sprite::new(rect, texture, None, 1,
-                    1, 1, 0, None,false)
+                    1, 1, 0, None,false).with_texture_path(path)
+    }
+    /// Создаёт слой параллакс фона. Картинка масштабируется так,
+    /// чтобы её высота совпадала с высотой экрана.
+    pub fn build_parallax_layer(&self, path: &Path, scroll_factor: f64) -> ParallaxLayer {
+        let texture = TextureLoader::load_rgba_texture(path, self.display);
+        let scale = self.screen_size.height as f64 / texture.height() as f64;
+        ParallaxLayer::new(texture, scroll_factor, scale)
+    }
+    /// Рисует текущий кадр анимации спрайта на указанном фрейме. Скрытый спрайт не рисуется.
+    /// Частицы его ParticleComponent рисуются под спрайтом, чтобы след шёл из-под него
+    pub fn draw(&self, sprite: &Sprite, frame: &mut impl Surface) {
+        self.draw_batches(sprite_quads([sprite]), frame);
     }
-    /// Рисует спрайт на указанном фрейме
-    pub fn draw(&self, sprite: &Sprite, frame: &mut glium::Frame) {
+    /// Рисует квадраты программой материала. Если программы нет в ProgramManager
+    /// или она не может нарисовать квадраты с параметрами материала, квадраты рисуются
+    /// программой по умолчанию. О сломанной или ненайденной программе сообщается один раз
+    pub fn draw_quads_with_material(&self, vb_data: &[Vertex], texture: &SrgbTexture2d,
+                                    material: &Material, frame: &mut impl Surface) {
+        let name = material.program();
+        let program = match self.programs.and_then(|p| p.get(name)) {
+            _ if self.broken_programs.borrow().contains(name) => None,
+            Some(program) => Some(program),
+            None => {
+                self.mark_broken(name, &"программы нет в ProgramManager");
+                None
+            }
+        };
+        if let Some(program) = program {
+            let uniforms = MaterialUniforms {
+                projection: self.projection(),
+                texture,
+                tint: WHITE,
+                time: self.time,
+                material,
+            };
+            match self.draw_quads_with(vb_data, program, &uniforms, frame) {
+                Ok(()) => return,
+                Err(e) => self.mark_broken(name, &e),
+            }
+        }
+        self.draw_quads(vb_data, texture, WHITE, false, self.projection(), frame);
+    }
+    /// Рисует спрайты группы по слоям: спрайты верхних слоёв рисуются поверх нижних
+    pub fn draw_sprites(&self, sprites: &SpriteGroup, frame: &mut impl Surface) {
+        self.draw_layers(sprites, &[], frame);
+    }
+    /// Рисует спрайты и текст вместе по слоям, так что текст может оказаться
+    /// и под спрайтом, и над ним. На одном слое текст рисуется поверх спрайтов
+    pub fn draw_layers(&self, sprites: &SpriteGroup, texts: &[(&Text, &Font)],
+                       frame: &mut impl Surface) {
+        let mut batches = sprite_quads(sprites.get_elements().iter().flatten());
+        for (text, font) in texts {
+            batches.extend(text_quads(text, font));
+        }
+        self.draw_batches(batches, frame);
+    }
+    /// Рисует квадраты спрайтов по слоям через FrameRenderer
+    fn draw_batches<S: Surface>(&self, batches: Vec<QuadBatch<'_, Sprite>>, frame: &mut S) {
+        draw_layered(&mut self.renderer(frame), batches, |source| match source {
+            QuadSource::Sprite(sprite) => Some(sprite.get_texture()),
+            QuadSource::White => Some(&self.white),
+            QuadSource::Font(font) => Some(font.get_texture()),
+        });
+    }
+    /// Рисовальщик, который рисует через этот SpriteManager в указанный фрейм
+    pub fn renderer<'f, S: Surface>(&'f self, frame: &'f mut S) -> FrameRenderer<'f, 'a, S> {
+        FrameRenderer::new(self, frame)
+    }
+    /// Копия того, что уже нарисовано во фрейме, вместе с полосами по краям окна.
+    /// Верхняя строка картинки это верх окна. Ждёт, пока видеокарта дорисует кадр
+    pub fn screenshot(&self, frame: &glium::Frame) -> Result<RgbaImage, TextureCreationError> {
+        Ok(raw_to_image(self.copy_frame(frame)?.read()))
+    }
+    /// Начинает копировать фрейм в память, не дожидаясь видеокарты.
+    /// Картинку можно забрать из PendingFrame позже, например на следующем кадре
+    pub fn read_frame_later(&self, frame: &glium::Frame)
+        -> Result<PendingFrame, TextureCreationError>
+    {
+        Ok(PendingFrame::new(self.copy_frame(frame)?.read_to_pixel_buffer()))
+    }
+    /// Копирует фрейм в текстуру, из которой его можно прочитать
+    fn copy_frame(&self, frame: &glium::Frame) -> Result<Texture2d, TextureCreationError> {
+        let (width, height) = frame.get_dimensions();
+        let texture = Texture2d::empty_with_format(self.display, UncompressedFloatFormat::U8U8U8U8,
+                                                   MipmapsOption::NoMipmap, width, height)?;
+        frame.fill(&texture.as_surface(), MagnifySamplerFilter::Nearest);
+        Ok(texture)
+    }
+    /// Рисует все частицы излучателя за один вызов draw квадратами без текстуры
+    pub fn draw_particles(&self, emitter: &ParticleEmitter, frame: &mut impl Surface) {
+        self.draw_particles_with_texture(emitter, &self.white, frame);
+    }
+    /// Рисует все частицы излучателя за один вызов draw, каждая частица это texture,
+    /// умноженная на её цвет
+    pub fn draw_particles_with_texture(&self, emitter: &ParticleEmitter, texture: &SrgbTexture2d,
+                                       frame: &mut impl Surface) {
+        let vb_data = particle_vertices(emitter);
+        self.draw_quads(&vb_data, texture, WHITE, false, self.projection(), frame);
+    }
+    /// Рисует все слои фона с учётом позиции камеры
+    pub fn draw_background(&self, background: &ParallaxBackground, frame: &mut impl Surface) {
+        let visible = self.viewport.visible_area();
+        let camera = self.camera.position();
+        let visible_top = visible.bottom() - visible.height();
+        for layer in background.layers() {
+            let (width, height) = layer.size();
+            if width <= 0.0 || height <= 0.0 {
+                continue;
+            }
+            let factor = layer.scroll_factor();
+            let (offset_x, offset_y) = layer.offset();
+            let anchor_y = match layer.anchor() {
+                VerticalAnchor::Top => visible_top + height,
+                VerticalAnchor::Center => visible_top + (visible.height() + height) / 2.0,
+                VerticalAnchor::Bottom => visible.bottom(),
+            };
+            // Позиция на экране, а не в мире: камера к ней прибавится ниже
+            let bottom = anchor_y + offset_y - camera.y * factor;
+            let mut left = offset_x - camera.x * factor;
+            let mut count = 1;
+            if layer.repeat_x() {
+                left += ((visible.left() - left) / width).floor() * width;
+                count = ((visible.right() - left) / width).ceil().max(1.0) as u32;
+            }
+            for i in 0..count {
+                let rect = Rect::new(
+                    Point2::new(left + i as f64 * width + camera.x, bottom + camera.y),
+                    PhysicalSize::new(width, height));
+                self.draw_texture(layer.get_texture(), &rect, frame);
+            }
+        }
+    }
+    /// Рисует предсказанный путь снаряда пунктиром: точка размера dot_size на каждом
+    /// every-м шаге, дальние точки прозрачнее. Место столкновения отмечается точкой побольше.
+    /// Всё рисуется за один вызов draw
+    pub fn draw_trajectory(&self, trajectory: &Trajectory, every: usize, dot_size: f64,
+                           color: [f32; 4], frame: &mut impl Surface) {
+        let dot = |point: Point2<f64>, size: f64, color: [f32; 4]| {
+            let rect = Rect::new(Point2::new(point.x - size / 2.0, point.y + size / 2.0),
+                                 PhysicalSize::new(size, size));
+            SpriteManager::colored_quad(&rect, [0.0, 0.0, 1.0, 1.0], color)
+        };
+        let count = trajectory.points.len().max(1) as f32;
+        let mut vb_data = Vec::new();
+        for (i, point) in trajectory.points.iter().enumerate().step_by(every.max(1)) {
+            let mut color = color;
+            color[3] *= 1.0 - i as f32 / count * 0.7;
+            vb_data.extend_from_slice(&dot(*point, dot_size, color));
+        }
+        if let Some(collision) = trajectory.collision {
+            vb_data.extend_from_slice(&dot(collision.point, dot_size * 2.0, color));
+        }
+        self.draw_quads(&vb_data, &self.white, WHITE, false, self.projection(), frame);
+    }
+    /// Рисует отладочные фигуры за один вызов draw. Фигуры рисуются поверх
+    /// того, что уже нарисовано, в координатах мира с учётом камеры
+    pub fn draw_shapes(&self, shapes: &DebugDraw, frame: &mut impl Surface) {
+        if shapes.is_empty() {
+            return;
+        }
+        let vertices = glium::VertexBuffer::new(self.display, shapes.vertices()).unwrap();
+        let indices = glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList);
+        let uniforms = uniform! {
+            projection: self.projection(),
+            tex: &self.white,
+            tint: WHITE,
+        };
+        let result = frame.draw(&vertices, indices, self.program, &uniforms,
+                                &self.draw_parameters);
+        if let Err(e) = result {
+            self.mark_broken(DEFAULT_PROGRAM, &e);
+        }
+    }
+    /// Загружает карту из файла. Путь к тайлсету в файле карты считается
+    /// относительно папки, в которой лежит карта.
+    pub fn build_tilemap(&self, path: &Path) -> Result<Tilemap, TilemapError> {
+        let description = MapDescription::parse(&fs::read_to_string(path)?)?;
+        let tileset_path = path.parent().unwrap_or(Path::new("")).join(&description.tileset);
+        let texture = TextureLoader::load_rgba_texture(&tileset_path, self.display);
+        Ok(Tilemap::from_description(description, texture))
+    }
+    /// Создаёт заново спрайты из сохранения. Компоненты загружаются через registry
+    pub fn load_sprites(&self, state: &GameState, registry: &ComponentRegistry)
+        -> Result<SpriteGroup, SaveError>
+    {
+        let mut sprites = SpriteGroup::new();
+        for sprite in &state.sprites {
+            sprites.insert(self.load_sprite(sprite, registry)?);
+        }
+        Ok(sprites)
+    }
+    /// Создаёт заново один спрайт из сохранения
+    pub fn load_sprite(&self, state: &SpriteState, registry: &ComponentRegistry)
+        -> Result<Sprite, SaveError>
+    {
+        let path = state.texture.as_deref().ok_or_else(|| SaveError::NoTexture(state.name.clone()))?;
+        if !path.is_file() {
+            return Err(SaveError::NoTexture(state.name.clone()));
+        }
+        let mut components = Vec::with_capacity(state.components.len());
+        for (kind, data) in &state.components {
+            if !registry.is_registered(kind) {
+                return Err(SaveError::UnknownComponent(kind.clone()));
+            }
+            let component = registry.load(kind, data)
+                .ok_or_else(|| SaveError::BadComponent(kind.clone()))?;
+            components.push(component);
+        }
+        let texture = TextureLoader::load_rgba_texture(path, self.display);
+        let components = if components.is_empty() {None} else {Some(ComponentsGroup::from(components))};
+        Ok(Sprite::new(state.rect, texture, state.name.clone(),
+                       state.frames.0, state.frames.1, state.current_frame,
+                       state.layer, components, state.hidden)
+            .with_texture_path(path))
+    }
+    /// Рисует видимые тайлы карты. Каждый слой рисуется за один вызов draw
+    pub fn draw_tilemap(&self, tilemap: &Tilemap, frame: &mut impl Surface) {
+        let (columns, rows) = tilemap.cells_in(&self.visible_area());
+        let tileset = tilemap.tileset();
+        for layer in tilemap.layers() {
+            if layer.is_hidden() {
+                continue;
+            }
+            let mut vb_data = Vec::new();
+            for row in rows.clone() {
+                for column in columns.clone() {
+                    let coords = layer.get(column, row).and_then(|id| tileset.tex_coords(id));
+                    if let Some(coords) = coords {
+                        let rect = tilemap.cell_rect(column, row);
+                        vb_data.extend_from_slice(&SpriteManager::quad(&rect, coords));
+                    }
+                }
+            }
+            // Тайлы рисуются без сглаживания, иначе на стыках видны соседние тайлы
+            self.draw_quads(&vb_data, tileset.get_texture(), WHITE, true,
+                            self.projection(), frame);
+        }
+    }
+    /// Загружает шрифт размера size пикселей со стандартным набором символов
+    pub fn build_font(&self, path: &Path, size: f32) -> Result<Font, FontError> {
+        Font::load(self.display, path, size, DEFAULT_CHARSET)
+    }
+    /// Рисует текст указанным шрифтом поверх того, что уже нарисовано.
+    /// Сначала рисуется обводка, потом сам текст. Чтобы текст был на своём слое
+    /// среди спрайтов, его нужно рисовать через draw_layers
+    pub fn draw_text(&self, text: &Text, font: &Font, frame: &mut impl Surface) {
+        self.draw_text_with(text, font, self.projection(), frame);
+    }
+    /// Рисует интерфейс. Интерфейс рисуется поверх всего и не сдвигается камерой
+    pub fn draw_ui(&self, ui: &Ui, font: &Font, frame: &mut impl Surface) {
+        let projection = self.viewport.projection();
+        for command in ui.commands() {
+            match command {
+                UiCommand::Rect { rect, color } => {
+                    let vb_data = SpriteManager::quad(rect, [0.0, 0.0, 1.0, 1.0]);
+                    self.draw_quads(&vb_data, &self.white, *color, false, projection, frame);
+                }
+                UiCommand::Text(text) => self.draw_text_with(text, font, projection, frame),
+            }
+        }
+    }
+    fn draw_text_with(&self, text: &Text, font: &Font, projection: [[f32; 4]; 4],
+                      frame: &mut impl Surface) {
+        for batch in text_quads::<Sprite>(text, font) {
+            self.draw_quads(&batch.vertices, font.get_texture(), batch.tint, false, projection,
+                            frame);
+        }
+    }
+    /// Четыре вершины квадрата. tex_coords это (левый, нижний, правый, верхний)
+    fn quad(rect: &Rect, tex_coords: [f32; 4]) -> [Vertex; 4] {
+        SpriteManager::colored_quad(rect, tex_coords, WHITE)
+    }
+    /// Четыре вершины квадрата, текстура которого умножается на color
+    fn colored_quad(rect: &Rect, tex_coords: [f32; 4], color: [f32; 4]) -> [Vertex; 4] {
+        quad_vertices(rect, tex_coords, color)
+    }
+    /// Рисует за один вызов draw много квадратов одной текстуры.
+    /// Каждые четыре вершины это один квадрат в том же порядке, что и в draw_texture.
+    /// nearest выключает сглаживание текстуры.
+    pub(crate) fn draw_quads(&self, vb_data: &[Vertex], texture: &SrgbTexture2d, tint: [f32; 4],
+                  nearest: bool, projection: [[f32; 4]; 4], frame: &mut impl Surface) {
+        let (magnify, minify) = if nearest {
+            (MagnifySamplerFilter::Nearest, MinifySamplerFilter::Nearest)
+        } else {
+            (MagnifySamplerFilter::Linear, MinifySamplerFilter::Linear)
+        };
+        let uniforms = uniform! {
+            projection: projection,
+            tex: texture.sampled().magnify_filter(magnify).minify_filter(minify),
+            tint: tint,
+        };
+        if let Err(e) = self.draw_quads_with(vb_data, self.program, &uniforms, frame) {
+            self.mark_broken(DEFAULT_PROGRAM, &e);
+        }
+    }
+    /// Запоминает, что программа не рисует, и сообщает об этом, если ещё не сообщалось.
+    /// Программой по умолчанию рисовать больше нечем, поэтому её квадраты просто пропускаются
+    fn mark_broken(&self, name: &str, error: &dyn fmt::Display) {
+        if self.broken_programs.borrow_mut().insert(name.to_string()) {
+            report_broken_program(name, error);
+        }
+    }
+    /// Рисует квадраты указанной программой с указанными uniform-параметрами
+    fn draw_quads_with<U: Uniforms>(&self, vb_data: &[Vertex], program: &glium::Program,
+                                    uniforms: &U, frame: &mut impl Surface)
+        -> Result<(), DrawError>
+    {
+        if vb_data.is_empty() {
+            return Ok(());
+        }
+        let ib_data: Vec<u32> = (0..vb_data.len() as u32 / 4)
+            .flat_map(|q| [0, 1, 2, 1, 3, 2].map(|i| q * 4 + i))
+            .collect();
+        let vertices = glium::VertexBuffer::new(self.display, vb_data).unwrap();
+        let indices = glium::IndexBuffer::new(
+            self.display,
+            glium::index::PrimitiveType::TrianglesList,
+            &ib_data
+        ).unwrap();
+        frame.draw(&vertices, &indices, program, uniforms, &self.draw_parameters)
+    }
+    /// Видимая часть мира с учётом камеры. Позиция это левый нижний угол
+    pub fn visible_area(&self) -> Rect {
+        let camera = self.camera.position();
+        let area = self.viewport.visible_area();
+        area.with_position(area.left() + camera.x, area.bottom() + camera.y)
+    }
+    /// Рисует текстуру в указанном квадрате
+    pub fn draw_texture(&self, texture: &SrgbTexture2d, rect: &Rect, frame: &mut impl Surface) {
         // Before we can draw the rectangle we have to
         // tell OpenGL what a rectangle is. All OpenGL needs
         // to know is that a rectangle is four vertexes (points)
@@ -89,31 +533,34 @@ impl SpriteManager<'_> {
         };
         // Dynamically set the rectangle's vertices.
         {
-            let rect = sprite.get_rect();
             let left = rect.left();
             let right = rect.left() + rect.width() as f64;
             let bottom = rect.bottom();
             let top = rect.bottom() - rect.height() as f64;
             //println!("left: {left}\nright: {right}\nbottom: {bottom}\ntop: {top}");
             let vb_data = vec![
-                Vertex { position: [left, top] },
-                Vertex { position: [right, top] },
-                Vertex { position: [left, bottom] },
-                Vertex { position: [right, bottom] }
+                Vertex { position: [left, top], tex_coords: [0.0, 1.0], color: WHITE },
+                Vertex { position: [right, top], tex_coords: [1.0, 1.0], color: WHITE },
+                Vertex { position: [left, bottom], tex_coords: [0.0, 0.0], color: WHITE },
+                Vertex { position: [right, bottom], tex_coords: [1.0, 0.0], color: WHITE }
             ];
             rect_vertices.write(&vb_data);
         }
         let uniforms = uniform! {
-            projection: self.perspective,
-            tex: sprite.get_texture(),
+            projection: self.projection(),
+            tex: texture,
+            tint: WHITE,
         };
-        frame.draw(
+        let result = frame.draw(
                 &rect_vertices,
                 &rect_indices,
                 self.program,
                 &uniforms,
                 &self.draw_parameters
-        ).unwrap();
+        );
+        if let Err(e) = result {
+            self.mark_broken(DEFAULT_PROGRAM, &e);
+        }
     }
     /// Задаёт дефолтные параметры рисования спрайтов
     pub fn draw_parameters_default() -> glium::DrawParameters<'static> {
@@ -153,7 +600,25 @@ impl SpriteManager<'_> {
 pub struct SpriteManager<'a> {
     display: &'a glium::Display,
     program: &'a glium::Program,
-    perspective: [[f32; 4]; 4],
+    viewport: Viewport,
+    camera: Camera,
+    /// Белая текстура 1x1, чтобы рисовать квадраты одного цвета через tint
+    white: SrgbTexture2d,
     draw_parameters: glium::draw_parameters::DrawParameters<'a>,
     screen_size: PhysicalSize<u32>,
+    /// Программы, которыми рисуются материалы спрайтов
+    programs: Option<&'a ProgramManager>,
+    /// Время в секундах для параметра time материалов
+    time: f32,
+    /// Программы, которые не нашлись или не смогли рисовать. Вместо программ материалов
+    /// рисуется программой по умолчанию
+    broken_programs: RefCell<HashSet<String>>,
 }
+
+#[cfg(feature = "tracing")]
+fn report_broken_program(name: &str, error: &dyn fmt::Display) {
+    tracing::warn!(program = name, %error, "программа не рисует");
+}
+
+#[cfg(not(feature = "tracing"))]
+fn report_broken_program(_name: &str, _error: &dyn fmt::Display) {}
@@ -1,9 +1,19 @@
-use glium::texture::SrgbTexture2d;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use glium::texture::{SrgbTexture2d, Texture2d};
+use crate::component::Component;
 use crate::component::group::ComponentsGroup;
+use crate::component::messages::ComponentMessage;
 use crate::component::traits::Composite;
 use crate::Rect;
 use crate::rect::Rectangular;
 use crate::graphics::traits::{FrameList, HasTexture, Layered};
+use crate::graphics::material::{Flash, Material};
+use crate::graphics::particles::ParticleEmitter;
+use crate::graphics::render::Drawable;
+use crate::group::SomeGroup;
+use crate::messaging::bus::Address;
 use crate::misc_traits::named::Named;
 
 /// Спрайт - это текстура и квадрат, в котором эта текстура рисуется.
@@ -20,15 +30,98 @@ pub struct Sprite {
     layer: u32,
     components: Option<ComponentsGroup>,
     _hidden: bool,
+    /// Сообщения другим спрайтам, их разносит MessageBus::deliver
+    outbox: Vec<(Address, ComponentMessage)>,
+    /// Файл, из которого загружена текстура. Нужен, чтобы загрузить спрайт из сохранения
+    texture_path: Option<PathBuf>,
+    /// Чем рисуется спрайт. Без материала он рисуется программой по умолчанию
+    material: Option<Material>,
+    /// Вспышка рисуется вместо материала, пока не закончится
+    flash: Option<Flash>,
+    /// Карта нормалей с теми же кадрами, что и текстура. Нужна только освещению
+    normal_map: Option<Rc<Texture2d>>,
 }
 
 impl Sprite {
     pub fn new (rect: Rect, texture: SrgbTexture2d, name: Option<String>, frames_h: u32,
                 frames_v: u32, _cur_frame: u32,
                 layer: u32, components: Option<ComponentsGroup>, _hidden: bool) -> Self {
-        Sprite { rect, texture, name, frames_h, frames_v, _cur_frame, layer,  components, _hidden}
+        Sprite { rect, texture, name, frames_h, frames_v, _cur_frame, layer,  components, _hidden,
+                 outbox: Vec::new(), texture_path: None, material: None, flash: None,
+                 normal_map: None}
+    }
+    /// Возвращает спрайт, который помнит, из какого файла загружена его текстура
+    pub fn with_texture_path(mut self, path: &Path) -> Self {
+        self.texture_path = Some(path.to_path_buf());
+        self
+    }
+    /// Файл, из которого загружена текстура спрайта
+    pub fn texture_path(&self) -> Option<&Path> {
+        self.texture_path.as_deref()
+    }
+    /// Текущий кадр анимации. Кадры считаются с единицы
+    pub fn current_frame(&self) -> u32 {
+        self._cur_frame
+    }
+    pub fn set_current_frame(&mut self, frame: u32) {
+        self._cur_frame = frame;
+    }
+    pub fn is_hidden(&self) -> bool {
+        self._hidden
+    }
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self._hidden = hidden;
+    }
+    /// Возвращает спрайт, который рисуется указанным материалом
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.set_material(Some(material));
+        self
+    }
+    pub fn material(&self) -> Option<&Material> {
+        self.material.as_ref()
+    }
+    pub fn material_mut(&mut self) -> Option<&mut Material> {
+        self.material.as_mut()
+    }
+    pub fn set_material(&mut self, material: Option<Material>) {
+        self.material = material;
+    }
+    /// Спрайт вспыхивает цветом, например белым при попадании. Новая вспышка заменяет старую
+    pub fn flash(&mut self, flash: Flash) {
+        self.flash = Some(flash);
+    }
+    /// Вспышка, которая сейчас идёт
+    pub fn current_flash(&self) -> Option<&Flash> {
+        self.flash.as_ref()
+    }
+    /// Возвращает спрайт, который освещается с учётом карты нормалей
+    pub fn with_normal_map(mut self, normal_map: Rc<Texture2d>) -> Self {
+        self.set_normal_map(Some(normal_map));
+        self
+    }
+    pub fn normal_map(&self) -> Option<&Rc<Texture2d>> {
+        self.normal_map.as_ref()
+    }
+    pub fn set_normal_map(&mut self, normal_map: Option<Rc<Texture2d>>) {
+        self.normal_map = normal_map;
+    }
+    /// Отправляет сообщение другому спрайту, всем спрайтам или на тему.
+    /// Компоненты могут звать это у своего спрайта прямо во время обновления
+    pub fn send_to(&mut self, to: Address, message: ComponentMessage) {
+        self.outbox.push((to, message));
+    }
+    /// Публикует сообщение на тему
+    pub fn publish(&mut self, topic: &str, message: ComponentMessage) {
+        self.send_to(Address::Topic(topic.to_string()), message);
+    }
+    /// Забирает сообщения, которые спрайт отправил
+    pub fn take_outbox(&mut self) -> Vec<(Address, ComponentMessage)> {
+        std::mem::take(&mut self.outbox)
     }
     pub fn updated(mut self, dt: u32) -> Sprite {
+        if let Some(mut flash) = self.flash {
+            self.flash = flash.update(dt).then_some(flash);
+        }
         let components = match self.components.take() {
             Some(t) => t,
             None => return self,
@@ -94,3 +187,28 @@ impl Composite for Sprite {
         &mut self.components
     }
 }
+
+impl Drawable for Sprite {
+    fn is_hidden(&self) -> bool {
+        Sprite::is_hidden(self)
+    }
+    fn current_frame(&self) -> u32 {
+        Sprite::current_frame(self)
+    }
+    fn texture_path(&self) -> Option<&Path> {
+        Sprite::texture_path(self)
+    }
+    fn current_material(&self) -> Option<Cow<'_, Material>> {
+        match &self.flash {
+            Some(flash) => Some(Cow::Owned(flash.material())),
+            None => self.material.as_ref().map(Cow::Borrowed),
+        }
+    }
+    fn particle_emitters(&self) -> Vec<&ParticleEmitter> {
+        let components = self.components.iter().flat_map(|c| c.get_elements().iter().flatten());
+        components.filter_map(|component| match component {
+            Component::ParticleComponent(p) => Some(p.emitter()),
+            _ => None,
+        }).collect()
+    }
+}
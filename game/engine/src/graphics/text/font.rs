@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use ab_glyph::{Font as _, FontVec, PxScale, ScaleFont};
+use glium::texture::SrgbTexture2d;
+use crate::graphics::traits::HasTexture;
+
+/// Символы, которые попадают в атлас шрифта: ASCII, кириллица и немного типографики
+pub const DEFAULT_CHARSET: &str = concat!(
+    " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`",
+    "abcdefghijklmnopqrstuvwxyz{|}~",
+    "АБВГДЕЁЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯабвгдеёжзийклмнопрстуфхцчшщъыьэюя",
+    "№«»—–…°"
+);
+/// Ширина атласа в пикселях, высота подбирается под количество символов
+const ATLAS_WIDTH: u32 = 1024;
+/// Символ, который рисуется вместо отсутствующих в атласе
+const FALLBACK_CHAR: char = '?';
+
+/// Ошибка загрузки шрифта
+#[derive(Debug)]
+pub enum FontError {
+    Io(std::io::Error),
+    /// Файл не является шрифтом TTF/OTF
+    InvalidFont,
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Io(e) => write!(f, "не удалось прочитать шрифт: {e}"),
+            FontError::InvalidFont => write!(f, "файл не является шрифтом"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+impl From<std::io::Error> for FontError {
+    fn from(e: std::io::Error) -> Self {
+        FontError::Io(e)
+    }
+}
+
+/// Положение символа в атласе и его размеры в пикселях
+#[derive(Debug, Clone, Copy)]
+pub struct Glyph {
+    /// Координаты текстуры: (левый, нижний, правый, верхний)
+    pub tex_coords: [f32; 4],
+    /// Размер картинки символа
+    pub size: (f32, f32),
+    /// Сдвиг левого верхнего угла картинки относительно точки на базовой линии
+    pub offset: (f32, f32),
+    /// На сколько сдвигается курсор после символа
+    pub advance: f32,
+}
+
+/// Размеры всех символов шрифта. Не зависит от OpenGL, поэтому по ней можно
+/// раскладывать текст без окна.
+#[derive(Debug, Clone)]
+pub struct FontMetrics {
+    glyphs: HashMap<char, Glyph>,
+    ascent: f32,
+    line_height: f32,
+}
+
+impl FontMetrics {
+    pub fn new(glyphs: HashMap<char, Glyph>, ascent: f32, line_height: f32) -> Self {
+        FontMetrics {glyphs, ascent, line_height}
+    }
+    /// Возвращает символ, либо символ-заглушку, если такого символа нет
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&FALLBACK_CHAR))
+    }
+    /// Расстояние от верха строки до базовой линии
+    pub fn ascent(&self) -> f32 {
+        self.ascent
+    }
+    /// Расстояние между базовыми линиями соседних строк
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+    /// Ширина строки без переносов
+    pub fn width_of(&self, text: &str) -> f32 {
+        text.chars().filter_map(|c| self.glyph(c)).map(|g| g.advance).sum()
+    }
+}
+
+/// Шрифт, растеризованный в атлас указанного размера
+#[derive(Debug)]
+pub struct Font {
+    metrics: FontMetrics,
+    atlas: SrgbTexture2d,
+}
+
+impl Font {
+    /// Загружает TTF/OTF шрифт и рисует символы charset в атлас.
+    /// size это высота шрифта в пикселях.
+    pub fn load<F>(facade: &F, path: &Path, size: f32, charset: &str) -> Result<Font, FontError>
+    where F: glium::backend::Facade
+    {
+        let font = FontVec::try_from_vec(std::fs::read(path)?)
+            .map_err(|_| FontError::InvalidFont)?;
+        let (metrics, pixels, dimensions) = Font::rasterize(&font, size, charset);
+        let image = glium::texture::RawImage2d::from_raw_rgba_reversed(&pixels, dimensions);
+        let atlas = SrgbTexture2d::new(facade, image).unwrap();
+        Ok(Font {metrics, atlas})
+    }
+    pub fn metrics(&self) -> &FontMetrics {
+        &self.metrics
+    }
+    /// Рисует символы в картинку RGBA: символы белые, а покрытие пикселя хранится в альфе.
+    /// Символы раскладываются по полкам слева направо, сверху вниз.
+    fn rasterize(font: &FontVec, size: f32, charset: &str) -> (FontMetrics, Vec<u8>, (u32, u32)) {
+        let scale = PxScale::from(size);
+        let scaled = font.as_scaled(scale);
+        let mut outlines = Vec::new();
+        let mut glyphs = HashMap::new();
+        let (mut x, mut y, mut shelf_height) = (1u32, 1u32, 0u32);
+        for c in charset.chars().chain(std::iter::once(FALLBACK_CHAR)) {
+            if glyphs.contains_key(&c) {
+                continue;
+            }
+            let id = font.glyph_id(c);
+            let advance = scaled.h_advance(id);
+            let outline = font.outline_glyph(id.with_scale(scale));
+            let (width, height, offset) = match &outline {
+                Some(o) => {
+                    let bounds = o.px_bounds();
+                    (bounds.width() as u32, bounds.height() as u32, (bounds.min.x, bounds.min.y))
+                }
+                None => (0, 0, (0.0, 0.0)),
+            };
+            if x + width + 1 > ATLAS_WIDTH {
+                x = 1;
+                y += shelf_height + 1;
+                shelf_height = 0;
+            }
+            glyphs.insert(c, Glyph {
+                tex_coords: [x as f32, y as f32, (x + width) as f32, (y + height) as f32],
+                size: (width as f32, height as f32),
+                offset,
+                advance,
+            });
+            if let Some(o) = outline {
+                outlines.push((o, x, y));
+            }
+            x += width + 1;
+            shelf_height = shelf_height.max(height);
+        }
+        let atlas_height = (y + shelf_height + 1).next_power_of_two();
+        let mut pixels = vec![0u8; (ATLAS_WIDTH * atlas_height * 4) as usize];
+        for (outline, left, top) in outlines {
+            outline.draw(|gx, gy, coverage| {
+                let i = (((top + gy) * ATLAS_WIDTH + left + gx) * 4) as usize;
+                pixels[i..i + 3].copy_from_slice(&[255, 255, 255]);
+                pixels[i + 3] = (coverage.clamp(0.0, 1.0) * 255.0) as u8;
+            });
+        }
+        // Переводим пиксели в координаты текстуры. Картинка загружается перевёрнутой,
+        // поэтому верх картинки это v = 1
+        let (w, h) = (ATLAS_WIDTH as f32, atlas_height as f32);
+        for glyph in glyphs.values_mut() {
+            let [left, top, right, bottom] = glyph.tex_coords;
+            glyph.tex_coords = [left / w, 1.0 - bottom / h, right / w, 1.0 - top / h];
+        }
+        let line_height = scaled.ascent() - scaled.descent() + scaled.line_gap();
+        (FontMetrics::new(glyphs, scaled.ascent(), line_height), pixels, (ATLAS_WIDTH, atlas_height))
+    }
+}
+
+impl HasTexture for Font {
+    type Texture = SrgbTexture2d;
+    fn get_texture(&self) -> &Self::Texture {
+        &self.atlas
+    }
+    fn get_texture_mut(&mut self) -> &mut Self::Texture {
+        &mut self.atlas
+    }
+}
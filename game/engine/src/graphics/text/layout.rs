@@ -0,0 +1,106 @@
+use crate::graphics::text::font::FontMetrics;
+
+/// Выравнивание строк по горизонтали
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Выравнивание всего текста по вертикали внутри Rect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerticalAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Символ, поставленный на своё место. x и y это левый верхний угол его картинки
+/// относительно левого верхнего угла области текста.
+#[derive(Debug, Clone, Copy)]
+pub struct PlacedGlyph {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub tex_coords: [f32; 4],
+}
+
+/// Разбивает текст на строки шириной не больше max_width (None значит без переносов).
+/// Переносы идут по пробелам, слишком длинное слово режется по символам.
+/// Символ \n всегда начинает новую строку.
+pub fn wrap_lines(metrics: &FontMetrics, text: &str, max_width: Option<f32>) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let max_width = match max_width {
+            Some(w) => w,
+            None => {
+                lines.push(paragraph.to_string());
+                continue;
+            }
+        };
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let candidate = if line.is_empty() {word.to_string()} else {format!("{line} {word}")};
+            if metrics.width_of(&candidate) <= max_width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                line.push(c);
+                if metrics.width_of(&line) > max_width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::take(&mut line));
+                    line.push(c);
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Раскладывает текст внутри области размера area (ширина, высота)
+pub fn layout(metrics: &FontMetrics, text: &str, area: (f32, f32), wrap: bool,
+              align: Align, vertical_align: VerticalAlign) -> Vec<PlacedGlyph> {
+    let (width, height) = area;
+    let lines = wrap_lines(metrics, text, if wrap {Some(width)} else {None});
+    let text_height = lines.len() as f32 * metrics.line_height();
+    let mut y = match vertical_align {
+        VerticalAlign::Top => 0.0,
+        VerticalAlign::Middle => (height - text_height) / 2.0,
+        VerticalAlign::Bottom => height - text_height,
+    };
+    let mut placed = Vec::new();
+    for line in lines {
+        let line_width = metrics.width_of(&line);
+        let mut x = match align {
+            Align::Left => 0.0,
+            Align::Center => (width - line_width) / 2.0,
+            Align::Right => width - line_width,
+        };
+        let baseline = y + metrics.ascent();
+        for c in line.chars() {
+            let glyph = match metrics.glyph(c) {
+                Some(g) => g,
+                None => continue,
+            };
+            if glyph.size.0 > 0.0 && glyph.size.1 > 0.0 {
+                placed.push(PlacedGlyph {
+                    x: (x + glyph.offset.0).round(),
+                    y: (baseline + glyph.offset.1).round(),
+                    width: glyph.size.0,
+                    height: glyph.size.1,
+                    tex_coords: glyph.tex_coords,
+                });
+            }
+            x += glyph.advance;
+        }
+        y += metrics.line_height();
+    }
+    placed
+}
@@ -0,0 +1,7 @@
+pub mod font;
+pub mod layout;
+pub mod text_obj;
+
+pub use font::{Font, FontError, FontMetrics};
+pub use layout::{Align, VerticalAlign};
+pub use text_obj::Text;
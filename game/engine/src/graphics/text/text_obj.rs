@@ -0,0 +1,101 @@
+use crate::Rect;
+use crate::rect::Rectangular;
+use crate::graphics::text::layout::{Align, VerticalAlign};
+use crate::graphics::traits::Layered;
+
+/// Текст, который рисуется внутри своего Rect.
+/// Как и у спрайтов, позиция Rect это левый нижний угол.
+#[derive(Debug, Clone)]
+pub struct Text {
+    content: String,
+    rect: Rect,
+    align: Align,
+    vertical_align: VerticalAlign,
+    wrap: bool,
+    color: [f32; 4],
+    outline: Option<([f32; 4], f32)>,
+    layer: u32,
+}
+
+impl Text {
+    /// Создаёт чёрный текст без обводки в области размера width x height
+    pub fn new<W: Into<f64>, H: Into<f64>>(content: &str, width: W, height: H) -> Self {
+        Text {
+            content: content.to_string(),
+            rect: Rect::from_scaled((width.into(), height.into()), 1.0),
+            align: Align::Left,
+            vertical_align: VerticalAlign::Top,
+            wrap: true,
+            color: [0.0, 0.0, 0.0, 1.0],
+            outline: None,
+            layer: 1,
+        }
+    }
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+    /// Меняет текст
+    pub fn set_content(&mut self, content: &str) {
+        self.content.clear();
+        self.content.push_str(content);
+    }
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+    pub fn with_vertical_align(mut self, vertical_align: VerticalAlign) -> Self {
+        self.vertical_align = vertical_align;
+        self
+    }
+    /// Переносить ли строки, которые не помещаются в ширину Rect
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+    /// Цвет текста в RGBA от 0 до 1
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+    /// Обводка указанного цвета и толщины в пикселях
+    pub fn with_outline(mut self, color: [f32; 4], thickness: f32) -> Self {
+        self.outline = Some((color, thickness));
+        self
+    }
+    pub fn align(&self) -> Align {
+        self.align
+    }
+    pub fn vertical_align(&self) -> VerticalAlign {
+        self.vertical_align
+    }
+    pub fn wrap(&self) -> bool {
+        self.wrap
+    }
+    pub fn color(&self) -> [f32; 4] {
+        self.color
+    }
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+    }
+    pub fn outline(&self) -> Option<([f32; 4], f32)> {
+        self.outline
+    }
+}
+
+impl Rectangular for Text {
+    fn get_rect(&self) -> &Rect {
+        &self.rect
+    }
+    fn get_rect_mut(&mut self) -> &mut Rect {
+        &mut self.rect
+    }
+}
+
+impl Layered for Text {
+    fn get_layer(&self) -> u32 {
+        self.layer
+    }
+    fn get_layer_mut(&mut self) -> &mut u32 {
+        &mut self.layer
+    }
+}
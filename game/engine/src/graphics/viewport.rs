@@ -0,0 +1,142 @@
+use cgmath::{Matrix4, Point2};
+use glium::glutin::dpi::{PhysicalPosition, PhysicalSize};
+use crate::Rect;
+
+/// Способ подгонки виртуального разрешения игры под реальный размер окна
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalePolicy {
+    /// Растягивает картинку на всё окно, пропорции могут исказиться
+    Stretch,
+    /// Сохраняет пропорции, а свободное место окна остаётся полосами по краям
+    Letterbox,
+    /// Масштаб как у Letterbox, но вместо полос показывается больше мира
+    Expand,
+}
+
+/// Область окна, в которую рисуется игра, и проекция для неё.
+/// Игра всегда думает, что экран имеет виртуальный размер, а Viewport
+/// переводит это в реальные пиксели окна в зависимости от ScalePolicy.
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    virtual_size: PhysicalSize<u32>,
+    window_size: PhysicalSize<u32>,
+    policy: ScalePolicy,
+    projection: [[f32; 4]; 4],
+    gl_rect: Option<glium::Rect>,
+}
+
+impl Viewport {
+    /// Создаёт Viewport для игры с виртуальным разрешением virtual_size в окне размера window_size
+    pub fn new(virtual_size: PhysicalSize<u32>, window_size: PhysicalSize<u32>,
+               policy: ScalePolicy) -> Self {
+        let mut viewport = Viewport {
+            virtual_size,
+            window_size,
+            policy,
+            projection: Viewport::ortho(0.0, virtual_size.width as f32,
+                                        virtual_size.height as f32, 0.0),
+            gl_rect: None,
+        };
+        viewport.recalculate();
+        viewport
+    }
+    /// Сообщает о новом размере окна. Свёрнутое окно (нулевой размер) игнорируется.
+    pub fn resize(&mut self, window_size: PhysicalSize<u32>) {
+        if window_size.width == 0 || window_size.height == 0 {
+            return;
+        }
+        self.window_size = window_size;
+        self.recalculate();
+    }
+    /// Меняет способ подгонки картинки под окно
+    pub fn set_policy(&mut self, policy: ScalePolicy) {
+        self.policy = policy;
+        self.recalculate();
+    }
+    pub fn policy(&self) -> ScalePolicy {
+        self.policy
+    }
+    pub fn virtual_size(&self) -> PhysicalSize<u32> {
+        self.virtual_size
+    }
+    pub fn window_size(&self) -> PhysicalSize<u32> {
+        self.window_size
+    }
+    /// Матрица проекции, которую нужно передавать в шейдер
+    pub fn projection(&self) -> [[f32; 4]; 4] {
+        self.projection
+    }
+    /// Часть окна, в которую нужно рисовать. None значит всё окно.
+    pub fn gl_rect(&self) -> Option<glium::Rect> {
+        self.gl_rect
+    }
+    /// Во сколько раз игровой пиксель больше пикселя окна по x и по y
+    pub fn scale(&self) -> (f64, f64) {
+        let sx = self.window_size.width as f64 / self.virtual_size.width as f64;
+        let sy = self.window_size.height as f64 / self.virtual_size.height as f64;
+        match self.policy {
+            ScalePolicy::Stretch => (sx, sy),
+            ScalePolicy::Letterbox | ScalePolicy::Expand => (sx.min(sy), sx.min(sy)),
+        }
+    }
+    /// Видимая часть мира в игровых координатах.
+    /// Как и у спрайтов, позиция это левый нижний угол.
+    pub fn visible_area(&self) -> Rect {
+        let (left, top) = self.expand_offset();
+        let width = self.virtual_size.width as f64 + 2.0 * left;
+        let height = self.virtual_size.height as f64 + 2.0 * top;
+        Rect::new(Point2::new(-left, height - top), PhysicalSize::new(width, height))
+    }
+    /// Переводит позицию в окне (например, позицию мыши) в игровые координаты
+    pub fn window_to_world(&self, position: PhysicalPosition<f64>) -> Point2<f64> {
+        let (sx, sy) = self.scale();
+        let (offset_x, offset_y) = match self.gl_rect {
+            Some(r) => (r.left as f64, (self.window_size.height - r.bottom - r.height) as f64),
+            None => (0.0, 0.0),
+        };
+        let (left, top) = self.expand_offset();
+        Point2::new((position.x - offset_x) / sx - left, (position.y - offset_y) / sy - top)
+    }
+    /// Сколько мира видно слева и сверху за пределами виртуального экрана (только для Expand)
+    fn expand_offset(&self) -> (f64, f64) {
+        if self.policy != ScalePolicy::Expand {
+            return (0.0, 0.0);
+        }
+        let (s, _) = self.scale();
+        let visible_width = self.window_size.width as f64 / s;
+        let visible_height = self.window_size.height as f64 / s;
+        ((visible_width - self.virtual_size.width as f64) / 2.0,
+         (visible_height - self.virtual_size.height as f64) / 2.0)
+    }
+    fn recalculate(&mut self) {
+        let width = self.virtual_size.width as f32;
+        let height = self.virtual_size.height as f32;
+        self.gl_rect = None;
+        match self.policy {
+            ScalePolicy::Stretch => {
+                self.projection = Viewport::ortho(0.0, width, height, 0.0);
+            }
+            ScalePolicy::Letterbox => {
+                let (s, _) = self.scale();
+                let w = (width as f64 * s).round() as u32;
+                let h = (height as f64 * s).round() as u32;
+                self.gl_rect = Some(glium::Rect {
+                    left: self.window_size.width.saturating_sub(w) / 2,
+                    bottom: self.window_size.height.saturating_sub(h) / 2,
+                    width: w,
+                    height: h,
+                });
+                self.projection = Viewport::ortho(0.0, width, height, 0.0);
+            }
+            ScalePolicy::Expand => {
+                let (left, top) = self.expand_offset();
+                let (left, top) = (left as f32, top as f32);
+                self.projection = Viewport::ortho(-left, width + left, height + top, -top);
+            }
+        }
+    }
+    fn ortho(left: f32, right: f32, bottom: f32, top: f32) -> [[f32; 4]; 4] {
+        let matrix: Matrix4<f32> = cgmath::ortho(left, right, bottom, top, -1.0, 1.0);
+        Into::<[[f32; 4]; 4]>::into(matrix)
+    }
+}
@@ -1,17 +1,149 @@
+/// Ссылка на элемент группы. В отличие от индекса она не портится, когда из группы
+/// убирают другие элементы. Если элемент убрали и на его место положили новый,
+/// старая ссылка на новый элемент не укажет: у места меняется поколение.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u32,
+}
+
+impl Handle {
+    /// Индекс элемента в группе. Он не меняется, пока элемент в группе
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Отложенные изменения группы. Пока группа обходится через call, менять её нельзя,
+/// поэтому новые элементы (например, снаряды) и удаления копятся здесь,
+/// а потом применяются через apply
+#[derive(Debug)]
+pub struct Commands<T> {
+    spawn: Vec<T>,
+    despawn: Vec<Handle>,
+}
+
+impl <T> Commands<T> {
+    pub fn new() -> Self {
+        Commands {spawn: Vec::new(), despawn: Vec::new()}
+    }
+    /// Добавить элемент в группу
+    pub fn spawn(&mut self, element: T) {
+        self.spawn.push(element);
+    }
+    /// Убрать элемент из группы
+    pub fn despawn(&mut self, handle: Handle) {
+        self.despawn.push(handle);
+    }
+    pub fn is_empty(&self) -> bool {
+        self.spawn.is_empty() && self.despawn.is_empty()
+    }
+    /// Забирает накопленные изменения: сначала удаления, потом новые элементы
+    pub(crate) fn take(&mut self) -> (Vec<Handle>, Vec<T>) {
+        (std::mem::take(&mut self.despawn), std::mem::take(&mut self.spawn))
+    }
+}
+
+impl <T> Default for Commands<T> {
+    fn default() -> Self {
+        Commands::new()
+    }
+}
+
 /// Группа для чего угодно
 /// Группы нужны для того, чтобы вызывать одну и ту же функцию для всех элементов
-/// А также эффективно хранить различные элементы, работать с ними
+/// А также эффективно хранить различные элементы, работать с ними.
+/// Убранные элементы оставляют после себя пустое место, поэтому индексы остальных
+/// не меняются, а пустые места занимают новые элементы.
 #[derive(Debug)]
 pub struct Group<T> {
     elements: Vec<Option<T>>,
+    /// Поколение каждого места, увеличивается, когда элемент оттуда убирают
+    generations: Vec<u32>,
+    /// Пустые места, которые можно занять
+    free: Vec<usize>,
 }
 impl <T> Group<T> {
     pub fn new() -> Group<T> {
-        Group {elements: Vec::new()}
+        Group {elements: Vec::new(), generations: Vec::new(), free: Vec::new()}
     }
     /// Создаёт группу из вектора элементов
     pub fn from(elements: Vec<T>) -> Group<T> {
-        Group {elements: elements.into_iter().map(|s| Some(s)).collect()}
+        let generations = vec![0; elements.len()];
+        Group {elements: elements.into_iter().map(|s| Some(s)).collect(), generations,
+               free: Vec::new()}
+    }
+    /// Кладёт элемент в группу и возвращает ссылку на него
+    pub fn insert(&mut self, element: T) -> Handle {
+        self.generations.resize(self.elements.len(), 0);
+        while let Some(index) = self.free.pop() {
+            if index < self.elements.len() && self.elements[index].is_none() {
+                self.elements[index] = Some(element);
+                return Handle {index, generation: self.generations[index]};
+            }
+        }
+        self.elements.push(Some(element));
+        self.generations.push(0);
+        Handle {index: self.elements.len() - 1, generation: 0}
+    }
+    /// Убирает элемент из группы и возвращает его. Остальные ссылки остаются рабочими
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        if !self.contains(handle) {
+            return None;
+        }
+        let element = self.elements[handle.index].take();
+        self.generations[handle.index] += 1;
+        self.free.push(handle.index);
+        element
+    }
+    /// Есть ли ещё в группе элемент, на который указывает ссылка
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.handle_of(handle.index) == Some(handle)
+    }
+    /// Ссылка на элемент под указанным индексом, если он есть
+    pub fn handle_of(&self, index: usize) -> Option<Handle> {
+        match self.elements.get(index) {
+            Some(Some(_)) => {
+                let generation = self.generations.get(index).copied().unwrap_or(0);
+                Some(Handle {index, generation})
+            }
+            _ => None,
+        }
+    }
+    /// Ссылки на все элементы группы
+    pub fn handles(&self) -> Vec<Handle> {
+        (0..self.elements.len()).filter_map(|i| self.handle_of(i)).collect()
+    }
+    /// Возвращает элемент по ссылке
+    pub fn get_by_handle(&self, handle: Handle) -> Option<&T> {
+        if !self.contains(handle) {
+            return None;
+        }
+        self.elements[handle.index].as_ref()
+    }
+    /// Возвращает изменяемый элемент по ссылке
+    pub fn get_by_handle_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        if !self.contains(handle) {
+            return None;
+        }
+        self.elements[handle.index].as_mut()
+    }
+    /// Применяет отложенные изменения и возвращает ссылки на новые элементы
+    pub fn apply(&mut self, commands: &mut Commands<T>) -> Vec<Handle> {
+        let (despawn, spawn) = commands.take();
+        for handle in despawn {
+            self.remove(handle);
+        }
+        spawn.into_iter().map(|e| self.insert(e)).collect()
+    }
+}
+
+impl <T> Storage<T> for Group<T> {
+    fn get_elements_mut(&mut self) -> &mut Vec<Option<T>> {
+        &mut self.elements
     }
 }
 
@@ -19,18 +151,36 @@ impl <T> SomeGroup<T> for Group<T> {
     fn get_elements(&self) -> &Vec<Option<T>> {
         &self.elements
     }
-    fn get_elements_mut(&mut self) -> &mut Vec<Option<T>> {
-        &mut self.elements
+    fn push(&mut self, element: T) {
+        self.insert(element);
+    }
+    fn append(&mut self, elements: Vec<T>) {
+        for element in elements {
+            self.insert(element);
+        }
+    }
+    fn take_el(&mut self, i: usize) -> Option<T> {
+        let handle = self.handle_of(i)?;
+        self.remove(handle)
+    }
+}
+
+pub(crate) mod storage {
+    /// Изменяемый доступ к вектору элементов группы. Трейт лежит в модуле, который
+    /// виден только внутри движка: если положить или убрать элемент в обход группы,
+    /// её ссылки и индекс имён перестанут совпадать с элементами.
+    /// Снаружи элементы меняются через ссылки, call и методы самих групп
+    pub trait Storage<T> {
+        fn get_elements_mut(&mut self) -> &mut Vec<Option<T>>;
     }
 }
 
-pub trait SomeGroup<T> {
+use storage::Storage;
+
+pub trait SomeGroup<T>: Storage<T> {
     /// Возвращает ссылку на вектор элементов. Не рекомендуется работать с ними напрямую,
     /// если только вы не делаете на основе группы новую структуру.
     fn get_elements(&self) -> &Vec<Option<T>>;
-    /// Возвращает изменяемую ссылку на вектор элементов. Не рекомендуется работать с ними напрямую,
-    /// если только вы не делаете на основе группы новую структуру.
-    fn get_elements_mut(&mut self) -> &mut Vec<Option<T>>;
     /// Резервирует некоторый размер для внутреннего вектора. Полезно для оптимизации.
     fn reserve(&mut self, additional: usize) {
         self.get_elements_mut().reserve(additional)
@@ -61,12 +211,10 @@ pub trait SomeGroup<T> {
             el.replace(element);
         }
     }
-    /// Забирает элемент из группы и возвращает его
+    /// Забирает элемент из группы и возвращает его.
+    /// На его месте остаётся пустое место, так что индексы других элементов не меняются
     fn take_el(&mut self, i: usize) -> Option<T> {
-        assert!(i < self.get_elements().len(), "Элемента с индексом {i} не существует");
-        let i_last = self.get_elements().len() -1;
-        self.get_elements_mut().swap(i, i_last);
-        self.get_elements_mut().pop().unwrap()
+        self.get_elements_mut().get_mut(i)?.take()
     }
     /// Возвращает неизменяемую ссылку на элемент под указанным индексом
     fn get(&self, i: usize) -> Option<&T> {
@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use cgmath::Point2;
+use glium::glutin::event::{ElementState, ModifiersState, MouseButton, MouseScrollDelta,
+                           VirtualKeyCode, WindowEvent};
+use crate::graphics::Viewport;
+
+/// Состояние клавиатуры и мыши.
+/// Нужно передавать сюда все WindowEvent через handle_event, а в конце каждого кадра
+/// вызывать end_frame, чтобы забыть нажатия, случившиеся в этом кадре.
+#[derive(Debug)]
+pub struct Input {
+    mouse_position: Point2<f64>,
+    buttons_down: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    buttons_released: HashSet<MouseButton>,
+    keys_down: HashSet<VirtualKeyCode>,
+    keys_pressed: HashSet<VirtualKeyCode>,
+    keys_released: HashSet<VirtualKeyCode>,
+    modifiers: ModifiersState,
+    scroll: f64,
+    text: String,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Input {
+            mouse_position: Point2::new(0.0, 0.0),
+            buttons_down: HashSet::new(),
+            buttons_pressed: HashSet::new(),
+            buttons_released: HashSet::new(),
+            keys_down: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            keys_released: HashSet::new(),
+            modifiers: ModifiersState::empty(),
+            scroll: 0.0,
+            text: String::new(),
+        }
+    }
+    /// Обрабатывает событие окна. Позиция мыши переводится в координаты
+    /// виртуального экрана через viewport.
+    pub fn handle_event(&mut self, event: &WindowEvent, viewport: &Viewport) {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.mouse_position = viewport.window_to_world(*position);
+            }
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => {
+                    self.buttons_down.insert(*button);
+                    self.buttons_pressed.insert(*button);
+                }
+                ElementState::Released => {
+                    self.buttons_down.remove(button);
+                    self.buttons_released.insert(*button);
+                }
+            },
+            WindowEvent::KeyboardInput { input, .. } => {
+                let key = match input.virtual_keycode {
+                    Some(k) => k,
+                    None => return,
+                };
+                match input.state {
+                    ElementState::Pressed => {
+                        // Зажатая клавиша повторяет событие, но нажатием это не считается
+                        if self.keys_down.insert(key) {
+                            self.keys_pressed.insert(key);
+                        }
+                    }
+                    ElementState::Released => {
+                        self.keys_down.remove(&key);
+                        self.keys_released.insert(key);
+                    }
+                }
+            }
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = *modifiers,
+            WindowEvent::MouseWheel { delta, .. } => match delta {
+                MouseScrollDelta::LineDelta(_, y) => self.scroll += *y as f64,
+                MouseScrollDelta::PixelDelta(p) => self.scroll += p.y / 100.0,
+            },
+            WindowEvent::ReceivedCharacter(c) if !c.is_control() => self.text.push(*c),
+            WindowEvent::Focused(false) => {
+                self.buttons_down.clear();
+                self.keys_down.clear();
+            }
+            _ => (),
+        }
+    }
+    /// Забывает всё, что случилось за кадр: нажатия, отпускания, прокрутку и введённый текст
+    pub fn end_frame(&mut self) {
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.scroll = 0.0;
+        self.text.clear();
+    }
+    /// Позиция мыши в координатах виртуального экрана (без учёта камеры)
+    pub fn mouse_position(&self) -> Point2<f64> {
+        self.mouse_position
+    }
+    /// Кнопка мыши зажата
+    pub fn is_button_down(&self, button: MouseButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+    /// Кнопку мыши нажали в этом кадре
+    pub fn is_button_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+    /// Кнопку мыши отпустили в этом кадре
+    pub fn is_button_released(&self, button: MouseButton) -> bool {
+        self.buttons_released.contains(&button)
+    }
+    /// Клавиша зажата
+    pub fn is_key_down(&self, key: VirtualKeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+    /// Клавишу нажали в этом кадре
+    pub fn is_key_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+    /// Клавишу отпустили в этом кадре
+    pub fn is_key_released(&self, key: VirtualKeyCode) -> bool {
+        self.keys_released.contains(&key)
+    }
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+    /// Прокрутка колёсика за кадр, в строках
+    pub fn scroll(&self) -> f64 {
+        self.scroll
+    }
+    /// Текст, введённый за кадр
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Input::new()
+    }
+}
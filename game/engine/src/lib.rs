@@ -2,18 +2,26 @@
 //!
 //! `engine` это движок игры, в нём содержаться различные модули игры, такие как
 //! `sounds`, `physics`, `graphics`, а также некоторые полезные функции
+pub mod ai;
 pub mod graphics;
 pub mod physics;
 pub mod sounds;
 pub mod rect;
+pub mod replay;
 pub mod time;
 pub mod component;
 pub mod unit;
 pub mod group;
+pub mod input;
 pub mod misc_traits;
+pub mod net;
 pub mod loader;
 pub mod programs;
 pub mod messaging;
+pub mod save;
+pub mod tilemap;
+pub mod turns;
+pub(crate) mod parse;
 
 pub use rect::Rect;
 
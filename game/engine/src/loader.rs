@@ -1,5 +1,5 @@
 use std::path::Path;
-use glium::texture::SrgbTexture2d;
+use glium::texture::{SrgbTexture2d, Texture2d};
 
 pub struct TextureLoader;
 
@@ -12,4 +12,13 @@ impl TextureLoader {
         ::from_raw_rgba_reversed(&img.into_raw(), img_dim);
         SrgbTexture2d::new(display, img).unwrap()
     }
-}
\ No newline at end of file
+    /// Загружает карту нормалей. В отличие от обычной текстуры её цвета
+    /// это направления, поэтому они не переводятся из sRGB
+    pub fn load_normal_map(path: &Path, display: &glium::Display) -> Texture2d {
+        let img = image::open(path).unwrap().to_rgba8();
+        let img_dim = img.dimensions();
+        let img = glium::texture::RawImage2d
+        ::from_raw_rgba_reversed(&img.into_raw(), img_dim);
+        Texture2d::new(display, img).unwrap()
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use crate::component::messages::ComponentMessage;
+use crate::component::traits::Composite;
+use crate::graphics::sprite::SpriteGroup;
+use crate::group::Handle;
+use crate::messaging::message::Message;
+use crate::messaging::traits::Messaging;
+
+/// Кому адресовано сообщение
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Address {
+    /// Спрайту с таким именем
+    Name(String),
+    /// Спрайту с такой ссылкой
+    Id(Handle),
+    /// Всем спрайтам, кроме отправителя
+    All,
+    /// Всем, кто подписан на тему, например "unit_died" или "turn_ended"
+    Topic(String),
+}
+
+/// Сообщение вместе с адресом и отправителем
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    /// Спрайт, который отправил сообщение. None, если его отправил не спрайт
+    pub from: Option<Handle>,
+    pub to: Address,
+    pub message: ComponentMessage,
+}
+
+/// Общая шина сообщений между спрайтами. Спрайты кладут сообщения в свой
+/// ящик через Sprite::send_to и Sprite::publish, а deliver после обновления группы
+/// разносит их компонентам спрайтов-получателей. Те обработают их при своём
+/// следующем обновлении.
+#[derive(Debug, Default)]
+pub struct MessageBus {
+    queue: Vec<Envelope>,
+    /// Спрайты, подписанные на каждую тему
+    subscriptions: HashMap<String, HashSet<Handle>>,
+    /// Слушатели тем, которые не спрайты: интерфейс, компьютерный противник
+    listeners: Vec<(String, Sender<Envelope>)>,
+}
+
+impl MessageBus {
+    pub fn new() -> Self {
+        MessageBus::default()
+    }
+    /// Отправляет сообщение не от имени спрайта
+    pub fn send(&mut self, to: Address, message: ComponentMessage) {
+        self.queue.push(Envelope {from: None, to, message});
+    }
+    /// Публикует сообщение на тему
+    pub fn publish(&mut self, topic: &str, message: ComponentMessage) {
+        self.send(Address::Topic(topic.to_string()), message);
+    }
+    /// Подписывает спрайт на тему
+    pub fn subscribe(&mut self, sprite: Handle, topic: &str) {
+        self.subscriptions.entry(topic.to_string()).or_default().insert(sprite);
+    }
+    /// Отписывает спрайт от темы
+    pub fn unsubscribe(&mut self, sprite: Handle, topic: &str) {
+        if let Some(subscribers) = self.subscriptions.get_mut(topic) {
+            subscribers.remove(&sprite);
+        }
+    }
+    /// Подписывает на тему кого-то, кроме спрайтов. Сообщения забираются через try_iter
+    pub fn listen(&mut self, topic: &str) -> Receiver<Envelope> {
+        let (sender, receiver) = channel();
+        self.listeners.push((topic.to_string(), sender));
+        receiver
+    }
+    /// Сколько сообщений ждёт доставки
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+    /// Забирает сообщения из ящиков спрайтов и доставляет все сообщения получателям.
+    /// Сообщения спрайтам, которых уже нет, пропадают. Возвращает число доставок
+    pub fn deliver(&mut self, sprites: &mut SpriteGroup) -> usize {
+        for handle in sprites.handles() {
+            let sprite = match sprites.get_by_handle_mut(handle) {
+                Some(s) => s,
+                None => continue,
+            };
+            for (to, message) in sprite.take_outbox() {
+                self.queue.push(Envelope {from: Some(handle), to, message});
+            }
+        }
+        let mut delivered = 0;
+        for envelope in std::mem::take(&mut self.queue) {
+            trace(&envelope);
+            let targets: Vec<Handle> = match &envelope.to {
+                Address::Name(name) => sprites.find_handle(name).into_iter().collect(),
+                Address::Id(handle) => vec![*handle],
+                Address::All => sprites.handles().into_iter()
+                    .filter(|h| Some(*h) != envelope.from)
+                    .collect(),
+                Address::Topic(topic) => {
+                    self.notify_listeners(topic, &envelope);
+                    self.subscriptions.get(topic)
+                        .map(|s| s.iter().copied().collect())
+                        .unwrap_or_default()
+                }
+            };
+            for target in targets {
+                let components = sprites.get_by_handle_mut(target)
+                    .and_then(|s| s.get_components_mut().as_mut());
+                if let Some(components) = components {
+                    let message = Message::ComponentMessage(envelope.message);
+                    if components.reveive_msg(message).is_ok() {
+                        components.handle_messages();
+                        delivered += 1;
+                    }
+                }
+            }
+        }
+        // Забываем подписки спрайтов, которых больше нет
+        for subscribers in self.subscriptions.values_mut() {
+            subscribers.retain(|h| sprites.contains(*h));
+        }
+        delivered
+    }
+    fn notify_listeners(&mut self, topic: &str, envelope: &Envelope) {
+        self.listeners.retain(|(t, sender)| {
+            t != topic || sender.send(envelope.clone()).is_ok()
+        });
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn trace(envelope: &Envelope) {
+    tracing::trace!(from = ?envelope.from, to = ?envelope.to, message = ?envelope.message,
+                    "доставка сообщения");
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace(_envelope: &Envelope) {}
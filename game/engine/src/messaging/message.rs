@@ -1,6 +1,15 @@
 use crate::component::messages::ComponentMessage;
+use crate::messaging::queue::Coalesce;
 
 #[derive(Debug, Clone, Copy)]
 pub enum Message {
     ComponentMessage(ComponentMessage)
-}
\ No newline at end of file
+}
+
+impl Coalesce for Message {
+    fn coalesce(&mut self, other: &Self) -> bool {
+        match (self, other) {
+            (Message::ComponentMessage(a), Message::ComponentMessage(b)) => a.coalesce(b),
+        }
+    }
+}
@@ -1,2 +1,4 @@
+pub mod bus;
+pub mod queue;
 pub mod traits;
 pub mod message;
\ No newline at end of file
@@ -0,0 +1,276 @@
+use std::fmt;
+
+/// Сообщения, которые можно сливать в одно, пока они ждут в очереди.
+/// Например две силы PhysicsMessage, пришедшие в одном кадре, можно сложить
+pub trait Coalesce {
+    /// Пытается добавить other к self. Возвращает true, если получилось,
+    /// тогда other в очередь уже не попадает
+    fn coalesce(&mut self, _other: &Self) -> bool {
+        false
+    }
+}
+
+/// Очередь переполнена, сообщение в неё не попало и возвращается обратно
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueOverflow<T>(pub T);
+
+impl <T> fmt::Display for QueueOverflow<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "очередь сообщений переполнена")
+    }
+}
+
+impl <T: fmt::Debug> std::error::Error for QueueOverflow<T> {}
+
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    message: T,
+    priority: i32,
+    /// Время очереди, начиная с которого сообщение можно забрать
+    ready_at: u64,
+    /// Порядок добавления, чтобы сообщения с одинаковым приоритетом шли по очереди
+    order: u64,
+}
+
+/// Содержимое очереди. Лежит отдельно в куче, чтобы очереди
+/// не раздували размер компонентов
+#[derive(Debug, Clone)]
+struct QueueState<T> {
+    entries: Vec<Entry<T>>,
+    now: u64,
+    next_order: u64,
+    capacity: Option<usize>,
+    dropped: usize,
+    coalescing: bool,
+}
+
+/// Очередь сообщений. Сообщение можно отложить на сколько-то миллисекунд
+/// и дать ему приоритет: первым забирается готовое сообщение с наибольшим приоритетом,
+/// а из равных - добавленное раньше. Время очереди двигается через advance.
+/// У очереди может быть наибольший размер, тогда лишние сообщения не добавляются.
+#[derive(Debug, Clone)]
+pub struct MessageQueue<T> {
+    state: Box<QueueState<T>>,
+}
+
+impl <T: Coalesce> MessageQueue<T> {
+    /// Очередь без ограничения размера
+    pub fn new() -> Self {
+        MessageQueue {
+            state: Box::new(QueueState {
+                entries: Vec::new(),
+                now: 0,
+                next_order: 0,
+                capacity: None,
+                dropped: 0,
+                coalescing: true,
+            }),
+        }
+    }
+    /// Очередь, в которой не бывает больше capacity сообщений
+    pub fn bounded(capacity: usize) -> Self {
+        let mut queue = MessageQueue::new();
+        queue.state.capacity = Some(capacity);
+        queue
+    }
+    /// Включает или выключает слияние сообщений
+    pub fn with_coalescing(mut self, coalescing: bool) -> Self {
+        self.state.coalescing = coalescing;
+        self
+    }
+    /// Добавляет сообщение, которое можно забрать сразу
+    pub fn add(&mut self, message: T) -> Result<(), QueueOverflow<T>> {
+        self.schedule(message, 0, 0)
+    }
+    /// Добавляет сообщение с приоритетом
+    pub fn add_with_priority(&mut self, message: T, priority: i32) -> Result<(), QueueOverflow<T>> {
+        self.schedule(message, 0, priority)
+    }
+    /// Добавляет сообщение, которое можно будет забрать через delay миллисекунд.
+    /// Сообщение с тем же приоритетом и временем сливается с уже ждущим, если может
+    pub fn schedule(&mut self, message: T, delay: u32, priority: i32)
+        -> Result<(), QueueOverflow<T>>
+    {
+        let ready_at = self.state.now + delay as u64;
+        if self.state.coalescing {
+            let same = self.state.entries.iter_mut()
+                .filter(|e| e.ready_at == ready_at && e.priority == priority);
+            for entry in same {
+                if entry.message.coalesce(&message) {
+                    return Ok(());
+                }
+            }
+        }
+        if let Some(capacity) = self.state.capacity {
+            if self.state.entries.len() >= capacity {
+                self.state.dropped += 1;
+                return Err(QueueOverflow(message));
+            }
+        }
+        self.state.entries.push(Entry {message, priority, ready_at, order: self.state.next_order});
+        self.state.next_order += 1;
+        Ok(())
+    }
+    /// Забирает готовое сообщение с наибольшим приоритетом
+    pub fn remove(&mut self) -> Option<T> {
+        let now = self.state.now;
+        let index = self.state.entries.iter().enumerate()
+            .filter(|(_, e)| e.ready_at <= now)
+            .max_by(|(_, a), (_, b)| a.priority.cmp(&b.priority).then(b.order.cmp(&a.order)))
+            .map(|(i, _)| i)?;
+        Some(self.state.entries.remove(index).message)
+    }
+    /// Сколько сообщений уже можно забрать
+    pub fn size(&self) -> usize {
+        self.state.entries.iter().filter(|e| e.ready_at <= self.state.now).count()
+    }
+    /// Сколько всего сообщений в очереди, вместе с отложенными
+    pub fn len(&self) -> usize {
+        self.state.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.state.entries.is_empty()
+    }
+    /// Двигает время очереди на dt миллисекунд
+    pub fn advance(&mut self, dt: u32) {
+        self.state.now += dt as u64;
+    }
+    pub fn capacity(&self) -> Option<usize> {
+        self.state.capacity
+    }
+    /// Сколько сообщений не попало в очередь из-за переполнения
+    pub fn dropped(&self) -> usize {
+        self.state.dropped
+    }
+    pub fn clear(&mut self) {
+        self.state.entries.clear();
+    }
+}
+
+impl <T: Coalesce> Default for MessageQueue<T> {
+    fn default() -> Self {
+        MessageQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::messages::{ComponentMessage, CustomMessage};
+    use crate::physics::messages::PhysicsMessage;
+
+    fn custom(value: f64) -> ComponentMessage {
+        ComponentMessage::Custom(CustomMessage::new("test", value))
+    }
+
+    fn force(x: f64, y: f64) -> ComponentMessage {
+        ComponentMessage::PhysicsMessage(PhysicsMessage::apply_force(x, y))
+    }
+
+    /// Значения всех готовых CustomMessage в порядке, в котором их отдаёт очередь
+    fn drain(queue: &mut MessageQueue<ComponentMessage>) -> Vec<f64> {
+        std::iter::from_fn(|| queue.remove()).map(|m| match m {
+            ComponentMessage::Custom(c) => c.value,
+            other => panic!("{other:?}"),
+        }).collect()
+    }
+
+    fn forces(queue: &mut MessageQueue<ComponentMessage>) -> Vec<(f64, f64)> {
+        std::iter::from_fn(|| queue.remove()).map(|m| match m {
+            ComponentMessage::PhysicsMessage(p) => p.force,
+            other => panic!("{other:?}"),
+        }).collect()
+    }
+
+    #[test]
+    fn scheduled_message_is_ready_after_its_delay() {
+        let mut queue = MessageQueue::new();
+        queue.schedule(custom(1.0), 100, 0).unwrap();
+        queue.add(custom(2.0)).unwrap();
+        assert_eq!((queue.size(), queue.len()), (1, 2));
+        assert_eq!(drain(&mut queue), vec![2.0]);
+        queue.advance(99);
+        assert!(queue.remove().is_none());
+        queue.advance(1);
+        assert_eq!(queue.size(), 1);
+        assert_eq!(drain(&mut queue), vec![1.0]);
+        assert!(queue.is_empty());
+        // Задержка считается от текущего времени очереди
+        queue.schedule(custom(3.0), 50, 0).unwrap();
+        queue.advance(49);
+        assert_eq!(queue.size(), 0);
+        queue.advance(1);
+        assert_eq!(drain(&mut queue), vec![3.0]);
+    }
+
+    #[test]
+    fn higher_priority_goes_first_and_equals_keep_order() {
+        let mut queue = MessageQueue::new();
+        queue.add_with_priority(custom(1.0), 0).unwrap();
+        queue.add_with_priority(custom(2.0), 5).unwrap();
+        queue.add_with_priority(custom(3.0), 0).unwrap();
+        queue.add_with_priority(custom(4.0), 5).unwrap();
+        queue.add_with_priority(custom(5.0), -1).unwrap();
+        // Отложенное сообщение с большим приоритетом не обгоняет готовые
+        queue.schedule(custom(6.0), 10, 9).unwrap();
+        assert_eq!(drain(&mut queue), vec![2.0, 4.0, 1.0, 3.0, 5.0]);
+        queue.advance(10);
+        assert_eq!(drain(&mut queue), vec![6.0]);
+    }
+
+    #[test]
+    fn simultaneous_forces_are_summed() {
+        let mut queue = MessageQueue::new();
+        queue.add(force(1.0, 2.0)).unwrap();
+        queue.add(force(3.0, -5.0)).unwrap();
+        queue.add(force(0.5, 0.0)).unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(forces(&mut queue), vec![(4.5, -3.0)]);
+        // Без слияния каждая сила остаётся отдельным сообщением
+        let mut queue = MessageQueue::new().with_coalescing(false);
+        queue.add(force(1.0, 2.0)).unwrap();
+        queue.add(force(3.0, -5.0)).unwrap();
+        assert_eq!(forces(&mut queue), vec![(1.0, 2.0), (3.0, -5.0)]);
+    }
+
+    #[test]
+    fn forces_with_other_time_or_priority_are_not_summed() {
+        let mut queue = MessageQueue::new();
+        queue.add(force(1.0, 0.0)).unwrap();
+        queue.schedule(force(2.0, 0.0), 10, 0).unwrap();
+        queue.add_with_priority(force(4.0, 0.0), 1).unwrap();
+        queue.add(custom(7.0)).unwrap();
+        queue.add(force(8.0, 0.0)).unwrap();
+        assert_eq!(queue.len(), 4);
+        queue.advance(10);
+        let order: Vec<f64> = std::iter::from_fn(|| queue.remove()).map(|m| match m {
+            ComponentMessage::PhysicsMessage(p) => p.force.0,
+            ComponentMessage::Custom(c) => c.value,
+            other => panic!("{other:?}"),
+        }).collect();
+        assert_eq!(order, vec![4.0, 9.0, 2.0, 7.0]);
+    }
+
+    #[test]
+    fn bounded_queue_returns_overflowing_message() {
+        let mut queue = MessageQueue::bounded(2);
+        assert_eq!(queue.capacity(), Some(2));
+        queue.add(custom(1.0)).unwrap();
+        queue.schedule(custom(2.0), 100, 0).unwrap();
+        match queue.add(custom(3.0)) {
+            Err(QueueOverflow(ComponentMessage::Custom(c))) => assert_eq!(c.value, 3.0),
+            other => panic!("{other:?}"),
+        }
+        assert!(queue.add(custom(4.0)).is_err());
+        assert_eq!(queue.dropped(), 2);
+        // Слияние места не занимает, поэтому сила проходит и в полную очередь
+        let mut queue = MessageQueue::bounded(1);
+        queue.add(force(1.0, 0.0)).unwrap();
+        queue.add(force(1.0, 0.0)).unwrap();
+        assert_eq!(queue.dropped(), 0);
+        assert_eq!(forces(&mut queue), vec![(2.0, 0.0)]);
+        // После того как сообщение забрали, место снова есть
+        queue.add(custom(5.0)).unwrap();
+        assert_eq!(drain(&mut queue), vec![5.0]);
+    }
+}
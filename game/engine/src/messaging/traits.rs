@@ -1,29 +1,56 @@
 use std::fmt::Debug;
-use queues::{IsQueue, Queue};
+use crate::messaging::queue::{Coalesce, MessageQueue, QueueOverflow};
 
 pub trait Messaging {
-    type Message: Clone + Copy + Debug;
+    type Message: Clone + Copy + Debug + Coalesce;
     /// Возвращает изменяемую ссылку на входящие сообщения
-    fn input_messages(&mut self) -> &mut Queue<Self::Message>;
+    fn input_messages(&mut self) -> &mut MessageQueue<Self::Message>;
     /// Возвращает изменяемую ссылку на исходящие сообщения
-    fn output_messages(&mut self) -> &mut Queue<Self::Message>;
-    /// Получает сообщение
-    fn reveive_msg(&mut self, message:Self::Message) {
-        self.input_messages().add(message).unwrap();
+    fn output_messages(&mut self) -> &mut MessageQueue<Self::Message>;
+    /// Получает сообщение. Если очередь переполнена, сообщение возвращается в ошибке
+    fn reveive_msg(&mut self, message:Self::Message) -> Result<(), QueueOverflow<Self::Message>> {
+        let result = self.input_messages().add(message);
+        report_overflow(&result);
+        result
     }
-    /// Отправляет сообщение
-    fn send_msg(&mut self, message:Self::Message) {
-        self.output_messages().add(message).unwrap();
+    /// Отправляет сообщение. Если очередь переполнена, сообщение возвращается в ошибке
+    fn send_msg(&mut self, message:Self::Message) -> Result<(), QueueOverflow<Self::Message>> {
+        let result = self.output_messages().add(message);
+        report_overflow(&result);
+        result
     }
-    /// Обрабатывает все входящие сообщения
+    /// Отправляет сообщение, которое уйдёт через delay миллисекунд.
+    /// Из готовых сообщений первыми уходят те, у которых priority больше
+    fn send_msg_delayed(&mut self, message: Self::Message, delay: u32, priority: i32)
+        -> Result<(), QueueOverflow<Self::Message>>
+    {
+        let result = self.output_messages().schedule(message, delay, priority);
+        report_overflow(&result);
+        result
+    }
+    /// Двигает время очередей сообщений, чтобы отложенные сообщения становились готовыми
+    fn advance_messages(&mut self, dt: u32) {
+        self.input_messages().advance(dt);
+        self.output_messages().advance(dt);
+    }
+    /// Обрабатывает все готовые входящие сообщения
     fn handle_messages(&mut self) {
-        let msg_len = self.input_messages().size();
-        for i in 0..msg_len {
-            let oldest_message = self.input_messages().remove().unwrap();
-            println!("{:?}", oldest_message);
+        while let Some(oldest_message) = self.input_messages().remove() {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(message = ?oldest_message, "обработка сообщения");
             self.handle_message(oldest_message);
         }
     }
     /// Обрабатывает входящее сообщение
     fn handle_message(&mut self, message: Self::Message);
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "tracing")]
+fn report_overflow<T: Debug>(result: &Result<(), QueueOverflow<T>>) {
+    if let Err(QueueOverflow(message)) = result {
+        tracing::warn!(?message, "очередь сообщений переполнена, сообщение потеряно");
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+fn report_overflow<T: Debug>(_result: &Result<(), QueueOverflow<T>>) {}
@@ -0,0 +1,111 @@
+use std::io;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use crate::net::packet::Packet;
+
+/// Сколько ждать соединения
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Соединение с другим игроком. Сообщения это строки текста, по одной на сообщение.
+/// Сокет неблокирующий: send только копит данные, а отправляются и читаются
+/// они в poll, который нужно звать каждый кадр
+#[derive(Debug)]
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    closed: bool,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        // Сообщения маленькие, ждать, пока их наберётся на целый пакет, незачем
+        stream.set_nodelay(true)?;
+        Ok(Connection {stream, incoming: Vec::new(), outgoing: Vec::new(), closed: false})
+    }
+    /// Подключается к адресу
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "адрес не найден");
+        for addr in addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+                Ok(stream) => return Connection::new(stream),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.stream.peer_addr().ok()
+    }
+    /// Закрыто ли соединение с той или с этой стороны
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+    /// Ставит сообщение в очередь на отправку
+    pub fn send(&mut self, packet: &Packet) {
+        if !self.closed {
+            self.outgoing.extend_from_slice(packet.to_string().as_bytes());
+            self.outgoing.push(b'\n');
+        }
+    }
+    /// Отправляет накопленное и возвращает пришедшие сообщения.
+    /// Сообщения, которые не получилось разобрать, пропускаются
+    pub fn poll(&mut self) -> Vec<Packet> {
+        self.flush();
+        let mut buffer = [0u8; 4096];
+        while !self.closed {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => self.closed = true,
+                Ok(n) => self.incoming.extend_from_slice(&buffer[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => self.closed = true,
+            }
+        }
+        let mut packets = Vec::new();
+        while let Some(end) = self.incoming.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.incoming.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            match Packet::parse(&line) {
+                Some(packet) => packets.push(packet),
+                None => report_bad_packet(&line),
+            }
+        }
+        packets
+    }
+    /// Отправляет всё, что можно отправить, не дожидаясь сети
+    pub fn flush(&mut self) {
+        while !self.closed && !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => self.closed = true,
+                Ok(n) => {
+                    self.outgoing.drain(..n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => self.closed = true,
+            }
+        }
+    }
+    /// Отправляет всё накопленное, дожидаясь сети не дольше timeout, и закрывает соединение
+    pub fn close(&mut self, timeout: Duration) {
+        let _ = self.stream.set_nonblocking(false);
+        let _ = self.stream.set_write_timeout(Some(timeout));
+        if !self.closed {
+            let _ = self.stream.write_all(&self.outgoing);
+        }
+        self.outgoing.clear();
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+        self.closed = true;
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn report_bad_packet(line: &str) {
+    tracing::warn!(line, "не удалось разобрать сообщение");
+}
+
+#[cfg(not(feature = "tracing"))]
+fn report_bad_packet(_line: &str) {}
@@ -0,0 +1,9 @@
+//! Игра по сети. Игроки обмениваются только своими действиями, а мир
+//! у каждого считается сам (см. replay), поэтому по сети ходит очень мало данных.
+pub mod connection;
+pub mod packet;
+pub mod session;
+
+pub use connection::Connection;
+pub use packet::{Packet, PlayerId, PROTOCOL_VERSION};
+pub use session::{LockstepSession, NetEvent};
@@ -0,0 +1,101 @@
+use std::fmt;
+use crate::replay::InputAction;
+
+/// Версия протокола. Игры с разными версиями друг с другом не играют
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Номер игрока в матче. У того, кто создал матч, номер 0
+pub type PlayerId = usize;
+
+/// Сообщение между игроками. Передаётся одной строкой текста
+#[derive(Debug, Clone, PartialEq)]
+pub enum Packet {
+    /// Игрок хочет присоединиться к матчу
+    Hello { version: u32, name: String },
+    /// Игрок потерял связь и вернулся, tick это первый шаг, которого у него нет
+    Resume { version: u32, player: PlayerId, tick: u64 },
+    /// Ответ на Hello и Resume: номер игрока и настройки симуляции
+    Welcome { player: PlayerId, seed: u64, step: u32, delay: u64 },
+    /// К матчу присоединился игрок
+    Joined { player: PlayerId, name: String },
+    /// Все собрались, матч начинается
+    Start { players: Vec<PlayerId> },
+    /// Действия игрока на шаге tick. Пустой список тоже нужно отправлять
+    Input { player: PlayerId, tick: u64, actions: Vec<InputAction> },
+    /// Контрольная сумма мира игрока после шага tick
+    Checksum { player: PlayerId, tick: u64, value: u64 },
+    /// Игрок ушёл, его действий нет начиная с шага tick
+    Leave { player: PlayerId, tick: u64 },
+    /// В матч не пустили
+    Reject { reason: String },
+}
+
+impl Packet {
+    /// Читает сообщение из строки, записанной через Display
+    pub fn parse(line: &str) -> Option<Packet> {
+        let (kind, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let mut words = rest.split_whitespace();
+        let mut next = || words.next();
+        Some(match kind {
+            "hello" => {
+                let (version, name) = rest.split_once(' ')?;
+                Packet::Hello {version: version.parse().ok()?, name: name.trim().to_string()}
+            }
+            "resume" => Packet::Resume {version: next()?.parse().ok()?,
+                                        player: next()?.parse().ok()?,
+                                        tick: next()?.parse().ok()?},
+            "welcome" => Packet::Welcome {player: next()?.parse().ok()?,
+                                          seed: next()?.parse().ok()?,
+                                          step: next()?.parse().ok()?,
+                                          delay: next()?.parse().ok()?},
+            "joined" => {
+                let (player, name) = rest.split_once(' ')?;
+                Packet::Joined {player: player.parse().ok()?, name: name.trim().to_string()}
+            }
+            "start" => Packet::Start {
+                players: rest.split(',').map(str::trim).filter(|p| !p.is_empty())
+                    .map(|p| p.parse().ok())
+                    .collect::<Option<Vec<_>>>()?,
+            },
+            "input" => {
+                let player = next()?.parse().ok()?;
+                let tick = next()?.parse().ok()?;
+                // Действия идут после номера шага через '|'
+                let actions = rest.splitn(3, ' ').nth(2).unwrap_or("")
+                    .split('|').map(str::trim).filter(|a| !a.is_empty())
+                    .map(InputAction::parse)
+                    .collect::<Option<Vec<_>>>()?;
+                Packet::Input {player, tick, actions}
+            }
+            "checksum" => Packet::Checksum {player: next()?.parse().ok()?,
+                                            tick: next()?.parse().ok()?,
+                                            value: u64::from_str_radix(next()?, 16).ok()?},
+            "leave" => Packet::Leave {player: next()?.parse().ok()?, tick: next()?.parse().ok()?},
+            "reject" => Packet::Reject {reason: rest.trim().to_string()},
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::Hello { version, name } => write!(f, "hello {version} {name}"),
+            Packet::Resume { version, player, tick } => write!(f, "resume {version} {player} {tick}"),
+            Packet::Welcome { player, seed, step, delay } =>
+                write!(f, "welcome {player} {seed} {step} {delay}"),
+            Packet::Joined { player, name } => write!(f, "joined {player} {name}"),
+            Packet::Start { players } => {
+                let players: Vec<String> = players.iter().map(|p| p.to_string()).collect();
+                write!(f, "start {}", players.join(","))
+            }
+            Packet::Input { player, tick, actions } => {
+                let actions: Vec<String> = actions.iter().map(|a| a.to_string()).collect();
+                write!(f, "input {player} {tick} {}", actions.join("|"))
+            }
+            Packet::Checksum { player, tick, value } => write!(f, "checksum {player} {tick} {value:016x}"),
+            Packet::Leave { player, tick } => write!(f, "leave {player} {tick}"),
+            Packet::Reject { reason } => write!(f, "reject {reason}"),
+        }
+    }
+}
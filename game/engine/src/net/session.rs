@@ -0,0 +1,671 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use crate::net::connection::Connection;
+use crate::net::packet::{Packet, PlayerId, PROTOCOL_VERSION};
+use crate::replay::InputAction;
+
+/// Через сколько шагов начинают действовать действия игрока. Пока они идут
+/// по сети, у остальных игроков симуляция не стоит
+const DEFAULT_DELAY: u64 = 4;
+/// Сколько шагов истории действий хранится для вернувшихся игроков
+const HISTORY_TICKS: u64 = 3600;
+/// Как часто пытаться переподключиться
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// Сколько ждать ухода при закрытии соединения
+const CLOSE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Что случилось в сетевом матче
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetEvent {
+    /// Нас приняли в матч под номером player
+    Joined { player: PlayerId },
+    /// К матчу присоединился игрок
+    PlayerJoined { player: PlayerId, name: String },
+    /// Все собрались, симуляцию можно начинать
+    Started { players: Vec<PlayerId> },
+    /// С игроком пропала связь. Симуляция стоит, пока он не вернётся
+    PlayerLost { player: PlayerId },
+    /// Игрок вернулся
+    Reconnected { player: PlayerId },
+    /// Игрок ушёл или так и не вернулся. Дальше матч идёт без него
+    PlayerLeft { player: PlayerId },
+    /// Пропала связь с создателем матча, идут попытки переподключиться
+    Disconnected,
+    /// Связь с создателем матча так и не вернулась, матч окончен
+    HostLost,
+    /// В матч не пустили
+    Rejected { reason: String },
+    /// Мир игрока player после шага tick не такой, как у нас
+    Desync { tick: u64, player: PlayerId, ours: u64, theirs: u64 },
+}
+
+/// Место игрока у создателя матча
+#[derive(Debug)]
+struct Slot {
+    name: String,
+    connection: Option<Connection>,
+    /// Когда пропала связь с игроком
+    lost_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+enum Role {
+    /// Создатель матча. Принимает игроков и пересылает всем сообщения каждого
+    Host {
+        listener: TcpListener,
+        expected_players: usize,
+        /// Соединения, которые ещё не представились
+        pending: Vec<Option<Connection>>,
+        slots: Vec<Slot>,
+    },
+    /// Игрок, который присоединился к чужому матчу
+    Client {
+        addr: SocketAddr,
+        name: String,
+        connection: Option<Connection>,
+        lost_at: Option<Instant>,
+        last_attempt: Instant,
+        /// Подключение идёт в своём потоке, чтобы кадр не ждал его до CONNECT_TIMEOUT
+        reconnecting: Option<JoinHandle<io::Result<Connection>>>,
+    },
+}
+
+/// Сетевой матч по схеме lockstep: по сети ходят только действия игроков,
+/// а мир у каждого считается сам одной и той же детерминированной симуляцией
+/// (см. replay). Шаг симуляции делается, только когда известны действия всех
+/// игроков на этот шаг. После шага игроки обмениваются контрольными суммами мира,
+/// чтобы заметить рассинхронизацию.
+///
+/// Используется TCP: действия должны дойти все и по порядку, а задержка
+/// в пошаговой игре не так важна. Все соединяются с создателем матча,
+/// а он пересылает сообщения остальным.
+///
+/// Каждый кадр нужно звать poll, а на каждом шаге симуляции next_tick
+/// и после обновления мира end_tick.
+#[derive(Debug)]
+pub struct LockstepSession {
+    role: Role,
+    local: Option<PlayerId>,
+    seed: u64,
+    step: u32,
+    delay: u64,
+    started: bool,
+    /// Следующий шаг симуляции
+    tick: u64,
+    /// Следующий шаг, на который мы ещё не отправили свои действия
+    submitted: u64,
+    /// Игроки матча и шаг, начиная с которого их уже нет
+    players: BTreeMap<PlayerId, Option<u64>>,
+    inputs: BTreeMap<u64, BTreeMap<PlayerId, Vec<InputAction>>>,
+    checksums: BTreeMap<u64, BTreeMap<PlayerId, u64>>,
+    reconnect_timeout: Duration,
+    events: Vec<NetEvent>,
+}
+
+impl LockstepSession {
+    /// Создаёт матч на addr и ждёт, пока соберутся expected_players игроков вместе с нами.
+    /// seed это зерно генератора случайных чисел матча, step это шаг симуляции
+    pub fn host<A: ToSocketAddrs>(addr: A, name: &str, expected_players: usize,
+                                  seed: u64, step: u32) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let slots = vec![Slot {name: name.to_string(), connection: None, lost_at: None}];
+        let mut session = LockstepSession::new(Role::Host {
+            listener,
+            expected_players: expected_players.max(1),
+            pending: Vec::new(),
+            slots,
+        });
+        session.local = Some(0);
+        session.seed = seed;
+        session.step = step.max(1);
+        session.players.insert(0, None);
+        session.events.push(NetEvent::Joined {player: 0});
+        session.start_if_ready();
+        Ok(session)
+    }
+    /// Присоединяется к матчу на addr
+    pub fn join<A: ToSocketAddrs>(addr: A, name: &str) -> io::Result<Self> {
+        let mut connection = Connection::connect(&addr)?;
+        let addr = connection.peer_addr()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "нет адреса"))?;
+        connection.send(&Packet::Hello {version: PROTOCOL_VERSION, name: name.to_string()});
+        Ok(LockstepSession::new(Role::Client {
+            addr,
+            name: name.to_string(),
+            connection: Some(connection),
+            lost_at: None,
+            last_attempt: Instant::now(),
+            reconnecting: None,
+        }))
+    }
+    fn new(role: Role) -> Self {
+        LockstepSession {
+            role,
+            local: None,
+            seed: 0,
+            step: 16,
+            delay: DEFAULT_DELAY,
+            started: false,
+            tick: 0,
+            submitted: 0,
+            players: BTreeMap::new(),
+            inputs: BTreeMap::new(),
+            checksums: BTreeMap::new(),
+            reconnect_timeout: Duration::from_secs(30),
+            events: Vec::new(),
+        }
+    }
+    /// Сколько ждать ушедшего игрока, прежде чем продолжить матч без него
+    pub fn with_reconnect_timeout(mut self, timeout: Duration) -> Self {
+        self.reconnect_timeout = timeout;
+        self
+    }
+    /// Через сколько шагов начинают действовать действия. Создатель матча
+    /// сообщает свою задержку остальным
+    pub fn with_delay(mut self, delay: u64) -> Self {
+        self.delay = delay;
+        self
+    }
+    /// Наш номер в матче. None, пока нас не приняли
+    pub fn local_player(&self) -> Option<PlayerId> {
+        self.local
+    }
+    pub fn is_host(&self) -> bool {
+        matches!(self.role, Role::Host {..})
+    }
+    /// Адрес, на котором создатель матча ждёт игроков. Нужен, если порт выбрала система,
+    /// как при адресе "127.0.0.1:0". У остальных игроков None
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match &self.role {
+            Role::Host { listener, .. } => listener.local_addr().ok(),
+            Role::Client { .. } => None,
+        }
+    }
+    /// Зерно генератора случайных чисел матча
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// Шаг симуляции в миллисекундах
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+    /// Следующий шаг симуляции
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+    /// Игроки, которые сейчас в матче
+    pub fn players(&self) -> Vec<PlayerId> {
+        self.players.iter().filter(|(_, left)| left.is_none()).map(|(p, _)| *p).collect()
+    }
+    /// Отправляет и получает сообщения, принимает игроков и переподключается.
+    /// Возвращает всё, что случилось с прошлого вызова
+    pub fn poll(&mut self) -> Vec<NetEvent> {
+        // Для ещё не представившихся соединений вместо номера игрока их индекс в pending
+        let mut packets = Vec::new();
+        let mut greeted = HashMap::new();
+        match &mut self.role {
+            Role::Host { listener, pending, slots, .. } => {
+                while let Ok((stream, _)) = listener.accept() {
+                    if let Ok(connection) = Connection::new(stream) {
+                        pending.push(Some(connection));
+                    }
+                }
+                for (i, connection) in pending.iter_mut().enumerate() {
+                    for packet in connection.iter_mut().flat_map(|c| c.poll()) {
+                        packets.push((Err(i), packet));
+                    }
+                }
+                for (player, slot) in slots.iter_mut().enumerate() {
+                    for packet in slot.connection.iter_mut().flat_map(|c| c.poll()) {
+                        packets.push((Ok(player), packet));
+                    }
+                }
+            }
+            Role::Client { connection: Some(connection), .. } => {
+                for packet in connection.poll() {
+                    packets.push((Ok(0), packet));
+                }
+            }
+            Role::Client { .. } => (),
+        }
+        for (from, packet) in packets {
+            match from {
+                Ok(player) => self.handle_packet(player, packet),
+                Err(i) => {
+                    let connection = match &mut self.role {
+                        Role::Host { pending, .. } => pending.get_mut(i).and_then(Option::take),
+                        Role::Client { .. } => None,
+                    };
+                    match connection {
+                        Some(connection) => {
+                            if let Some(player) = self.handle_greeting(connection, packet) {
+                                greeted.insert(i, player);
+                            }
+                        }
+                        // Соединение уже представилось, и это его следующие сообщения
+                        None => {
+                            if let Some(player) = greeted.get(&i) {
+                                self.handle_packet(*player, packet);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self.check_connections();
+        self.prune();
+        std::mem::take(&mut self.events)
+    }
+    /// Действия всех игроков на следующий шаг симуляции, по порядку номеров игроков.
+    /// Перед этим отправляет наши действия local, они начнут действовать через
+    /// несколько шагов. None - действия кого-то из игроков ещё не пришли, шаг делать нельзя
+    pub fn next_tick(&mut self, local: &mut Vec<InputAction>) -> Option<Vec<(PlayerId, InputAction)>> {
+        if !self.started {
+            return None;
+        }
+        let player = self.local?;
+        while self.submitted <= self.tick + self.delay {
+            let tick = self.submitted;
+            let actions = if tick == self.tick + self.delay {std::mem::take(local)} else {Vec::new()};
+            self.inputs.entry(tick).or_default().insert(player, actions.clone());
+            self.broadcast(&Packet::Input {player, tick, actions}, None);
+            self.submitted += 1;
+        }
+        let inputs = self.inputs.get(&self.tick)?;
+        let active = self.active_at(self.tick);
+        if !active.iter().all(|p| inputs.contains_key(p)) {
+            return None;
+        }
+        // Действия ушедших игроков, которые успели прийти, не считаются
+        let actions = inputs.iter()
+            .filter(|(player, _)| active.contains(player))
+            .flat_map(|(player, actions)| actions.iter().map(|a| (*player, a.clone())))
+            .collect();
+        self.tick += 1;
+        Some(actions)
+    }
+    /// Запоминает контрольную сумму мира после только что сделанного шага
+    /// и отправляет её остальным
+    pub fn end_tick(&mut self, checksum: u64) {
+        let (player, tick) = match (self.local, self.tick.checked_sub(1)) {
+            (Some(player), Some(tick)) => (player, tick),
+            _ => return,
+        };
+        self.store_checksum(player, tick, checksum);
+        self.broadcast(&Packet::Checksum {player, tick, value: checksum}, None);
+    }
+    /// Уходит из матча, предупредив остальных
+    pub fn leave(&mut self) {
+        if let Some(player) = self.local {
+            self.broadcast(&Packet::Leave {player, tick: self.submitted}, None);
+        }
+        match &mut self.role {
+            Role::Host { slots, pending, .. } => {
+                for connection in slots.iter_mut().filter_map(|s| s.connection.as_mut())
+                    .chain(pending.iter_mut().flatten())
+                {
+                    connection.close(CLOSE_TIMEOUT);
+                }
+            }
+            Role::Client { connection, .. } => {
+                if let Some(connection) = connection {
+                    connection.close(CLOSE_TIMEOUT);
+                }
+                *connection = None;
+            }
+        }
+    }
+    /// Первое сообщение от ещё не представившегося соединения.
+    /// Возвращает номер игрока, если соединение приняли
+    fn handle_greeting(&mut self, mut connection: Connection, packet: Packet)
+        -> Option<PlayerId>
+    {
+        let expected = match &self.role {
+            Role::Host { expected_players, .. } => *expected_players,
+            Role::Client { .. } => return None,
+        };
+        let welcome = |player| Packet::Welcome {player, seed: self.seed, step: self.step,
+                                                 delay: self.delay};
+        let player = match packet {
+            Packet::Hello { version, .. } | Packet::Resume { version, .. }
+                if version != PROTOCOL_VERSION =>
+            {
+                reject(connection, &format!("нужна версия {PROTOCOL_VERSION}"));
+                return None;
+            }
+            Packet::Hello { name, .. } => {
+                if self.started || self.players.len() >= expected {
+                    reject(connection, "матч уже идёт");
+                    return None;
+                }
+                // Место игрока, ушедшего до начала матча, достаётся следующему
+                let slots = self.slots();
+                let player = (1..slots.len()).find(|p| !self.players.contains_key(p))
+                    .unwrap_or(slots.len());
+                connection.send(&welcome(player));
+                for (other, slot) in slots.iter().enumerate() {
+                    if self.players.contains_key(&other) {
+                        connection.send(&Packet::Joined {player: other, name: slot.name.clone()});
+                    }
+                }
+                self.broadcast(&Packet::Joined {player, name: name.clone()}, None);
+                let slot = Slot {name: name.clone(), connection: None, lost_at: None};
+                match self.slots_mut().get_mut(player) {
+                    Some(free) => *free = slot,
+                    None => self.slots_mut().push(slot),
+                }
+                self.players.insert(player, None);
+                self.events.push(NetEvent::PlayerJoined {player, name});
+                player
+            }
+            Packet::Resume { player, tick, .. } => {
+                let oldest = self.inputs.keys().next().copied().unwrap_or(0);
+                let can_resume = player != 0 && tick >= oldest
+                    && self.players.get(&player) == Some(&None)
+                    && self.slots().get(player).map(|s| s.connection.is_none()).unwrap_or(false);
+                if !can_resume {
+                    reject(connection, "вернуться не получится");
+                    return None;
+                }
+                connection.send(&welcome(player));
+                connection.send(&Packet::Start {players: self.players.keys().copied().collect()});
+                for (left, at) in self.players.iter().filter_map(|(p, at)| at.map(|at| (p, at))) {
+                    connection.send(&Packet::Leave {player: *left, tick: at});
+                }
+                for (t, inputs) in self.inputs.range(tick..) {
+                    for (from, actions) in inputs {
+                        connection.send(&Packet::Input {player: *from, tick: *t,
+                                                        actions: actions.clone()});
+                    }
+                }
+                self.events.push(NetEvent::Reconnected {player});
+                player
+            }
+            _ => {
+                connection.close(CLOSE_TIMEOUT);
+                return None;
+            }
+        };
+        let slot = &mut self.slots_mut()[player];
+        slot.connection = Some(connection);
+        slot.lost_at = None;
+        self.start_if_ready();
+        Some(player)
+    }
+    fn handle_packet(&mut self, from: PlayerId, packet: Packet) {
+        match packet.clone() {
+            Packet::Welcome { player, seed, step, delay } => {
+                if self.local.is_none() {
+                    self.events.push(NetEvent::Joined {player});
+                }
+                self.local = Some(player);
+                self.seed = seed;
+                self.step = step.max(1);
+                self.delay = delay;
+            }
+            Packet::Joined { player, name } => {
+                if Some(player) != self.local {
+                    self.events.push(NetEvent::PlayerJoined {player, name});
+                }
+            }
+            Packet::Start { players } => {
+                if !self.started {
+                    self.started = true;
+                    for player in &players {
+                        self.players.entry(*player).or_insert(None);
+                    }
+                    self.events.push(NetEvent::Started {players});
+                }
+            }
+            Packet::Input { player, tick, actions } => {
+                // От игрока принимаются только его собственные действия,
+                // от создателя матча - чьи угодно
+                if (self.is_host() && player != from) || Some(player) == self.local {
+                    return;
+                }
+                if tick >= self.tick {
+                    self.inputs.entry(tick).or_default().entry(player).or_insert(actions);
+                }
+                self.relay(from, &packet);
+            }
+            Packet::Checksum { player, tick, value } => {
+                if (self.is_host() && player != from) || Some(player) == self.local {
+                    return;
+                }
+                self.store_checksum(player, tick, value);
+                self.relay(from, &packet);
+            }
+            Packet::Leave { player, tick } => {
+                if self.is_host() && player != from {
+                    return;
+                }
+                self.player_left(player, tick);
+            }
+            Packet::Reject { reason } => {
+                self.events.push(NetEvent::Rejected {reason});
+                if let Role::Client { connection, .. } = &mut self.role {
+                    *connection = None;
+                }
+            }
+            Packet::Hello { .. } | Packet::Resume { .. } => (),
+        }
+    }
+    fn slots(&self) -> &[Slot] {
+        match &self.role {
+            Role::Host { slots, .. } => slots,
+            Role::Client { .. } => &[],
+        }
+    }
+    fn slots_mut(&mut self) -> &mut Vec<Slot> {
+        match &mut self.role {
+            Role::Host { slots, .. } => slots,
+            Role::Client { .. } => unreachable!("места игроков есть только у создателя матча"),
+        }
+    }
+    /// Создатель матча пересылает сообщение игрока остальным
+    fn relay(&mut self, from: PlayerId, packet: &Packet) {
+        if self.is_host() {
+            self.broadcast(packet, Some(from));
+        }
+    }
+    /// Отправляет сообщение всем, с кем есть связь, кроме except
+    fn broadcast(&mut self, packet: &Packet, except: Option<PlayerId>) {
+        match &mut self.role {
+            Role::Host { slots, .. } => {
+                for (player, slot) in slots.iter_mut().enumerate() {
+                    if Some(player) == except {
+                        continue;
+                    }
+                    if let Some(connection) = &mut slot.connection {
+                        connection.send(packet);
+                    }
+                }
+            }
+            Role::Client { connection: Some(connection), .. } => connection.send(packet),
+            Role::Client { .. } => (),
+        }
+    }
+    /// Создатель матча начинает его, когда с нужным числом игроков есть связь
+    fn start_if_ready(&mut self) {
+        let ready = match &self.role {
+            Role::Host { slots, expected_players, .. } => {
+                let connected = slots.iter().enumerate()
+                    .filter(|(player, slot)| self.players.contains_key(player) && (*player == 0
+                        || slot.connection.as_ref().map(|c| !c.is_closed()).unwrap_or(false)))
+                    .count();
+                connected >= *expected_players
+            }
+            Role::Client { .. } => false,
+        };
+        if ready && !self.started {
+            self.started = true;
+            let players: Vec<PlayerId> = self.players.keys().copied().collect();
+            self.broadcast(&Packet::Start {players: players.clone()}, None);
+            self.events.push(NetEvent::Started {players});
+        }
+    }
+    /// Игрок ушёл, его действия больше не ждутся начиная с шага tick
+    fn player_left(&mut self, player: PlayerId, tick: u64) {
+        if !self.started {
+            self.drop_player(player);
+            return;
+        }
+        if let Some(left) = self.players.get_mut(&player) {
+            if left.is_none() {
+                *left = Some(tick);
+                self.events.push(NetEvent::PlayerLeft {player});
+                if let Role::Host { slots, .. } = &mut self.role {
+                    if let Some(connection) = slots.get_mut(player).and_then(|s| s.connection.as_mut()) {
+                        connection.close(CLOSE_TIMEOUT);
+                    }
+                }
+                self.relay(player, &Packet::Leave {player, tick});
+            }
+        }
+    }
+    /// Игрок ушёл до начала матча. Его место освобождается, и матч не начнётся,
+    /// пока вместо него не придёт другой
+    fn drop_player(&mut self, player: PlayerId) {
+        let known = self.players.remove(&player).is_some();
+        if let Role::Host { slots, .. } = &mut self.role {
+            if !known {
+                return;
+            }
+            if let Some(connection) = slots.get_mut(player).and_then(|s| s.connection.as_mut()) {
+                connection.close(CLOSE_TIMEOUT);
+            }
+        }
+        self.events.push(NetEvent::PlayerLeft {player});
+        self.relay(player, &Packet::Leave {player, tick: 0});
+    }
+    /// Замечает пропавшие соединения, переподключается и забывает тех,
+    /// кто слишком долго не возвращался
+    fn check_connections(&mut self) {
+        let now = Instant::now();
+        let timeout = self.reconnect_timeout;
+        let mut lost = Vec::new();
+        let mut gone = Vec::new();
+        match &mut self.role {
+            Role::Host { pending, slots, .. } => {
+                pending.retain(|c| c.as_ref().map(|c| !c.is_closed()).unwrap_or(false));
+                for (player, slot) in slots.iter_mut().enumerate() {
+                    if slot.connection.as_ref().map(|c| c.is_closed()).unwrap_or(false) {
+                        slot.connection = None;
+                        slot.lost_at = Some(now);
+                        lost.push(player);
+                    }
+                    if let Some(lost_at) = slot.lost_at {
+                        if now - lost_at >= timeout {
+                            slot.lost_at = None;
+                            gone.push(player);
+                        }
+                    }
+                }
+            }
+            Role::Client { addr, connection, lost_at, last_attempt, reconnecting, name } => {
+                if connection.as_ref().map(|c| c.is_closed()).unwrap_or(false) {
+                    *connection = None;
+                    *lost_at = Some(now);
+                    self.events.push(NetEvent::Disconnected);
+                }
+                let attempt = match reconnecting.as_ref().map(|r| r.is_finished()) {
+                    Some(true) => reconnecting.take().and_then(|r| r.join().ok()),
+                    _ => None,
+                };
+                if let (None, Some(since)) = (&connection, *lost_at) {
+                    if now - since >= timeout {
+                        *lost_at = None;
+                        // Поток подключения закончится сам, его результат уже не нужен
+                        *reconnecting = None;
+                        self.events.push(NetEvent::HostLost);
+                    } else if let Some(Ok(mut c)) = attempt {
+                        match self.local {
+                            Some(player) => {
+                                c.send(&Packet::Resume {version: PROTOCOL_VERSION, player,
+                                                        tick: self.tick});
+                                // Наши действия, которые создатель матча мог не получить
+                                for (tick, inputs) in self.inputs.range(self.tick..) {
+                                    if let Some(actions) = inputs.get(&player) {
+                                        c.send(&Packet::Input {player, tick: *tick,
+                                                               actions: actions.clone()});
+                                    }
+                                }
+                            }
+                            None => c.send(&Packet::Hello {version: PROTOCOL_VERSION,
+                                                           name: name.clone()}),
+                        }
+                        *connection = Some(c);
+                        *lost_at = None;
+                    } else if reconnecting.is_none() && now - *last_attempt >= RECONNECT_INTERVAL {
+                        *last_attempt = now;
+                        let addr = *addr;
+                        *reconnecting = Some(thread::spawn(move || Connection::connect(addr)));
+                    }
+                }
+            }
+        }
+        for player in lost {
+            if !self.started {
+                self.drop_player(player);
+            } else if self.players.get(&player) == Some(&None) {
+                self.events.push(NetEvent::PlayerLost {player});
+            }
+        }
+        for player in gone {
+            // Действия, которые уже пришли, остаются в силе
+            let tick = (self.tick..).find(|t| {
+                self.inputs.get(t).map(|i| !i.contains_key(&player)).unwrap_or(true)
+            }).unwrap_or(self.tick);
+            self.player_left(player, tick);
+        }
+    }
+    fn store_checksum(&mut self, player: PlayerId, tick: u64, value: u64) {
+        if tick + HISTORY_TICKS < self.tick {
+            return;
+        }
+        let checksums = self.checksums.entry(tick).or_default();
+        checksums.insert(player, value);
+        let local = match self.local {
+            Some(local) => local,
+            None => return,
+        };
+        let ours = match checksums.get(&local) {
+            Some(ours) => *ours,
+            None => return,
+        };
+        // Если пришла наша сумма, сверяем её со всеми, а иначе только с пришедшей
+        let mismatched: Vec<(PlayerId, u64)> = checksums.iter()
+            .filter(|(p, v)| **p != local && **v != ours && (player == local || **p == player))
+            .map(|(p, v)| (*p, *v))
+            .collect();
+        for (other, theirs) in mismatched {
+            self.events.push(NetEvent::Desync {tick, player: other, ours, theirs});
+        }
+    }
+    /// Игроки, действия которых нужны на шаге tick
+    fn active_at(&self, tick: u64) -> Vec<PlayerId> {
+        self.players.iter()
+            .filter(|(_, left)| left.map(|at| tick < at).unwrap_or(true))
+            .map(|(p, _)| *p)
+            .collect()
+    }
+    /// Забывает слишком старую историю
+    fn prune(&mut self) {
+        let oldest = self.tick.saturating_sub(HISTORY_TICKS);
+        self.inputs = self.inputs.split_off(&oldest);
+        self.checksums = self.checksums.split_off(&oldest);
+    }
+}
+
+/// Отказывает соединению и закрывает его
+fn reject(mut connection: Connection, reason: &str) {
+    connection.send(&Packet::Reject {reason: reason.to_string()});
+    connection.close(CLOSE_TIMEOUT);
+}
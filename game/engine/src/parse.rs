@@ -0,0 +1,105 @@
+//! Разбор текстовых файлов движка. Все они устроены одинаково: пустые строки
+//! и строки, начинающиеся с `#`, пропускаются, строка `[раздел]` начинает раздел,
+//! остальные строки это `ключ = значение` или данные.
+//! Ошибки здесь это пара из номера строки и причины, а ошибку своего файла
+//! каждый модуль получает из неё через `From<(usize, String)>`
+use std::str::FromStr;
+
+/// Ошибка в строке файла: номер строки начиная с 1 и что с ней не так
+pub(crate) type SyntaxError = (usize, String);
+
+/// Строка файла без пробелов по краям
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Line<'a> {
+    /// `[имя]`, имя без скобок и пробелов по краям
+    Section(&'a str),
+    /// Любая другая строка
+    Text(&'a str),
+}
+
+/// Строки файла вместе с их номерами. Пустые строки и комментарии пропускаются
+pub(crate) fn lines(source: &str) -> impl Iterator<Item = Result<(usize, Line<'_>), SyntaxError>> {
+    source.lines().enumerate().filter_map(|(i, line)| {
+        let n = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        Some(match line.strip_prefix('[') {
+            Some(header) => header.strip_suffix(']')
+                .map(|name| (n, Line::Section(name.trim())))
+                .ok_or_else(|| syntax(n, "у раздела нет закрывающей ]")),
+            None => Ok((n, Line::Text(line))),
+        })
+    })
+}
+
+/// Делит строку `ключ = значение` на ключ и значение без пробелов по краям
+pub(crate) fn key_value(line: &str, n: usize) -> Result<(&str, &str), SyntaxError> {
+    line.split_once('=')
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .ok_or_else(|| syntax(n, "ожидалось ключ = значение"))
+}
+
+pub(crate) fn syntax(line: usize, reason: &str) -> SyntaxError {
+    (line, reason.to_string())
+}
+
+pub(crate) fn parse_number<T: FromStr>(value: &str, line: usize) -> Result<T, SyntaxError> {
+    value.trim().parse()
+        .map_err(|_| syntax(line, &format!("не удалось прочитать {}", value.trim())))
+}
+
+/// Числа через запятую. Пустые места между запятыми пропускаются
+pub(crate) fn parse_numbers<T: FromStr>(value: &str, line: usize) -> Result<Vec<T>, SyntaxError> {
+    value.split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| parse_number(v, line))
+        .collect()
+}
+
+/// Цвет из трёх чисел через запятую
+pub(crate) fn parse_rgb(value: &str, line: usize) -> Result<[f32; 3], SyntaxError> {
+    match parse_numbers(value, line)?.as_slice() {
+        [r, g, b] => Ok([*r, *g, *b]),
+        _ => Err(syntax(line, "цвет должен быть тремя числами")),
+    }
+}
+
+/// Цвет из трёх или четырёх чисел через запятую. Без прозрачности цвет непрозрачный
+pub(crate) fn parse_rgba(value: &str, line: usize) -> Result<[f32; 4], SyntaxError> {
+    match parse_numbers(value, line)?.as_slice() {
+        [r, g, b] => Ok([*r, *g, *b, 1.0]),
+        [r, g, b, a] => Ok([*r, *g, *b, *a]),
+        _ => Err(syntax(line, "цвет должен быть тремя или четырьмя числами")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_skip_comments_and_keep_numbers() {
+        let source = "# карта\n\nkey = value\n  [ layer ground ]  \n1, 2\n[broken\n";
+        let lines: Vec<_> = lines(source).collect();
+        assert_eq!(lines, vec![
+            Ok((3, Line::Text("key = value"))),
+            Ok((4, Line::Section("layer ground"))),
+            Ok((5, Line::Text("1, 2"))),
+            Err((6, "у раздела нет закрывающей ]".to_string())),
+        ]);
+    }
+
+    #[test]
+    fn values_are_parsed_with_line_numbers() {
+        assert_eq!(key_value(" tint =  1, 2 ", 1), Ok(("tint", "1, 2")));
+        assert_eq!(key_value("tint", 7).unwrap_err().0, 7);
+        assert_eq!(parse_numbers::<u32>("1, 2,,3,", 1), Ok(vec![1, 2, 3]));
+        assert_eq!(parse_number::<u32>("x", 4).unwrap_err().0, 4);
+        assert_eq!(parse_rgb("0.5, 1, 0", 1), Ok([0.5, 1.0, 0.0]));
+        assert_eq!(parse_rgba("0.5, 1, 0", 1), Ok([0.5, 1.0, 0.0, 1.0]));
+        assert!(parse_rgba("1, 1", 2).is_err());
+    }
+}
@@ -0,0 +1,14 @@
+use crate::Rect;
+use crate::tilemap::Tilemap;
+
+/// Что-то, сквозь что нельзя пролететь: карта из тайлов, стены и т.п.
+pub trait Obstacles {
+    /// Пересекается ли rect с препятствием
+    fn overlaps(&self, rect: &Rect) -> bool;
+}
+
+impl Obstacles for Tilemap {
+    fn overlaps(&self, rect: &Rect) -> bool {
+        self.overlaps_solid(rect)
+    }
+}
@@ -1,5 +1,4 @@
 use measurements::Mass;
-use queues::Queue;
 use crate::component::Component;
 use crate::component::messages::ComponentMessage;
 use crate::graphics::Sprite;
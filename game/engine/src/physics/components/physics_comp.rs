@@ -1,7 +1,8 @@
 use cgmath::{Point2, Vector2};
 use measurements::{Mass};
-use queues::Queue;
+use crate::messaging::queue::MessageQueue;
 use crate::component::Component;
+use crate::component::data::ComponentData;
 use crate::component::messages::ComponentMessage;
 use crate::graphics::Sprite;
 use crate::messaging::traits::Messaging;
@@ -13,25 +14,80 @@ pub struct PhysicsComponent {
     time_elapsed: u32,
     mass: Mass,
     gravity: f64,
+    wind: f64,
     velocity: Vector2<f64>,
     acceleration: Vector2<f64>,
-    input_messages: Queue<ComponentMessage>,
-    output_messages: Queue<ComponentMessage>
+    input_messages: MessageQueue<ComponentMessage>,
+    output_messages: MessageQueue<ComponentMessage>
 }
 
 impl PhysicsComponent {
+    /// Имя типа компонента для сохранения
+    pub const KIND: &'static str = "physics";
     pub fn new(mass: Mass, gravity: f64, starting_velocity: Option<Vector2<f64>>) -> Component {
         return Component::PhysicsComponent(PhysicsComponent
         {
             time_elapsed: 0,
             mass,
             gravity,
+            wind: 0.0,
             velocity: starting_velocity.unwrap_or(Vector2::new(0.0, 0.0)),
             acceleration: Vector2::new(0.0, 0.0),
-            input_messages: Queue::new(),
-            output_messages: Queue::new(),
+            input_messages: MessageQueue::new(),
+            output_messages: MessageQueue::new(),
         })
     }
+    /// Состояние компонента для сохранения
+    pub fn save(&self) -> ComponentData {
+        ComponentData::new()
+            .with("mass", self.mass.as_kilograms())
+            .with("gravity", self.gravity)
+            .with("wind", self.wind)
+            .with("vx", self.velocity.x)
+            .with("vy", self.velocity.y)
+    }
+    /// Создаёт компонент из сохранённого состояния
+    pub fn load(data: &ComponentData) -> Option<Component> {
+        let velocity = Vector2::new(data.get("vx")?, data.get("vy")?);
+        let mut component = PhysicsComponent::new(Mass::from_kilograms(data.get("mass")?),
+                                                  data.get("gravity")?, Some(velocity));
+        if let Component::PhysicsComponent(p) = &mut component {
+            p.wind = data.get("wind").unwrap_or(0.0);
+        }
+        Some(component)
+    }
+    /// Скорость объекта в пикселях в секунду
+    pub fn velocity(&self) -> Vector2<f64> {
+        self.velocity
+    }
+    /// Задаёт скорость объекта, например при выстреле
+    pub fn set_velocity(&mut self, velocity: Vector2<f64>) {
+        self.velocity = velocity;
+    }
+    /// Ускорение свободного падения в пикселях в секунду за секунду
+    pub fn gravity(&self) -> f64 {
+        self.gravity
+    }
+    /// Ускорение, которое ветер придаёт объекту по x
+    pub fn wind(&self) -> f64 {
+        self.wind
+    }
+    pub fn set_wind(&mut self, wind: f64) {
+        self.wind = wind;
+    }
+    /// Полное ускорение объекта: собственное ускорение, ветер по x и гравитация по y
+    pub fn acceleration(&self) -> Vector2<f64> {
+        self.acceleration + Vector2::new(self.wind, self.gravity)
+    }
+    /// Один шаг интегрирования длиной dt секунд при постоянном ускорении a.
+    /// Возвращает, на сколько сдвинулся объект, и его новую скорость.
+    /// Этим же шагом пользуются все, кому нужно предсказать движение без спрайта.
+    pub fn integrate(velocity: Vector2<f64>, a: Vector2<f64>, dt: f64)
+        -> (Vector2<f64>, Vector2<f64>)
+    {
+        let distance = velocity * dt + 0.5 * a * dt * dt;
+        (distance, velocity + a * dt)
+    }
     // /// Ускоряем объект
     // fn accelerate(&mut self, acc: Vector2<f64>) {
     //     self.velocity += acc;
@@ -48,24 +104,35 @@ impl PhysicsComponent {
     // }
     /// Не даёт объектам выпасть за экран
     fn apply_constraint(&mut self, host: &mut Sprite) {
+        PhysicsComponent::constrain(host, &mut self.velocity);
+    }
+    /// Не даёт объекту с такой скоростью выпасть за экран: отталкивает его от краёв.
+    /// Возвращает true, если объект ударился о край
+    pub fn constrain<T: Rectangular>(host: &mut T, velocity: &mut Vector2<f64>) -> bool {
+        let mut hit = false;
         if host.y() > 768.0 {
-            self.velocity.x *= 0.99;
-            self.velocity.y = self.velocity.y * -0.4;
+            velocity.x *= 0.99;
+            velocity.y = velocity.y * -0.4;
             host.move_ip(None, Some(768));
+            hit = true;
         }
         if host.y() < 0.0+host.height() {
-            self.velocity.x *= 0.99;
-            self.velocity.y = self.velocity.y * -0.9;
+            velocity.x *= 0.99;
+            velocity.y = velocity.y * -0.9;
             host.move_ip(None, Some(0.0+host.height()));
+            hit = true;
         }
         if host.x() > 1224.0-host.width() {
-            self.velocity.x = - self.velocity.x;
+            velocity.x = - velocity.x;
             host.move_ip(Some(1224.0-host.width()), None);
+            hit = true;
         }
         if host.x() < 0.0 {
-            self.velocity.x = - self.velocity.x;
+            velocity.x = - velocity.x;
             host.move_ip(Some(0), None);
+            hit = true;
         }
+        hit
     }
 }
 
@@ -82,12 +149,7 @@ impl Updatable for PhysicsComponent {
     }
     fn updated_internal(mut self, dt: u32, _host: &mut Self::Host) -> Self where Self: Sized {
         let dt = dt as f64 / 1000.0;
-        let mut position_current = _host.position();
-        let a = Vector2::new(0.0, self.gravity);
-        let mut v = self.velocity;
-        let distance = v * dt + 0.5 * a * dt * dt;
-        v += 0.5 * a * dt;
-        v += 0.5 * a * dt;
+        let (distance, v) = PhysicsComponent::integrate(self.velocity, self.acceleration(), dt);
         self.velocity = v;
         _host.move_by(distance.x, distance.y);
         self.apply_constraint(_host);
@@ -97,10 +159,10 @@ impl Updatable for PhysicsComponent {
 
 impl Messaging for PhysicsComponent {
     type Message = ComponentMessage;
-    fn input_messages(&mut self) -> &mut Queue<Self::Message> {
+    fn input_messages(&mut self) -> &mut MessageQueue<Self::Message> {
         &mut self.input_messages
     }
-    fn output_messages(&mut self) -> &mut Queue<Self::Message> {
+    fn output_messages(&mut self) -> &mut MessageQueue<Self::Message> {
         &mut self.output_messages
     }
     fn handle_message(&mut self, message: Self::Message) {
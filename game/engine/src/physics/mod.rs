@@ -1,2 +1,4 @@
+pub mod collision;
 pub mod components;
-pub mod messages;
\ No newline at end of file
+pub mod messages;
+pub mod trajectory;
\ No newline at end of file
@@ -0,0 +1,98 @@
+use cgmath::{Point2, Vector2};
+use glium::glutin::dpi::PhysicalSize;
+use crate::physics::collision::Obstacles;
+use crate::physics::components::PhysicsComponent;
+use crate::Rect;
+use crate::rect::Rectangular;
+
+/// Во что снаряд врезался первым делом
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionKind {
+    /// В край мира, от которого PhysicsComponent отталкивает объекты
+    Bounds,
+    /// В препятствие, например в твёрдый тайл карты
+    Obstacle,
+}
+
+/// Первое столкновение на траектории
+#[derive(Debug, Clone, Copy)]
+pub struct TrajectoryCollision {
+    /// Центр снаряда в момент столкновения
+    pub point: Point2<f64>,
+    /// На каком шаге случилось столкновение, начиная с 0
+    pub step: usize,
+    pub kind: CollisionKind,
+}
+
+/// Предсказанный путь снаряда: центры снаряда после каждого шага
+#[derive(Debug, Clone, Default)]
+pub struct Trajectory {
+    pub points: Vec<Point2<f64>>,
+    pub collision: Option<TrajectoryCollision>,
+}
+
+/// Предсказывает полёт снаряда тем же шагом и с теми же краями мира, что у PhysicsComponent,
+/// ничего при этом не двигая. Время в миллисекундах.
+#[derive(Debug, Clone, Copy)]
+pub struct TrajectoryPredictor {
+    acceleration: Vector2<f64>,
+    size: PhysicalSize<f64>,
+    step: u32,
+    steps: usize,
+}
+
+impl TrajectoryPredictor {
+    /// Предсказатель для снаряда с ускорением acceleration (гравитация и ветер)
+    pub fn new(acceleration: Vector2<f64>) -> Self {
+        TrajectoryPredictor {
+            acceleration,
+            size: PhysicalSize::new(1.0, 1.0),
+            step: 16,
+            steps: 120,
+        }
+    }
+    /// Предсказатель с гравитацией и ветром этого PhysicsComponent
+    pub fn from_component(physics: &PhysicsComponent) -> Self {
+        TrajectoryPredictor::new(physics.acceleration())
+    }
+    /// Размер снаряда, чтобы столкновения были как у настоящего спрайта
+    pub fn with_size(mut self, width: f64, height: f64) -> Self {
+        self.size = PhysicalSize::new(width, height);
+        self
+    }
+    /// Длина одного шага в миллисекундах и количество шагов
+    pub fn with_steps(mut self, step: u32, steps: usize) -> Self {
+        self.step = step.max(1);
+        self.steps = steps;
+        self
+    }
+    /// Предсказывает путь снаряда, центр которого в start, а скорость velocity.
+    /// Путь обрывается на первом столкновении
+    pub fn predict(&self, start: Point2<f64>, velocity: Vector2<f64>,
+                   obstacles: Option<&dyn Obstacles>) -> Trajectory {
+        let (width, height) = (self.size.width, self.size.height);
+        let mut rect = Rect::new(Point2::new(start.x - width / 2.0, start.y + height / 2.0),
+                                 self.size);
+        let mut velocity = velocity;
+        let dt = self.step as f64 / 1000.0;
+        let mut trajectory = Trajectory::default();
+        for step in 0..self.steps {
+            let (distance, v) = PhysicsComponent::integrate(velocity, self.acceleration, dt);
+            velocity = v;
+            rect.move_by(distance.x, distance.y);
+            let mut kind = None;
+            if obstacles.map(|o| o.overlaps(&rect)).unwrap_or(false) {
+                kind = Some(CollisionKind::Obstacle);
+            } else if PhysicsComponent::constrain(&mut rect, &mut velocity) {
+                kind = Some(CollisionKind::Bounds);
+            }
+            let point = Point2::new(rect.left() + width / 2.0, rect.bottom() - height / 2.0);
+            trajectory.points.push(point);
+            if let Some(kind) = kind {
+                trajectory.collision = Some(TrajectoryCollision {point, step, kind});
+                break;
+            }
+        }
+        trajectory
+    }
+}
@@ -1,49 +1,163 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
 use glium::backend::Facade;
+use glium::program::{ProgramCreationError, ShaderType};
 
-/// Создаёт программы для рисования различных объектов на экране.
+/// Программа, которой рисуются спрайты без материала
+pub const DEFAULT_PROGRAM: &str = "default";
+/// Спрайт заливается цветом effect_color на долю amount. Белым спрайт мигает при попадании
+pub const FLASH_PROGRAM: &str = "flash";
+/// Вокруг непрозрачной части спрайта рисуется обводка цвета effect_color
+/// толщиной thickness пикселей текстуры
+pub const OUTLINE_PROGRAM: &str = "outline";
+/// Спрайт рассыпается: при amount = 0 он целый, при amount = 1 его нет.
+/// Край рассыпающейся части светится цветом effect_color
+pub const DISSOLVE_PROGRAM: &str = "dissolve";
+
+/// Ошибка загрузки программы
+#[derive(Debug)]
+pub enum ProgramError {
+    /// Файл шейдера не получилось прочитать
+    Io(PathBuf, std::io::Error),
+    /// Шейдер не скомпилировался или программа не собралась.
+    /// message это сообщение драйвера видеокарты
+    Compile { name: String, message: String },
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgramError::Io(path, e) =>
+                write!(f, "не удалось прочитать шейдер {}: {e}", path.display()),
+            ProgramError::Compile { name, message } =>
+                write!(f, "программа {name} не собралась: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ProgramError {}
+
+impl ProgramError {
+    fn compile(name: &str, error: ProgramCreationError) -> Self {
+        let message = match error {
+            ProgramCreationError::CompilationError(log, ShaderType::Vertex) =>
+                format!("ошибка в вершинном шейдере\n{log}"),
+            ProgramCreationError::CompilationError(log, ShaderType::Fragment) =>
+                format!("ошибка во фрагментном шейдере\n{log}"),
+            ProgramCreationError::CompilationError(log, _) => log,
+            ProgramCreationError::LinkingError(log) => format!("ошибка сборки\n{log}"),
+            e => e.to_string(),
+        };
+        ProgramError::Compile { name: name.to_string(), message }
+    }
+}
+
+/// Создаёт программы для рисования различных объектов на экране и хранит их по именам.
 /// Программа это просто кучка шейдеров, поэтому можно использовать
-/// разные программы для разных объектов без всяких проблем
-pub struct ProgramManager;
+/// разные программы для разных объектов без всяких проблем.
+/// Сразу есть программа DEFAULT_PROGRAM и встроенные эффекты
+/// FLASH_PROGRAM, OUTLINE_PROGRAM и DISSOLVE_PROGRAM
+pub struct ProgramManager {
+    programs: HashMap<String, glium::Program>,
+}
 
 impl ProgramManager {
-    /// Программа для рисования квадратов
-    /// Создаёт программу для рисования квадратов из двух шейдеров
-    pub fn rect_drawing_program<F>(display: &F) -> glium::Program
+    /// Собирает программу по умолчанию и встроенные эффекты
+    pub fn new<F>(display: &F) -> Result<Self, ProgramError>
+    where F: Facade
+    {
+        let mut manager = ProgramManager { programs: HashMap::new() };
+        manager.compile(display, DEFAULT_PROGRAM, VERTEX_SHADER, FRAGMENT_SHADER)?;
+        manager.compile(display, FLASH_PROGRAM, VERTEX_SHADER, FLASH_SHADER)?;
+        manager.compile(display, OUTLINE_PROGRAM, VERTEX_SHADER, OUTLINE_SHADER)?;
+        manager.compile(display, DISSOLVE_PROGRAM, VERTEX_SHADER, DISSOLVE_SHADER)?;
+        Ok(manager)
+    }
+    /// Собирает программу из исходников шейдеров и запоминает её под именем name.
+    /// Программа с тем же именем заменяется
+    pub fn compile<F>(&mut self, display: &F, name: &str, vertex: &str, fragment: &str)
+        -> Result<(), ProgramError>
     where F: Facade
     {
-        glium::Program::from_source(
-        display,
-        VERTEX_SHADER,
-        FRAGMENT_SHADER,
-        None
-    ).unwrap()
+        let program = glium::Program::from_source(display, vertex, fragment, None)
+            .map_err(|e| ProgramError::compile(name, e))?;
+        self.programs.insert(name.to_string(), program);
+        Ok(())
     }
+    /// Загружает фрагментный шейдер из файла .glsl, вершинный шейдер берётся
+    /// по умолчанию. В шейдере есть v_tex_coords, v_color и uniform tex, tint и time,
+    /// а результат пишется в out vec4 color
+    pub fn load<F>(&mut self, display: &F, name: &str, fragment: &Path) -> Result<(), ProgramError>
+    where F: Facade
+    {
+        self.compile(display, name, VERTEX_SHADER, &read_shader(fragment)?)
+    }
+    /// Загружает программу из файлов вершинного и фрагментного шейдеров
+    pub fn load_with_vertex<F>(&mut self, display: &F, name: &str, vertex: &Path, fragment: &Path)
+        -> Result<(), ProgramError>
+    where F: Facade
+    {
+        self.compile(display, name, &read_shader(vertex)?, &read_shader(fragment)?)
+    }
+    /// Загружает все файлы .glsl из папки как фрагментные шейдеры. Имя программы это
+    /// имя файла без расширения. Возвращает ошибки файлов, которые не загрузились,
+    /// остальные программы при этом загружаются
+    pub fn load_dir<F>(&mut self, display: &F, dir: &Path) -> Vec<ProgramError>
+    where F: Facade
+    {
+        let entries = match fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(e) => return vec![ProgramError::Io(dir.to_path_buf(), e)],
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().map(|e| e == "glsl").unwrap_or(false))
+            .collect();
+        paths.sort();
+        paths.iter()
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_str()?;
+                self.load(display, name, path).err()
+            })
+            .collect()
+    }
+    pub fn get(&self, name: &str) -> Option<&glium::Program> {
+        self.programs.get(name)
+    }
+    /// Программа, которой рисуются спрайты без материала
+    pub fn default_program(&self) -> &glium::Program {
+        &self.programs[DEFAULT_PROGRAM]
+    }
+    /// Имена всех программ по алфавиту
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.programs.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
+
+fn read_shader(path: &Path) -> Result<String, ProgramError> {
+    fs::read_to_string(path).map_err(|e| ProgramError::Io(path.to_path_buf(), e))
 }
 
 const VERTEX_SHADER: &'static str = r#"
     #version 140
-    // Input parameter from the Vertex struct.
+    // Input parameters from the Vertex struct.
     in vec2 position;
+    in vec2 tex_coords;
+    in vec4 color;
     // Uniform parameter passed in from the frame.draw() call.
     uniform mat4 projection;
-    // Output texture coordinates that gets passed into the fragment shader.
+    // Output texture coordinates and color that get passed into the fragment shader.
     out vec2 v_tex_coords;
+    out vec4 v_color;
     void main() {
-        // In order to return the texture coordinate for a specific
-        // vertex we have to know what vertex is currently being passed in.
-        // We do this through gl_VertexID which increments with every vertex passed in.
-        // We can figure out the rectangle specific index from the vertex id by modding it
-        // by 4. Example: if a vertex has id 16, then it is the first vertex of the fourth
-        // rectangle being drawn. 16 % 4 == 0 which correctly returns the first index.
-        if (gl_VertexID % 4 == 0) { // First vertex
-            v_tex_coords = vec2(0.0, 1.0);
-        } else if (gl_VertexID % 4 == 1) { // Second vertex
-            v_tex_coords = vec2(1.0, 1.0);
-        } else if (gl_VertexID % 4 == 2) { // Third vertex
-            v_tex_coords = vec2(0.0, 0.0);
-        } else { // Fourth vertex
-            v_tex_coords = vec2(1.0, 0.0);
-        }
+        // Every vertex carries its own texture coordinates, so the same program
+        // can draw whole textures as well as single tiles from a tileset.
+        v_tex_coords = tex_coords;
+        v_color = color;
         gl_Position = projection * vec4(position, 0.0, 1.0);
     }
 "#;
@@ -52,12 +166,84 @@ const FRAGMENT_SHADER: &'static str = r#"
     #version 140
     // Input texture coordinates passed from the vertex shader.
     in vec2 v_tex_coords;
+    in vec4 v_color;
     // Outputs the color for the specific fragment.
     out vec4 color;
-    // Uniform parameter passed in from the frame.draw() call.
+    // Uniform parameters passed in from the frame.draw() call.
     uniform sampler2D tex;
+    // Color the texture is multiplied by. White keeps the texture as is,
+    // and white glyphs of a font become text of any color.
+    uniform vec4 tint;
     void main() {
         // Applies a texture to the rectangle.
-        color = texture(tex, v_tex_coords);
+        color = texture(tex, v_tex_coords) * v_color * tint;
     }
-"#;
\ No newline at end of file
+"#;
+
+const FLASH_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    in vec4 v_color;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform vec4 tint;
+    uniform vec4 effect_color;
+    uniform float amount;
+    void main() {
+        vec4 texel = texture(tex, v_tex_coords) * v_color * tint;
+        // Only the opaque part of the sprite is filled, so its shape stays the same.
+        color = vec4(mix(texel.rgb, effect_color.rgb, amount * effect_color.a), texel.a);
+    }
+"#;
+
+const OUTLINE_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    in vec4 v_color;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform vec4 tint;
+    uniform vec4 effect_color;
+    uniform float thickness;
+    void main() {
+        vec4 texel = texture(tex, v_tex_coords) * v_color * tint;
+        vec2 offset = thickness / vec2(textureSize(tex, 0));
+        // A transparent pixel next to an opaque one belongs to the outline.
+        float around = 0.0;
+        around = max(around, texture(tex, v_tex_coords + vec2(offset.x, 0.0)).a);
+        around = max(around, texture(tex, v_tex_coords - vec2(offset.x, 0.0)).a);
+        around = max(around, texture(tex, v_tex_coords + vec2(0.0, offset.y)).a);
+        around = max(around, texture(tex, v_tex_coords - vec2(0.0, offset.y)).a);
+        around = max(around, texture(tex, v_tex_coords + offset).a);
+        around = max(around, texture(tex, v_tex_coords - offset).a);
+        around = max(around, texture(tex, v_tex_coords + vec2(offset.x, -offset.y)).a);
+        around = max(around, texture(tex, v_tex_coords + vec2(-offset.x, offset.y)).a);
+        vec4 outline = vec4(effect_color.rgb, effect_color.a * around * tint.a);
+        color = mix(outline, texel, texel.a);
+    }
+"#;
+
+const DISSOLVE_SHADER: &str = r#"
+    #version 140
+    in vec2 v_tex_coords;
+    in vec4 v_color;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform vec4 tint;
+    uniform vec4 effect_color;
+    uniform float amount;
+    // Cheap value noise, good enough to break a sprite into grains.
+    float hash(vec2 p) {
+        return fract(sin(dot(p, vec2(12.9898, 78.233))) * 43758.5453);
+    }
+    void main() {
+        vec4 texel = texture(tex, v_tex_coords) * v_color * tint;
+        float noise = hash(floor(v_tex_coords * vec2(textureSize(tex, 0)) / 2.0));
+        if (noise < amount) {
+            discard;
+        }
+        // Pixels that are about to disappear glow.
+        float edge = 1.0 - smoothstep(0.0, 0.08, noise - amount);
+        color = vec4(mix(texel.rgb, effect_color.rgb, edge * step(0.001, amount)), texel.a);
+    }
+"#;
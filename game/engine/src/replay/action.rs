@@ -0,0 +1,45 @@
+use std::fmt;
+
+/// Действие игрока, которое меняет мир. В повтор записываются только они,
+/// а всё остальное получается из них той же самой симуляцией
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputAction {
+    /// Боец выстрелил. Угол в радианах (0 вправо, -PI/2 вверх), скорость в пикселях в секунду
+    Fire { angle: f64, speed: f64 },
+    /// Боец идёт, direction от -1 (влево) до 1 (вправо), 0 - стоит
+    Walk { direction: f64 },
+    Jump,
+    /// Игрок досрочно закончил ход
+    EndTurn,
+    /// Действие, о котором движок не знает
+    Custom { name: String, value: f64 },
+}
+
+impl InputAction {
+    /// Читает действие из строки, записанной через Display
+    pub fn parse(s: &str) -> Option<InputAction> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        Some(match words.as_slice() {
+            ["fire", angle, speed] => InputAction::Fire {angle: angle.parse().ok()?,
+                                                         speed: speed.parse().ok()?},
+            ["walk", direction] => InputAction::Walk {direction: direction.parse().ok()?},
+            ["jump"] => InputAction::Jump,
+            ["end_turn"] => InputAction::EndTurn,
+            ["custom", name, value] => InputAction::Custom {name: name.to_string(),
+                                                            value: value.parse().ok()?},
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for InputAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputAction::Fire { angle, speed } => write!(f, "fire {angle} {speed}"),
+            InputAction::Walk { direction } => write!(f, "walk {direction}"),
+            InputAction::Jump => write!(f, "jump"),
+            InputAction::EndTurn => write!(f, "end_turn"),
+            InputAction::Custom { name, value } => write!(f, "custom {name} {value}"),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+use std::fmt::Write;
+use std::hash::Hasher;
+use crate::graphics::sprite::SpriteGroup;
+use crate::save::GameState;
+use crate::turns::MatchManager;
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Контрольная сумма FNV-1a. В отличие от DefaultHasher она одинакова
+/// в любой сборке и на любой машине, поэтому её можно сравнивать между запусками
+#[derive(Debug, Clone, Copy)]
+pub struct Checksum(u64);
+
+impl Checksum {
+    pub fn new() -> Self {
+        Checksum(FNV_OFFSET)
+    }
+    /// Контрольная сумма состояния мира: спрайтов с компонентами и матча.
+    /// Если она у двух запусков на каком-то шаге разная, симуляции разошлись
+    pub fn of_world(sprites: &SpriteGroup, game_match: Option<&MatchManager>) -> u64 {
+        let mut checksum = Checksum::new();
+        // Состояние пишется тем же текстом, что и в сохранение, прямо в сумму
+        let _ = write!(checksum, "{}", GameState::capture(sprites, game_match));
+        checksum.finish()
+    }
+}
+
+impl Default for Checksum {
+    fn default() -> Self {
+        Checksum::new()
+    }
+}
+
+impl Hasher for Checksum {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+impl Write for Checksum {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        Hasher::write(self, s.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use crate::parse::{self, key_value, parse_number, syntax, Line};
+use crate::replay::action::InputAction;
+use crate::replay::error::ReplayError;
+
+/// Версия формата повторов
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Записанный матч: зерно генератора случайных чисел, шаг симуляции,
+/// действия игроков на каждом шаге и контрольная сумма мира после каждого шага.
+///
+/// В файл записывается текстом:
+/// ```text
+/// version = 1
+/// seed = 42
+/// step = 16
+/// [actions]
+/// 15 fire -0.78 300
+/// 90 end_turn
+/// [checksums]
+/// 9f1c2a3b4d5e6f70
+/// ```
+/// Действие записывается вместе с номером шага, на котором оно случилось.
+/// Контрольные суммы идут по порядку, по одной на шаг.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    /// Шаг симуляции в миллисекундах
+    pub step: u32,
+    /// Номер шага и действие. Отсортированы по номеру шага
+    pub actions: Vec<(u64, InputAction)>,
+    pub checksums: Vec<u64>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, step: u32) -> Self {
+        Replay {seed, step, actions: Vec::new(), checksums: Vec::new()}
+    }
+    /// Генератор случайных чисел в том же состоянии, что и в начале записи.
+    /// Вся случайность в матче должна браться из него
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed)
+    }
+    /// Сколько шагов записано
+    pub fn ticks(&self) -> u64 {
+        self.checksums.len() as u64
+    }
+    /// Длительность записи в миллисекундах
+    pub fn duration(&self) -> u64 {
+        self.ticks() * self.step as u64
+    }
+    /// Записывает повтор в файл
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+    /// Читает повтор из файла
+    pub fn load(path: &Path) -> Result<Replay, ReplayError> {
+        Replay::parse(&fs::read_to_string(path)?)
+    }
+    /// Разбирает текст файла повтора
+    pub fn parse(source: &str) -> Result<Replay, ReplayError> {
+        let mut version = None;
+        let mut seed = None;
+        let mut step = None;
+        let mut replay = Replay::new(0, 0);
+        let mut section = "";
+        for line in parse::lines(source) {
+            let (n, line) = match line? {
+                (n, Line::Section(header)) => {
+                    section = match header {
+                        "actions" => "actions",
+                        "checksums" => "checksums",
+                        _ => return Err(syntax(n, "ожидалось [actions] или [checksums]").into()),
+                    };
+                    continue;
+                }
+                (n, Line::Text(line)) => (n, line),
+            };
+            match section {
+                "actions" => {
+                    let (tick, action) = line.split_once(' ')
+                        .ok_or_else(|| syntax(n, "ожидалось шаг действие"))?;
+                    let tick = parse_number(tick, n)?;
+                    let action = InputAction::parse(action)
+                        .ok_or_else(|| syntax(n, &format!("неизвестное действие {action}")))?;
+                    replay.actions.push((tick, action));
+                }
+                "checksums" => {
+                    let checksum = u64::from_str_radix(line, 16)
+                        .map_err(|_| syntax(n, &format!("{line} не является контрольной суммой")))?;
+                    replay.checksums.push(checksum);
+                }
+                _ => {
+                    let (key, value) = key_value(line, n)?;
+                    match key {
+                        "version" => {
+                            let v = parse_number(value, n)?;
+                            if v > REPLAY_VERSION {
+                                return Err(ReplayError::UnsupportedVersion(v));
+                            }
+                            version = Some(v);
+                        }
+                        "seed" => seed = Some(parse_number(value, n)?),
+                        "step" => step = Some(parse_number(value, n)?),
+                        other => return Err(syntax(n, &format!("неизвестный ключ {other}")).into()),
+                    }
+                }
+            }
+        }
+        version.ok_or(ReplayError::MissingKey("version"))?;
+        replay.seed = seed.ok_or(ReplayError::MissingKey("seed"))?;
+        replay.step = step.filter(|s| *s > 0).ok_or(ReplayError::MissingKey("step"))?;
+        replay.actions.sort_by_key(|(tick, _)| *tick);
+        Ok(replay)
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "version = {REPLAY_VERSION}")?;
+        writeln!(f, "seed = {}", self.seed)?;
+        writeln!(f, "step = {}", self.step)?;
+        writeln!(f, "[actions]")?;
+        for (tick, action) in &self.actions {
+            writeln!(f, "{tick} {action}")?;
+        }
+        writeln!(f, "[checksums]")?;
+        for checksum in &self.checksums {
+            writeln!(f, "{checksum:016x}")?;
+        }
+        Ok(())
+    }
+}
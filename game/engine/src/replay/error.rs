@@ -0,0 +1,39 @@
+use std::fmt;
+
+/// Ошибка записи или чтения повтора
+#[derive(Debug)]
+pub enum ReplayError {
+    /// Файл не получилось прочитать или записать
+    Io(std::io::Error),
+    /// Ошибка в самом файле повтора, line это номер строки начиная с 1
+    Syntax { line: usize, reason: String },
+    /// В файле нет обязательного ключа
+    MissingKey(&'static str),
+    /// Повтор записан более новой версией игры
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "не удалось прочитать или записать повтор: {e}"),
+            ReplayError::Syntax { line, reason } => write!(f, "строка {line}: {reason}"),
+            ReplayError::MissingKey(key) => write!(f, "не указан {key}"),
+            ReplayError::UnsupportedVersion(v) => write!(f, "повтор версии {v} не поддерживается"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl From<(usize, String)> for ReplayError {
+    fn from((line, reason): (usize, String)) -> Self {
+        ReplayError::Syntax { line, reason }
+    }
+}
@@ -0,0 +1,17 @@
+//! Повторы матчей. Симуляция идёт одинаковыми шагами (time::FixedStep), а вся
+//! случайность берётся из генератора с известным зерном, поэтому для повтора
+//! достаточно записать зерно и действия игроков. Контрольная сумма мира после
+//! каждого шага позволяет сразу заметить, где проигрывание разошлось с записью.
+pub mod action;
+pub mod checksum;
+pub mod data;
+pub mod error;
+pub mod player;
+pub mod recorder;
+
+pub use action::InputAction;
+pub use checksum::Checksum;
+pub use data::{Replay, REPLAY_VERSION};
+pub use error::ReplayError;
+pub use player::{Desync, ReplayPlayer};
+pub use recorder::ReplayRecorder;
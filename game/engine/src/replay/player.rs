@@ -0,0 +1,79 @@
+use std::fmt;
+use rand::rngs::StdRng;
+use crate::replay::action::InputAction;
+use crate::replay::data::Replay;
+
+/// Симуляция при проигрывании разошлась с записью
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Desync {
+    pub tick: u64,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl fmt::Display for Desync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "рассинхронизация на шаге {}: ожидалось {:016x}, получилось {:016x}",
+               self.tick, self.expected, self.actual)
+    }
+}
+
+impl std::error::Error for Desync {}
+
+/// Проигрывает записанный матч. Мир нужно создать так же, как при записи,
+/// взять генератор из rng, а потом на каждом шаге применить actions,
+/// обновить мир на шаг step и передать контрольную сумму в end_tick
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    tick: u64,
+    /// Индекс первого ещё не выданного действия
+    cursor: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayer {replay, tick: 0, cursor: 0}
+    }
+    /// Генератор случайных чисел в том же состоянии, что и в начале записи
+    pub fn rng(&self) -> StdRng {
+        self.replay.rng()
+    }
+    /// Шаг симуляции в миллисекундах
+    pub fn step(&self) -> u32 {
+        self.replay.step
+    }
+    /// Номер текущего шага, начиная с 0
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+    /// Проиграны ли все записанные шаги
+    pub fn is_finished(&self) -> bool {
+        self.tick >= self.replay.ticks()
+    }
+    /// Действия игроков на текущем шаге
+    pub fn actions(&mut self) -> Vec<InputAction> {
+        let mut actions = Vec::new();
+        while let Some((tick, action)) = self.replay.actions.get(self.cursor) {
+            if *tick > self.tick {
+                break;
+            }
+            actions.push(action.clone());
+            self.cursor += 1;
+        }
+        actions
+    }
+    /// Заканчивает шаг и сверяет контрольную сумму мира с записанной
+    pub fn end_tick(&mut self, checksum: u64) -> Result<(), Desync> {
+        let tick = self.tick;
+        self.tick += 1;
+        match self.replay.checksums.get(tick as usize) {
+            Some(expected) if *expected != checksum =>
+                Err(Desync {tick, expected: *expected, actual: checksum}),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use rand::rngs::StdRng;
+use crate::replay::action::InputAction;
+use crate::replay::data::Replay;
+
+/// Записывает матч. На каждом шаге симуляции нужно сначала передать действия игроков
+/// через record, а после обновления мира закончить шаг через end_tick
+#[derive(Debug, Clone)]
+pub struct ReplayRecorder {
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    /// Начинает запись. seed это зерно генератора, из которого берётся
+    /// вся случайность матча, step это шаг симуляции в миллисекундах
+    pub fn new(seed: u64, step: u32) -> Self {
+        ReplayRecorder {replay: Replay::new(seed, step)}
+    }
+    /// Генератор случайных чисел матча
+    pub fn rng(&self) -> StdRng {
+        self.replay.rng()
+    }
+    /// Номер текущего шага, начиная с 0
+    pub fn tick(&self) -> u64 {
+        self.replay.ticks()
+    }
+    /// Запоминает действие на текущем шаге
+    pub fn record(&mut self, action: InputAction) {
+        let tick = self.tick();
+        self.replay.actions.push((tick, action));
+    }
+    /// Заканчивает шаг, запоминая контрольную сумму мира после него
+    pub fn end_tick(&mut self, checksum: u64) {
+        self.replay.checksums.push(checksum);
+    }
+    /// Записанное на данный момент
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+    /// Заканчивает запись
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+/// Ошибка сохранения или загрузки игры
+#[derive(Debug)]
+pub enum SaveError {
+    /// Файл не получилось прочитать или записать
+    Io(std::io::Error),
+    /// Ошибка в самом файле сохранения, line это номер строки начиная с 1
+    Syntax { line: usize, reason: String },
+    /// В файле нет обязательного ключа или раздела
+    MissingKey(&'static str),
+    /// Сохранение сделано более новой версией игры
+    UnsupportedVersion(u32),
+    /// Тип компонента не зарегистрирован в ComponentRegistry
+    UnknownComponent(String),
+    /// Компонент зарегистрирован, но его данные не подходят
+    BadComponent(String),
+    /// Неизвестно, из какого файла загружать текстуру спрайта
+    NoTexture(Option<String>),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "не удалось прочитать или записать сохранение: {e}"),
+            SaveError::Syntax { line, reason } => write!(f, "строка {line}: {reason}"),
+            SaveError::MissingKey(key) => write!(f, "не указан {key}"),
+            SaveError::UnsupportedVersion(v) =>
+                write!(f, "сохранение версии {v} не поддерживается"),
+            SaveError::UnknownComponent(kind) => write!(f, "неизвестный компонент {kind}"),
+            SaveError::BadComponent(kind) => write!(f, "не удалось загрузить компонент {kind}"),
+            SaveError::NoTexture(name) =>
+                write!(f, "у спрайта {} нет файла текстуры", name.as_deref().unwrap_or("без имени")),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<(usize, String)> for SaveError {
+    fn from((line, reason): (usize, String)) -> Self {
+        SaveError::Syntax { line, reason }
+    }
+}
@@ -0,0 +1,10 @@
+//! Сохранение и загрузка игры. GameState запоминает спрайты с компонентами и матч,
+//! SaveStorage пишет его в файлы в папке данных пользователя, а
+//! SpriteManager::load_sprites создаёт спрайты заново.
+pub mod error;
+pub mod state;
+pub mod storage;
+
+pub use error::SaveError;
+pub use state::{GameState, MatchState, SpriteState, SAVE_VERSION};
+pub use storage::{user_data_dir, SaveStorage};
@@ -0,0 +1,412 @@
+use std::fmt;
+use std::path::PathBuf;
+use cgmath::Point2;
+use glium::glutin::dpi::PhysicalSize;
+use crate::component::data::ComponentData;
+use crate::component::traits::Composite;
+use crate::graphics::Sprite;
+use crate::graphics::sprite::SpriteGroup;
+use crate::graphics::traits::{FrameList, Layered};
+use crate::group::SomeGroup;
+use crate::misc_traits::named::Named;
+use crate::parse::{self, key_value, parse_number, parse_numbers, syntax, Line};
+use crate::Rect;
+use crate::rect::Rectangular;
+use crate::save::error::SaveError;
+use crate::turns::{MatchManager, MatchOutcome, Team, TurnPhase, Unit};
+
+/// Версия формата сохранения. Увеличивается, когда формат меняется так,
+/// что старая игра не сможет прочитать новое сохранение
+pub const SAVE_VERSION: u32 = 1;
+
+/// Всё, что нужно, чтобы заново создать спрайт
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteState {
+    pub name: Option<String>,
+    /// Файл текстуры. Без него спрайт не загрузить
+    pub texture: Option<PathBuf>,
+    pub rect: Rect,
+    pub layer: u32,
+    pub frames: (u32, u32),
+    pub current_frame: u32,
+    pub hidden: bool,
+    /// Тип компонента и его данные. Компоненты, которые не сохраняются, сюда не попадают
+    pub components: Vec<(String, ComponentData)>,
+}
+
+impl SpriteState {
+    /// Запоминает состояние спрайта и его компонентов
+    pub fn capture(sprite: &Sprite) -> Self {
+        let components = sprite.get_components().iter()
+            .flat_map(|group| group.get_elements().iter().flatten())
+            .filter_map(|c| c.save().map(|data| (c.kind().to_string(), data)))
+            .collect();
+        SpriteState {
+            name: sprite.get_name().clone(),
+            texture: sprite.texture_path().map(|p| p.to_path_buf()),
+            rect: *sprite.get_rect(),
+            layer: sprite.get_layer(),
+            frames: sprite.get_frames(),
+            current_frame: sprite.current_frame(),
+            hidden: sprite.is_hidden(),
+            components,
+        }
+    }
+}
+
+/// Состояние пошагового матча
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchState {
+    pub teams: Vec<Team>,
+    pub turn: u32,
+    pub current_team: usize,
+    /// Индекс бойца текущей команды, который сейчас ходит
+    pub current_unit: Option<usize>,
+    pub phase: TurnPhase,
+}
+
+/// Состояние всей игры: спрайты с компонентами и матч.
+///
+/// В файл записывается текстом, похожим на файл карты. Сначала идёт версия,
+/// потом разделы `[sprite]`, `[match]` и `[team имя]` со строками `ключ = значение`.
+/// Данные компонентов записываются так же, как ComponentData.
+/// Строки, начинающиеся с `#`, игнорируются.
+/// ```text
+/// version = 1
+/// [sprite]
+/// name = fox
+/// texture = ./assets/images/fox.png
+/// rect = 0, 0, 64, 48
+/// layer = 1
+/// frames = 1, 1, 1
+/// hidden = false
+/// component physics = mass=50;gravity=9.8;wind=0;vx=60;vy=10
+/// [match]
+/// turn = 3
+/// current_team = 0
+/// current_unit = 0
+/// phase = aiming 12000
+/// [team Лисы]
+/// next_unit = 1
+/// unit fox = 100
+/// ```
+/// rect это left, bottom, ширина и высота, а frames это кадры по горизонтали,
+/// по вертикали и текущий кадр.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameState {
+    pub sprites: Vec<SpriteState>,
+    pub game_match: Option<MatchState>,
+}
+
+impl GameState {
+    /// Запоминает состояние всех спрайтов группы и матча, если он есть
+    pub fn capture(sprites: &SpriteGroup, game_match: Option<&MatchManager>) -> Self {
+        GameState {
+            sprites: sprites.get_elements().iter().flatten().map(SpriteState::capture).collect(),
+            game_match: game_match.map(|m| m.snapshot()),
+        }
+    }
+    /// Разбирает текст файла сохранения
+    pub fn parse(source: &str) -> Result<GameState, SaveError> {
+        let mut version = None;
+        let mut state = GameState::default();
+        let mut section = Section::Header;
+        let mut teams: Vec<(String, Vec<Unit>, usize)> = Vec::new();
+        for line in parse::lines(source) {
+            let (n, line) = match line? {
+                (n, Line::Section(header)) => {
+                    if version.is_none() {
+                        return Err(syntax(n, "версия должна быть указана в начале файла").into());
+                    }
+                    section = match header {
+                        "sprite" => {
+                            state.sprites.push(SpriteState {
+                                name: None,
+                                texture: None,
+                                rect: Rect::default(),
+                                layer: 0,
+                                frames: (1, 1),
+                                current_frame: 1,
+                                hidden: false,
+                                components: Vec::new(),
+                            });
+                            Section::Sprite
+                        }
+                        "match" => {
+                            state.game_match = Some(MatchState {
+                                teams: Vec::new(),
+                                turn: 0,
+                                current_team: 0,
+                                current_unit: None,
+                                phase: TurnPhase::NotStarted,
+                            });
+                            Section::Match
+                        }
+                        _ => match header.strip_prefix("team ") {
+                            Some(name) => {
+                                teams.push((name.trim().to_string(), Vec::new(), 0));
+                                Section::Team
+                            }
+                            None => {
+                                let reason = format!("неизвестный раздел {header}");
+                                return Err(syntax(n, &reason).into());
+                            }
+                        },
+                    };
+                    continue;
+                }
+                (n, Line::Text(line)) => (n, line),
+            };
+            let (key, value) = key_value(line, n)?;
+            match section {
+                Section::Header => match key {
+                    "version" => {
+                        let v = parse_number(value, n)?;
+                        if v > SAVE_VERSION {
+                            return Err(SaveError::UnsupportedVersion(v));
+                        }
+                        version = Some(v);
+                    }
+                    other => return Err(syntax(n, &format!("неизвестный ключ {other}")).into()),
+                },
+                Section::Sprite => {
+                    let sprite = state.sprites.last_mut().unwrap();
+                    parse_sprite_line(sprite, key, value, n)?;
+                }
+                Section::Match => {
+                    let game_match = state.game_match.as_mut().unwrap();
+                    match key {
+                        "turn" => game_match.turn = parse_number(value, n)?,
+                        "current_team" => game_match.current_team = parse_number(value, n)?,
+                        "current_unit" => game_match.current_unit = match value {
+                            "none" => None,
+                            _ => Some(parse_number(value, n)?),
+                        },
+                        "phase" => game_match.phase = parse_phase(value)
+                            .ok_or_else(|| syntax(n, &format!("неизвестная фаза хода {value}")))?,
+                        other => return Err(syntax(n, &format!("неизвестный ключ {other}")).into()),
+                    }
+                }
+                Section::Team => {
+                    let (_, units, next_unit) = teams.last_mut().unwrap();
+                    match key {
+                        "next_unit" => *next_unit = parse_number(value, n)?,
+                        _ => match key.strip_prefix("unit ") {
+                            Some(sprite) => units.push(Unit::new(sprite.trim(),
+                                                                 parse_number(value, n)?)),
+                            None =>
+                                return Err(syntax(n, &format!("неизвестный ключ {key}")).into()),
+                        },
+                    }
+                }
+            }
+        }
+        if version.is_none() {
+            return Err(SaveError::MissingKey("version"));
+        }
+        if !teams.is_empty() {
+            let game_match = state.game_match.as_mut().ok_or(SaveError::MissingKey("[match]"))?;
+            game_match.teams = teams.into_iter()
+                .map(|(name, units, next_unit)| Team::restore(&name, units, next_unit))
+                .collect();
+        }
+        Ok(state)
+    }
+}
+
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "version = {SAVE_VERSION}")?;
+        for sprite in &self.sprites {
+            writeln!(f, "[sprite]")?;
+            if let Some(name) = &sprite.name {
+                writeln!(f, "name = {name}")?;
+            }
+            if let Some(texture) = &sprite.texture {
+                writeln!(f, "texture = {}", texture.display())?;
+            }
+            let rect = &sprite.rect;
+            writeln!(f, "rect = {}, {}, {}, {}",
+                     rect.left(), rect.bottom(), rect.width(), rect.height())?;
+            writeln!(f, "layer = {}", sprite.layer)?;
+            writeln!(f, "frames = {}, {}, {}", sprite.frames.0, sprite.frames.1,
+                     sprite.current_frame)?;
+            writeln!(f, "hidden = {}", sprite.hidden)?;
+            for (kind, data) in &sprite.components {
+                writeln!(f, "component {kind} = {data}")?;
+            }
+        }
+        if let Some(game_match) = &self.game_match {
+            writeln!(f, "[match]")?;
+            writeln!(f, "turn = {}", game_match.turn)?;
+            writeln!(f, "current_team = {}", game_match.current_team)?;
+            match game_match.current_unit {
+                Some(unit) => writeln!(f, "current_unit = {unit}")?,
+                None => writeln!(f, "current_unit = none")?,
+            }
+            writeln!(f, "phase = {}", format_phase(game_match.phase))?;
+            for team in &game_match.teams {
+                writeln!(f, "[team {}]", team.name())?;
+                writeln!(f, "next_unit = {}", team.next_unit_index())?;
+                for unit in team.units() {
+                    writeln!(f, "unit {} = {}", unit.sprite(), unit.health())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Раздел файла, строки которого сейчас читаются
+#[derive(Debug, Clone, Copy)]
+enum Section {
+    Header,
+    Sprite,
+    Match,
+    Team,
+}
+
+fn parse_sprite_line(sprite: &mut SpriteState, key: &str, value: &str, n: usize)
+    -> Result<(), SaveError>
+{
+    match key {
+        "name" => sprite.name = Some(value.to_string()),
+        "texture" => sprite.texture = Some(PathBuf::from(value)),
+        "rect" => match parse_numbers::<f64>(value, n)?.as_slice() {
+            [x, y, w, h] => sprite.rect = Rect::new(Point2::new(*x, *y), PhysicalSize::new(*w, *h)),
+            _ => return Err(syntax(n, "rect должен состоять из четырёх чисел").into()),
+        },
+        "layer" => sprite.layer = parse_number(value, n)?,
+        "frames" => match parse_numbers(value, n)?.as_slice() {
+            [h, v, current] => {
+                sprite.frames = (*h, *v);
+                sprite.current_frame = *current;
+            }
+            _ => return Err(syntax(n, "frames должен состоять из трёх чисел").into()),
+        },
+        "hidden" => sprite.hidden = parse_number(value, n)?,
+        _ => match key.strip_prefix("component ") {
+            Some(kind) => {
+                let data = ComponentData::parse(value)
+                    .ok_or_else(|| syntax(n, "у данных компонента нет '='"))?;
+                sprite.components.push((kind.trim().to_string(), data));
+            }
+            None => return Err(syntax(n, &format!("неизвестный ключ {key}")).into()),
+        },
+    }
+    Ok(())
+}
+
+fn format_phase(phase: TurnPhase) -> String {
+    match phase {
+        TurnPhase::NotStarted => "not_started".to_string(),
+        TurnPhase::Aiming { time_left } => format!("aiming {time_left}"),
+        TurnPhase::Retreat { time_left } => format!("retreat {time_left}"),
+        TurnPhase::Settling { elapsed } => format!("settling {elapsed}"),
+        TurnPhase::Finished(MatchOutcome::Winner(team)) => format!("finished winner {team}"),
+        TurnPhase::Finished(MatchOutcome::Draw) => "finished draw".to_string(),
+    }
+}
+
+fn parse_phase(value: &str) -> Option<TurnPhase> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    Some(match words.as_slice() {
+        ["not_started"] => TurnPhase::NotStarted,
+        ["aiming", t] => TurnPhase::Aiming {time_left: t.parse().ok()?},
+        ["retreat", t] => TurnPhase::Retreat {time_left: t.parse().ok()?},
+        ["settling", t] => TurnPhase::Settling {elapsed: t.parse().ok()?},
+        ["finished", "winner", team] => TurnPhase::Finished(MatchOutcome::Winner(team.parse().ok()?)),
+        ["finished", "draw"] => TurnPhase::Finished(MatchOutcome::Draw),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+    use super::*;
+    use crate::save::SaveStorage;
+
+    fn sprite(name: &str, hidden: bool) -> SpriteState {
+        SpriteState {
+            name: Some(name.to_string()),
+            texture: Some(PathBuf::from("./assets/images/fox.png")),
+            rect: Rect::new(Point2::new(10.5, -3.0), PhysicalSize::new(64.0, 48.0)),
+            layer: 2,
+            frames: (4, 2),
+            current_frame: 3,
+            hidden,
+            components: vec![
+                ("physics".to_string(), ComponentData::new().with("mass", 50).with("vx", -1.5)),
+                ("health".to_string(), ComponentData::new().with("value", 80)),
+            ],
+        }
+    }
+
+    fn state(phase: TurnPhase, current_unit: Option<usize>) -> GameState {
+        let foxes = Team::restore("Лисы", vec![Unit::new("fox", 100), Unit::new("fox2", 0)], 1);
+        let wolves = Team::restore("Волки", vec![Unit::new("wolf", 35)], 0);
+        GameState {
+            sprites: vec![sprite("fox", false), sprite("wolf", true)],
+            game_match: Some(MatchState {
+                teams: vec![foxes, wolves],
+                turn: 7,
+                current_team: 1,
+                current_unit,
+                phase,
+            }),
+        }
+    }
+
+    #[test]
+    fn state_survives_text_round_trip() {
+        let phases = [
+            TurnPhase::NotStarted,
+            TurnPhase::Aiming { time_left: 12000 },
+            TurnPhase::Retreat { time_left: 2500 },
+            TurnPhase::Settling { elapsed: 40 },
+            TurnPhase::Finished(MatchOutcome::Winner(1)),
+            TurnPhase::Finished(MatchOutcome::Draw),
+        ];
+        for phase in phases {
+            for current_unit in [Some(0), None] {
+                let state = state(phase, current_unit);
+                assert_eq!(GameState::parse(&state.to_string()).unwrap(), state);
+            }
+        }
+        let empty = GameState::default();
+        assert_eq!(GameState::parse(&empty.to_string()).unwrap(), empty);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let source = format!("version = {}\n", SAVE_VERSION + 1);
+        assert!(matches!(GameState::parse(&source),
+                         Err(SaveError::UnsupportedVersion(v)) if v == SAVE_VERSION + 1));
+    }
+
+    #[test]
+    fn teams_without_match_are_rejected() {
+        let source = "version = 1\n[team Лисы]\nnext_unit = 0\nunit fox = 100\n";
+        assert!(matches!(GameState::parse(source), Err(SaveError::MissingKey("[match]"))));
+    }
+
+    #[test]
+    fn storage_saves_loads_and_deletes_slots() {
+        let dir = env::temp_dir().join(format!("engine-save-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let storage = SaveStorage::in_dir(&dir);
+        let state = state(TurnPhase::Aiming { time_left: 500 }, Some(0));
+        assert!(storage.slots().is_empty());
+        storage.save("second", &state).unwrap();
+        storage.save("first", &GameState::default()).unwrap();
+        assert!(storage.exists("second"));
+        assert_eq!(storage.slots(), vec!["first", "second"]);
+        assert_eq!(storage.load("second").unwrap(), state);
+        storage.delete("second").unwrap();
+        assert!(!storage.exists("second"));
+        assert_eq!(storage.slots(), vec!["first"]);
+        assert!(matches!(storage.load("second"), Err(SaveError::Io(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
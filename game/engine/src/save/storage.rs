@@ -0,0 +1,84 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::save::error::SaveError;
+use crate::save::state::GameState;
+
+/// Расширение файлов сохранений
+const EXTENSION: &str = "save";
+
+/// Папка для данных пользователя в этой системе: %APPDATA% в Windows,
+/// ~/Library/Application Support в macOS, $XDG_DATA_HOME или ~/.local/share в остальных
+pub fn user_data_dir() -> Option<PathBuf> {
+    let from_env = |name: &str| env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    if cfg!(target_os = "windows") {
+        from_env("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        from_env("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        from_env("XDG_DATA_HOME")
+            .or_else(|| from_env("HOME").map(|home| home.join(".local").join("share")))
+    }
+}
+
+/// Сохранения игры. Каждое сохранение это файл `слот.save` в папке сохранений
+#[derive(Debug, Clone)]
+pub struct SaveStorage {
+    dir: PathBuf,
+}
+
+impl SaveStorage {
+    /// Сохранения в папке game/saves внутри папки данных пользователя.
+    /// None, если папку данных пользователя найти не получилось
+    pub fn new(game: &str) -> Option<Self> {
+        Some(SaveStorage::in_dir(&user_data_dir()?.join(game).join("saves")))
+    }
+    /// Сохранения в указанной папке
+    pub fn in_dir(dir: &Path) -> Self {
+        SaveStorage {dir: dir.to_path_buf()}
+    }
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+    /// Путь к файлу сохранения
+    pub fn path(&self, slot: &str) -> PathBuf {
+        self.dir.join(format!("{slot}.{EXTENSION}"))
+    }
+    /// Есть ли такое сохранение
+    pub fn exists(&self, slot: &str) -> bool {
+        self.path(slot).is_file()
+    }
+    /// Записывает сохранение. Сначала пишется временный файл, а потом он
+    /// переименовывается, чтобы при сбое не испортить старое сохранение
+    pub fn save(&self, slot: &str, state: &GameState) -> Result<(), SaveError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(slot);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, state.to_string())?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+    /// Читает сохранение
+    pub fn load(&self, slot: &str) -> Result<GameState, SaveError> {
+        GameState::parse(&fs::read_to_string(self.path(slot))?)
+    }
+    /// Удаляет сохранение
+    pub fn delete(&self, slot: &str) -> Result<(), SaveError> {
+        fs::remove_file(self.path(slot))?;
+        Ok(())
+    }
+    /// Имена всех сохранений по алфавиту
+    pub fn slots(&self) -> Vec<String> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(e) => e,
+            Err(_) => return Vec::new(),
+        };
+        let mut slots: Vec<String> = entries.flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().map(|e| e == EXTENSION).unwrap_or(false))
+            .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(String::from))
+            .collect();
+        slots.sort();
+        slots
+    }
+}
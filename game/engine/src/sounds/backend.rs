@@ -0,0 +1,126 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Номер звука, который был запущен через AudioBackend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VoiceId(pub u64);
+
+/// Ошибка проигрывания звука
+#[derive(Debug)]
+pub enum AudioError {
+    Io(std::io::Error),
+    /// Файл не получилось раскодировать
+    Decode(String),
+    /// Нет звуковой карты или её не удалось открыть
+    NoDevice(String),
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioError::Io(e) => write!(f, "не удалось прочитать звук: {e}"),
+            AudioError::Decode(e) => write!(f, "не удалось раскодировать звук: {e}"),
+            AudioError::NoDevice(e) => write!(f, "нет звукового устройства: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
+
+impl From<std::io::Error> for AudioError {
+    fn from(e: std::io::Error) -> Self {
+        AudioError::Io(e)
+    }
+}
+
+/// То, что на самом деле проигрывает звуки. Громкость здесь уже итоговая,
+/// с учётом всех шин, её считает SoundManager.
+/// Any нужен, чтобы по SoundManager::backend можно было получить сам бэкенд
+pub trait AudioBackend: Any {
+    /// Начинает проигрывать файл. stream = true значит, что файл не загружается
+    /// в память целиком, а читается по кусочкам (для музыки).
+    fn play(&mut self, path: &Path, looping: bool, volume: f32, stream: bool)
+        -> Result<VoiceId, AudioError>;
+    /// Меняет громкость звука, который уже играет
+    fn set_volume(&mut self, voice: VoiceId, volume: f32);
+    /// Сдвигает звук влево (-1) или вправо (1). Работает только для звуковых эффектов,
+    /// музыка (stream = true) остаётся как есть
+    fn set_pan(&mut self, voice: VoiceId, pan: f32);
+    /// Останавливает звук
+    fn stop(&mut self, voice: VoiceId);
+    /// Играет ли ещё звук
+    fn is_playing(&self, voice: VoiceId) -> bool;
+}
+
+/// Громкость левого и правого канала для pan от -1 до 1.
+/// Громкость считается так, чтобы общая мощность звука не менялась при сдвиге.
+pub fn pan_gains(pan: f32) -> (f32, f32) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * std::f32::consts::FRAC_PI_4;
+    (angle.cos(), angle.sin())
+}
+
+/// Звук, "играющий" в NullBackend
+#[derive(Debug, Clone)]
+pub struct NullVoice {
+    pub path: PathBuf,
+    pub looping: bool,
+    pub volume: f32,
+    pub pan: f32,
+}
+
+/// Бэкенд без звука, для машин без звуковой карты и для тестов.
+/// Зацикленные звуки играют, пока их не остановят,
+/// а обычные заканчиваются сразу, как будто они нулевой длины.
+#[derive(Debug, Default)]
+pub struct NullBackend {
+    voices: HashMap<VoiceId, NullVoice>,
+    next_id: u64,
+}
+
+impl NullBackend {
+    pub fn new() -> Self {
+        NullBackend::default()
+    }
+    /// Возвращает звук, если он ещё играет. Нужно, чтобы проверять громкость в тестах
+    pub fn voice(&self, voice: VoiceId) -> Option<&NullVoice> {
+        self.voices.get(&voice)
+    }
+    /// Все звуки, которые ещё играют, в порядке запуска
+    pub fn voices(&self) -> Vec<(VoiceId, &NullVoice)> {
+        let mut voices: Vec<_> = self.voices.iter().map(|(id, v)| (*id, v)).collect();
+        voices.sort_by_key(|(id, _)| id.0);
+        voices
+    }
+}
+
+impl AudioBackend for NullBackend {
+    fn play(&mut self, path: &Path, looping: bool, volume: f32, _stream: bool)
+        -> Result<VoiceId, AudioError>
+    {
+        self.next_id += 1;
+        let id = VoiceId(self.next_id);
+        if looping {
+            let voice = NullVoice {path: path.to_path_buf(), looping, volume, pan: 0.0};
+            self.voices.insert(id, voice);
+        }
+        Ok(id)
+    }
+    fn set_volume(&mut self, voice: VoiceId, volume: f32) {
+        if let Some(v) = self.voices.get_mut(&voice) {
+            v.volume = volume;
+        }
+    }
+    fn set_pan(&mut self, voice: VoiceId, pan: f32) {
+        if let Some(v) = self.voices.get_mut(&voice) {
+            v.pan = pan;
+        }
+    }
+    fn stop(&mut self, voice: VoiceId) {
+        self.voices.remove(&voice);
+    }
+    fn is_playing(&self, voice: VoiceId) -> bool {
+        self.voices.contains_key(&voice)
+    }
+}
@@ -0,0 +1,163 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use cgmath::{MetricSpace, Point2};
+use crate::messaging::queue::MessageQueue;
+use crate::component::Component;
+use crate::component::data::ComponentData;
+use crate::component::messages::ComponentMessage;
+use crate::graphics::Sprite;
+use crate::messaging::traits::Messaging;
+use crate::misc_traits::update::Updatable;
+use crate::rect::Rectangular;
+use crate::sounds::messages::AudioMessage;
+
+/// Счётчик, из которого берутся номера эмиттеров
+static NEXT_EMITTER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Компонент, который проигрывает звуки из позиции своего спрайта.
+/// Сам он звук не играет: он копит просьбы, а их исполняет SoundManager::update_emitters,
+/// он же считает громкость и pan относительно слушателя.
+/// Ближе min_distance звук слышно полностью, дальше max_distance не слышно совсем.
+#[derive(Debug)]
+pub struct AudioEmitter {
+    id: u64,
+    time_elapsed: u32,
+    position: Point2<f64>,
+    min_distance: f64,
+    max_distance: f64,
+    requests: Vec<AudioMessage>,
+    input_messages: MessageQueue<ComponentMessage>,
+    output_messages: MessageQueue<ComponentMessage>,
+}
+
+impl AudioEmitter {
+    /// Имя типа компонента для сохранения
+    pub const KIND: &'static str = "audio_emitter";
+    pub fn new(min_distance: f64, max_distance: f64) -> Component {
+        Component::AudioEmitter(AudioEmitter {
+            id: NEXT_EMITTER_ID.fetch_add(1, Ordering::Relaxed),
+            time_elapsed: 0,
+            position: Point2::new(0.0, 0.0),
+            min_distance,
+            max_distance,
+            requests: Vec::new(),
+            input_messages: MessageQueue::new(),
+            output_messages: MessageQueue::new(),
+        })
+    }
+    /// Состояние компонента для сохранения. Играющие звуки не сохраняются
+    pub fn save(&self) -> ComponentData {
+        ComponentData::new()
+            .with("min_distance", self.min_distance)
+            .with("max_distance", self.max_distance)
+    }
+    /// Создаёт компонент из сохранённого состояния
+    pub fn load(data: &ComponentData) -> Option<Component> {
+        Some(AudioEmitter::new(data.get("min_distance")?, data.get("max_distance")?))
+    }
+    /// Уникальный номер эмиттера, по нему SoundManager помнит его звуки
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    /// Позиция спрайта на момент последнего обновления
+    pub fn position(&self) -> Point2<f64> {
+        self.position
+    }
+    /// Просит проиграть звук из позиции спрайта
+    pub fn play(&mut self, sound: &'static str, volume: f32, looping: bool) {
+        self.requests.push(AudioMessage::Play {sound, volume, looping});
+    }
+    /// Просит остановить все звуки спрайта
+    pub fn stop_all(&mut self) {
+        self.requests.push(AudioMessage::StopAll);
+    }
+    /// Забирает накопившиеся просьбы
+    pub fn take_requests(&mut self) -> Vec<AudioMessage> {
+        std::mem::take(&mut self.requests)
+    }
+    /// Во сколько раз тише звук из точки source для слушателя в точке listener
+    pub fn attenuation(&self, source: Point2<f64>, listener: Point2<f64>) -> f32 {
+        let distance = source.distance(listener);
+        if distance <= self.min_distance {
+            return 1.0;
+        }
+        if distance >= self.max_distance {
+            return 0.0;
+        }
+        (1.0 - (distance - self.min_distance) / (self.max_distance - self.min_distance)) as f32
+    }
+    /// pan звука из точки source: -1 слева от слушателя, 1 справа.
+    /// На расстоянии pan_distance по горизонтали звук слышно только одним ухом.
+    pub fn pan(source: Point2<f64>, listener: Point2<f64>, pan_distance: f64) -> f32 {
+        if pan_distance <= 0.0 {
+            return 0.0;
+        }
+        ((source.x - listener.x) / pan_distance).clamp(-1.0, 1.0) as f32
+    }
+}
+
+impl Updatable for AudioEmitter {
+    type Host = Sprite;
+    fn get_time_elapsed(&self) -> u32 {
+        self.time_elapsed
+    }
+    fn get_time_elapsed_mut(&mut self) -> &mut u32 {
+        &mut self.time_elapsed
+    }
+    fn get_delay(&self) -> u32 {
+        1
+    }
+    fn updated_internal(mut self, _mult: u32, host: &mut Self::Host) -> Self {
+        self.position = host.position();
+        self
+    }
+}
+
+impl Messaging for AudioEmitter {
+    type Message = ComponentMessage;
+    fn input_messages(&mut self) -> &mut MessageQueue<Self::Message> {
+        &mut self.input_messages
+    }
+    fn output_messages(&mut self) -> &mut MessageQueue<Self::Message> {
+        &mut self.output_messages
+    }
+    fn handle_message(&mut self, message: Self::Message) {
+        if let ComponentMessage::AudioMessage(m) = message {
+            self.requests.push(m);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emitter(min_distance: f64, max_distance: f64) -> AudioEmitter {
+        match AudioEmitter::new(min_distance, max_distance) {
+            Component::AudioEmitter(e) => e,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn attenuation_falls_linearly_between_distances() {
+        let emitter = emitter(100.0, 300.0);
+        let listener = Point2::new(0.0, 0.0);
+        assert_eq!(emitter.attenuation(Point2::new(60.0, 80.0), listener), 1.0);
+        assert_eq!(emitter.attenuation(Point2::new(0.0, 100.0), listener), 1.0);
+        assert_eq!(emitter.attenuation(Point2::new(-200.0, 0.0), listener), 0.5);
+        assert_eq!(emitter.attenuation(Point2::new(0.0, 300.0), listener), 0.0);
+        assert_eq!(emitter.attenuation(Point2::new(1000.0, 0.0), listener), 0.0);
+    }
+
+    #[test]
+    fn pan_follows_horizontal_offset() {
+        let listener = Point2::new(100.0, 0.0);
+        assert_eq!(AudioEmitter::pan(Point2::new(100.0, 500.0), listener, 200.0), 0.0);
+        assert_eq!(AudioEmitter::pan(Point2::new(200.0, 0.0), listener, 200.0), 0.5);
+        assert_eq!(AudioEmitter::pan(Point2::new(0.0, 0.0), listener, 200.0), -0.5);
+        assert_eq!(AudioEmitter::pan(Point2::new(900.0, 0.0), listener, 200.0), 1.0);
+        assert_eq!(AudioEmitter::pan(Point2::new(-900.0, 0.0), listener, 200.0), -1.0);
+        // Без расстояния pan звук не сдвигается
+        assert_eq!(AudioEmitter::pan(Point2::new(900.0, 0.0), listener, 0.0), 0.0);
+    }
+}
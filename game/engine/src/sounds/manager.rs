@@ -0,0 +1,476 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use cgmath::Point2;
+use crate::component::Component;
+use crate::graphics::sprite::SpriteGroup;
+use crate::group::storage::Storage;
+use crate::component::traits::Composite;
+use crate::rect::Rectangular;
+use crate::sounds::backend::{AudioBackend, AudioError, NullBackend, VoiceId};
+use crate::sounds::components::AudioEmitter;
+use crate::sounds::messages::AudioMessage;
+
+/// Шина громкости. Итоговая громкость звука это его громкость,
+/// умноженная на громкость его шины и на Master.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    Master,
+    Music,
+    Sfx,
+}
+
+/// Тот, кто слышит звуки AudioEmitter
+#[derive(Debug, Clone, Copy)]
+pub enum Listener<'a> {
+    /// Точка мира, например центр камеры
+    Point(Point2<f64>),
+    /// Спрайт с указанным именем
+    Sprite(&'a str),
+}
+
+/// Плавное изменение громкости от from до to за duration миллисекунд
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    from: f32,
+    to: f32,
+    elapsed: u32,
+    duration: u32,
+}
+
+impl Fade {
+    fn new(from: f32, to: f32, duration: u32) -> Self {
+        Fade {from, to, elapsed: 0, duration}
+    }
+    fn level(&self) -> f32 {
+        if self.elapsed >= self.duration {
+            return self.to;
+        }
+        self.from + (self.to - self.from) * self.elapsed as f32 / self.duration as f32
+    }
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// Играющая музыка
+#[derive(Debug, Clone, Copy)]
+struct MusicTrack {
+    voice: VoiceId,
+    fade: Fade,
+}
+
+/// Играющий звуковой эффект и его собственная громкость
+#[derive(Debug, Clone, Copy)]
+struct Effect {
+    voice: VoiceId,
+    volume: f32,
+}
+
+/// SoundManager проигрывает звуковые эффекты и музыку.
+/// Эффекты ищутся в папке sounds, музыка в папке music.
+/// update нужно вызывать каждый кадр, иначе музыка не будет плавно появляться и затихать.
+pub struct SoundManager {
+    backend: Box<dyn AudioBackend>,
+    sounds_dir: PathBuf,
+    music_dir: PathBuf,
+    master_volume: f32,
+    music_volume: f32,
+    sfx_volume: f32,
+    effects: Vec<Effect>,
+    music: Option<MusicTrack>,
+    fading_out: Vec<MusicTrack>,
+    /// Звуки каждого AudioEmitter по его номеру и их собственная громкость
+    positional: HashMap<u64, Vec<Effect>>,
+    pan_distance: f64,
+    /// Файлы звуков, которые не получилось проиграть. О каждом сообщается один раз
+    failed: HashSet<PathBuf>,
+}
+
+impl SoundManager {
+    /// Создаёт SoundManager с указанным бэкендом.
+    /// Звуки берутся из ./assets/sounds, музыка из ./assets/music
+    pub fn new(backend: Box<dyn AudioBackend>) -> Self {
+        SoundManager {
+            backend,
+            sounds_dir: PathBuf::from("./assets/sounds"),
+            music_dir: PathBuf::from("./assets/music"),
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            effects: Vec::new(),
+            music: None,
+            fading_out: Vec::new(),
+            positional: HashMap::new(),
+            pan_distance: 600.0,
+            failed: HashSet::new(),
+        }
+    }
+    /// Создаёт SoundManager с настоящим звуком, если движок собран с фичей rodio
+    /// и звуковая карта доступна. Иначе звука просто не будет.
+    pub fn with_default_backend() -> Self {
+        #[cfg(feature = "rodio")]
+        match crate::sounds::rodio_backend::RodioBackend::new() {
+            Ok(backend) => return SoundManager::new(Box::new(backend)),
+            Err(e) => report_no_device(&e),
+        }
+        SoundManager::new(Box::new(NullBackend::new()))
+    }
+    /// Меняет папки, в которых ищутся звуки и музыка
+    pub fn with_dirs(mut self, sounds_dir: &Path, music_dir: &Path) -> Self {
+        self.sounds_dir = sounds_dir.to_path_buf();
+        self.music_dir = music_dir.to_path_buf();
+        self
+    }
+    /// На каком расстоянии по горизонтали от слушателя звук слышно только одним ухом
+    pub fn with_pan_distance(mut self, pan_distance: f64) -> Self {
+        self.pan_distance = pan_distance;
+        self
+    }
+    /// Громкость шины от 0 до 1
+    pub fn volume(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Master => self.master_volume,
+            Bus::Music => self.music_volume,
+            Bus::Sfx => self.sfx_volume,
+        }
+    }
+    /// Меняет громкость шины, в том числе у уже играющих звуков
+    pub fn set_volume(&mut self, bus: Bus, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+        match bus {
+            Bus::Master => self.master_volume = volume,
+            Bus::Music => self.music_volume = volume,
+            Bus::Sfx => self.sfx_volume = volume,
+        }
+        self.apply_volumes();
+    }
+    /// Проигрывает звуковой эффект из папки звуков, например "explosion.ogg"
+    pub fn play_sound(&mut self, name: &str) -> Result<VoiceId, AudioError> {
+        self.play_sound_with_volume(name, 1.0, false)
+    }
+    /// Проигрывает звуковой эффект со своей громкостью. Зацикленный звук играет,
+    /// пока его не остановят через stop_sound
+    pub fn play_sound_with_volume(&mut self, name: &str, volume: f32, looping: bool)
+        -> Result<VoiceId, AudioError>
+    {
+        let path = self.sounds_dir.join(name);
+        let voice = self.backend.play(&path, looping, self.sfx_level(volume), false)?;
+        self.effects.push(Effect {voice, volume});
+        Ok(voice)
+    }
+    /// Меняет собственную громкость звукового эффекта
+    pub fn set_sound_volume(&mut self, voice: VoiceId, volume: f32) {
+        let level = self.sfx_level(volume);
+        if let Some(effect) = self.effects.iter_mut().find(|e| e.voice == voice) {
+            effect.volume = volume;
+            self.backend.set_volume(voice, level);
+        }
+    }
+    /// Останавливает звуковой эффект
+    pub fn stop_sound(&mut self, voice: VoiceId) {
+        self.backend.stop(voice);
+        self.effects.retain(|e| e.voice != voice);
+    }
+    /// Играет ли ещё звук
+    pub fn is_playing(&self, voice: VoiceId) -> bool {
+        self.backend.is_playing(voice)
+    }
+    /// Включает музыку из папки музыки. Музыка не грузится в память целиком.
+    /// Если уже играет другая музыка, то она затихает за то же время, за которое появляется новая.
+    pub fn play_music(&mut self, name: &str, looping: bool, fade_in: u32)
+        -> Result<(), AudioError>
+    {
+        self.stop_music(fade_in);
+        let path = self.music_dir.join(name);
+        let fade = Fade::new(0.0, 1.0, fade_in);
+        let voice = self.backend.play(&path, looping, self.music_level(fade.level()), true)?;
+        self.music = Some(MusicTrack {voice, fade});
+        Ok(())
+    }
+    /// Плавно выключает музыку за fade_out миллисекунд (0 - сразу)
+    pub fn stop_music(&mut self, fade_out: u32) {
+        let track = match self.music.take() {
+            Some(t) => t,
+            None => return,
+        };
+        if fade_out == 0 {
+            self.backend.stop(track.voice);
+            return;
+        }
+        let fade = Fade::new(track.fade.level(), 0.0, fade_out);
+        self.fading_out.push(MusicTrack {voice: track.voice, fade});
+    }
+    /// Играет ли сейчас музыка
+    pub fn is_music_playing(&self) -> bool {
+        match self.music {
+            Some(track) => self.backend.is_playing(track.voice),
+            None => false,
+        }
+    }
+    /// Двигает плавное появление и затихание музыки и забывает доигравшие эффекты
+    pub fn update(&mut self, dt: u32) {
+        if let Some(track) = self.music.as_mut() {
+            if !track.fade.is_finished() {
+                track.fade.elapsed += dt;
+                let level = self.master_volume * self.music_volume * track.fade.level();
+                self.backend.set_volume(track.voice, level);
+            }
+        }
+        for track in self.fading_out.iter_mut() {
+            track.fade.elapsed += dt;
+            let level = self.master_volume * self.music_volume * track.fade.level();
+            self.backend.set_volume(track.voice, level);
+            if track.fade.is_finished() {
+                self.backend.stop(track.voice);
+            }
+        }
+        self.fading_out.retain(|t| !t.fade.is_finished());
+        let backend = &self.backend;
+        self.effects.retain(|e| backend.is_playing(e.voice));
+    }
+    /// Исполняет просьбы всех AudioEmitter в группе и пересчитывает громкость и pan
+    /// их звуков относительно слушателя. Звуки спрайтов, которых больше нет в группе,
+    /// останавливаются. Если слушателя-спрайта нет, звуки играют как без позиции.
+    pub fn update_emitters(&mut self, sprites: &mut SpriteGroup, listener: Listener) {
+        let listener = match listener {
+            Listener::Point(p) => Some(p),
+            Listener::Sprite(name) => sprites.find_get(name).map(|s| s.position()),
+        };
+        let emitters = sprites.get_elements_mut().iter_mut().flatten().flat_map(|sprite| {
+            let position = sprite.position();
+            sprite.get_components_mut().iter_mut()
+                .flat_map(|components| components.get_elements_mut().iter_mut().flatten())
+                .filter_map(move |component| match component {
+                    Component::AudioEmitter(e) => Some((position, e)),
+                    _ => None,
+                })
+        });
+        self.update_emitters_at(emitters, listener);
+    }
+    /// То же, что update_emitters, только эмиттеры передаются вместе с позициями
+    /// своих спрайтов. Звуки эмиттеров, которых нет среди emitters, останавливаются
+    pub fn update_emitters_at<'e, I>(&mut self, emitters: I, listener: Option<Point2<f64>>)
+        where I: IntoIterator<Item = (Point2<f64>, &'e mut AudioEmitter)>
+    {
+        let level = self.master_volume * self.sfx_volume;
+        let mut seen = HashSet::new();
+        for (position, emitter) in emitters {
+            seen.insert(emitter.id());
+            let (attenuation, pan) = match listener {
+                Some(l) => (emitter.attenuation(position, l),
+                            AudioEmitter::pan(position, l, self.pan_distance)),
+                None => (1.0, 0.0),
+            };
+            let voices = self.positional.entry(emitter.id()).or_default();
+            for request in emitter.take_requests() {
+                match request {
+                    AudioMessage::Play { sound, volume, looping } => {
+                        let path = self.sounds_dir.join(sound);
+                        let volume_now = level * volume * attenuation;
+                        match self.backend.play(&path, looping, volume_now, false) {
+                            Ok(voice) => voices.push(Effect {voice, volume}),
+                            Err(e) => {
+                                if self.failed.insert(path.clone()) {
+                                    report_play_error(&path, &e);
+                                }
+                            }
+                        }
+                    }
+                    AudioMessage::StopAll => {
+                        for effect in voices.drain(..) {
+                            self.backend.stop(effect.voice);
+                        }
+                    }
+                }
+            }
+            let backend = &mut self.backend;
+            voices.retain(|e| backend.is_playing(e.voice));
+            for effect in voices.iter() {
+                backend.set_volume(effect.voice, level * effect.volume * attenuation);
+                backend.set_pan(effect.voice, pan);
+            }
+        }
+        let backend = &mut self.backend;
+        self.positional.retain(|id, voices| {
+            if seen.contains(id) {
+                return true;
+            }
+            for effect in voices.iter() {
+                backend.stop(effect.voice);
+            }
+            false
+        });
+    }
+    /// Возвращает бэкенд, если он типа B. Например, в тестах можно посмотреть
+    /// звуки NullBackend
+    pub fn backend<B: AudioBackend>(&self) -> Option<&B> {
+        let backend: &dyn Any = self.backend.as_ref();
+        backend.downcast_ref()
+    }
+    /// Возвращает изменяемую ссылку на бэкенд
+    pub fn backend_mut(&mut self) -> &mut dyn AudioBackend {
+        self.backend.as_mut()
+    }
+    fn sfx_level(&self, volume: f32) -> f32 {
+        self.master_volume * self.sfx_volume * volume
+    }
+    fn music_level(&self, fade: f32) -> f32 {
+        self.master_volume * self.music_volume * fade
+    }
+    fn apply_volumes(&mut self) {
+        for effect in &self.effects {
+            self.backend.set_volume(effect.voice, self.sfx_level(effect.volume));
+        }
+        let music: Vec<MusicTrack> = self.music.iter().chain(self.fading_out.iter())
+            .copied()
+            .collect();
+        for track in music {
+            self.backend.set_volume(track.voice, self.music_level(track.fade.level()));
+        }
+    }
+}
+
+#[cfg(all(feature = "rodio", feature = "tracing"))]
+fn report_no_device(error: &AudioError) {
+    tracing::warn!(%error, "звук выключен");
+}
+
+#[cfg(all(feature = "rodio", not(feature = "tracing")))]
+fn report_no_device(_error: &AudioError) {}
+
+#[cfg(feature = "tracing")]
+fn report_play_error(path: &Path, error: &AudioError) {
+    tracing::warn!(path = %path.display(), %error, "не удалось проиграть звук");
+}
+
+#[cfg(not(feature = "tracing"))]
+fn report_play_error(_path: &Path, _error: &AudioError) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sounds::backend::NullVoice;
+
+    fn manager() -> SoundManager {
+        SoundManager::new(Box::new(NullBackend::new()))
+    }
+
+    /// Громкость звука в NullBackend
+    fn volume(sounds: &SoundManager, voice: VoiceId) -> Option<f32> {
+        let backend = sounds.backend::<NullBackend>().unwrap();
+        backend.voice(voice).map(|v| v.volume)
+    }
+
+    /// Громкость музыки, которая сейчас играет. Музыка в тестах всегда зациклена,
+    /// иначе NullBackend сразу бы её закончил
+    fn music_volumes(sounds: &SoundManager) -> Vec<(String, f32)> {
+        let backend = sounds.backend::<NullBackend>().unwrap();
+        backend.voices().into_iter()
+            .filter(|(_, v)| v.path.starts_with("./assets/music"))
+            .map(|(_, v)| (v.path.file_name().unwrap().to_string_lossy().into_owned(), v.volume))
+            .collect()
+    }
+
+    fn emitter(min_distance: f64, max_distance: f64) -> AudioEmitter {
+        match AudioEmitter::new(min_distance, max_distance) {
+            Component::AudioEmitter(e) => e,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn bus_volume_multiplies_sound_volume() {
+        let mut sounds = manager();
+        let voice = sounds.play_sound_with_volume("engine.ogg", 0.5, true).unwrap();
+        assert_eq!(volume(&sounds, voice), Some(0.5));
+        sounds.set_volume(Bus::Sfx, 0.5);
+        sounds.set_volume(Bus::Master, 0.5);
+        assert_eq!(volume(&sounds, voice), Some(0.125));
+        // Громкость музыки эффекты не трогает, а шина не бывает громче 1
+        sounds.set_volume(Bus::Music, 0.0);
+        sounds.set_volume(Bus::Sfx, 3.0);
+        assert_eq!(sounds.volume(Bus::Sfx), 1.0);
+        assert_eq!(volume(&sounds, voice), Some(0.25));
+        sounds.set_sound_volume(voice, 1.0);
+        assert_eq!(volume(&sounds, voice), Some(0.5));
+    }
+
+    #[test]
+    fn looping_sound_plays_until_stopped() {
+        let mut sounds = manager();
+        let shot = sounds.play_sound("shot.ogg").unwrap();
+        let engine = sounds.play_sound_with_volume("engine.ogg", 1.0, true).unwrap();
+        sounds.update(16);
+        assert!(!sounds.is_playing(shot));
+        assert!(sounds.is_playing(engine));
+        let path = &sounds.backend::<NullBackend>().unwrap().voice(engine).unwrap().path;
+        assert_eq!(path, &Path::new("./assets/sounds").join("engine.ogg"));
+        sounds.stop_sound(engine);
+        assert!(!sounds.is_playing(engine));
+    }
+
+    #[test]
+    fn music_fades_in_and_out() {
+        let mut sounds = manager();
+        sounds.set_volume(Bus::Music, 0.5);
+        sounds.play_music("theme.ogg", true, 100).unwrap();
+        assert_eq!(music_volumes(&sounds), vec![("theme.ogg".to_string(), 0.0)]);
+        sounds.update(50);
+        assert_eq!(music_volumes(&sounds), vec![("theme.ogg".to_string(), 0.25)]);
+        sounds.update(100);
+        assert_eq!(music_volumes(&sounds), vec![("theme.ogg".to_string(), 0.5)]);
+        // Новая музыка появляется, пока старая затихает
+        sounds.play_music("battle.ogg", true, 100).unwrap();
+        sounds.update(50);
+        assert_eq!(music_volumes(&sounds), vec![("theme.ogg".to_string(), 0.25),
+                                                ("battle.ogg".to_string(), 0.25)]);
+        sounds.update(50);
+        assert_eq!(music_volumes(&sounds), vec![("battle.ogg".to_string(), 0.5)]);
+        assert!(sounds.is_music_playing());
+    }
+
+    #[test]
+    fn stop_music_fades_out_or_stops_at_once() {
+        let mut sounds = manager();
+        sounds.play_music("theme.ogg", true, 0).unwrap();
+        sounds.stop_music(200);
+        assert!(!sounds.is_music_playing());
+        sounds.update(100);
+        assert_eq!(music_volumes(&sounds), vec![("theme.ogg".to_string(), 0.5)]);
+        sounds.update(100);
+        assert!(music_volumes(&sounds).is_empty());
+        sounds.play_music("theme.ogg", true, 0).unwrap();
+        sounds.stop_music(0);
+        assert!(music_volumes(&sounds).is_empty());
+        // Остановить музыку, которой нет, тоже можно
+        sounds.stop_music(100);
+        sounds.update(100);
+    }
+
+    #[test]
+    fn emitter_voices_follow_listener_and_stop_with_host() {
+        let mut sounds = manager().with_pan_distance(100.0);
+        let mut engine = emitter(10.0, 110.0);
+        let mut horn = emitter(10.0, 110.0);
+        engine.play("engine.ogg", 1.0, true);
+        horn.play("horn.ogg", 0.5, true);
+        let listener = Some(Point2::new(0.0, 0.0));
+        sounds.update_emitters_at([(Point2::new(60.0, 0.0), &mut engine),
+                                   (Point2::new(0.0, 0.0), &mut horn)], listener);
+        let voices: Vec<NullVoice> = sounds.backend::<NullBackend>().unwrap().voices()
+            .into_iter().map(|(_, v)| v.clone()).collect();
+        assert_eq!(voices.len(), 2);
+        assert_eq!((voices[0].volume, voices[0].pan), (0.5, 0.6));
+        assert_eq!((voices[1].volume, voices[1].pan), (0.5, 0.0));
+        // Спрайт с гудком удалён: его звук останавливается, а звук мотора играет дальше
+        sounds.update_emitters_at([(Point2::new(-30.0, 0.0), &mut engine)], listener);
+        let voices: Vec<NullVoice> = sounds.backend::<NullBackend>().unwrap().voices()
+            .into_iter().map(|(_, v)| v.clone()).collect();
+        assert_eq!(voices.len(), 1);
+        assert_eq!(voices[0].path, Path::new("./assets/sounds").join("engine.ogg"));
+        assert_eq!((voices[0].volume, voices[0].pan), (0.8, -0.3));
+        sounds.update_emitters_at([], listener);
+        assert!(sounds.backend::<NullBackend>().unwrap().voices().is_empty());
+    }
+}
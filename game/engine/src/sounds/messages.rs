@@ -0,0 +1,14 @@
+/// Сообщения для AudioEmitter. Имена звуков это файлы из папки звуков
+#[derive(Debug, Clone, Copy)]
+pub enum AudioMessage {
+    /// Проиграть звук из позиции спрайта
+    Play { sound: &'static str, volume: f32, looping: bool },
+    /// Остановить все звуки спрайта
+    StopAll,
+}
+
+impl AudioMessage {
+    pub fn play(sound: &'static str) -> AudioMessage {
+        AudioMessage::Play {sound, volume: 1.0, looping: false}
+    }
+}
@@ -0,0 +1,11 @@
+//! Звук. Настоящий звук играет только если движок собран с фичей `rodio`,
+//! иначе используется NullBackend и игра работает без звука.
+pub mod backend;
+pub mod components;
+pub mod manager;
+pub mod messages;
+#[cfg(feature = "rodio")]
+pub mod rodio_backend;
+
+pub use backend::{AudioBackend, AudioError, NullBackend, VoiceId};
+pub use manager::{Bus, Listener, SoundManager};
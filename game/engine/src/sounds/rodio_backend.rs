@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use rodio::source::ChannelVolume;
+use crate::sounds::backend::{AudioBackend, AudioError, VoiceId, pan_gains};
+
+/// Как часто звук проверяет, не поменялся ли pan
+const PAN_UPDATE_PERIOD: Duration = Duration::from_millis(20);
+
+/// Бэкенд, который играет звук через rodio.
+/// Короткие звуки один раз читаются в память и дальше берутся оттуда.
+pub struct RodioBackend {
+    _stream: OutputStream,
+    handle: OutputStreamHandle,
+    sinks: HashMap<VoiceId, Sink>,
+    /// pan каждого звукового эффекта, хранится как биты f32
+    pans: HashMap<VoiceId, Arc<AtomicU32>>,
+    cache: HashMap<PathBuf, Arc<[u8]>>,
+    next_id: u64,
+}
+
+impl RodioBackend {
+    /// Открывает звуковое устройство по умолчанию
+    pub fn new() -> Result<Self, AudioError> {
+        let (stream, handle) = OutputStream::try_default()
+            .map_err(|e| AudioError::NoDevice(e.to_string()))?;
+        Ok(RodioBackend {
+            _stream: stream,
+            handle,
+            sinks: HashMap::new(),
+            pans: HashMap::new(),
+            cache: HashMap::new(),
+            next_id: 0,
+        })
+    }
+    fn bytes(&mut self, path: &Path) -> Result<Arc<[u8]>, AudioError> {
+        if let Some(bytes) = self.cache.get(path) {
+            return Ok(bytes.clone());
+        }
+        let bytes: Arc<[u8]> = std::fs::read(path)?.into();
+        self.cache.insert(path.to_path_buf(), bytes.clone());
+        Ok(bytes)
+    }
+}
+
+impl AudioBackend for RodioBackend {
+    fn play(&mut self, path: &Path, looping: bool, volume: f32, stream: bool)
+        -> Result<VoiceId, AudioError>
+    {
+        let sink = Sink::try_new(&self.handle)
+            .map_err(|e| AudioError::NoDevice(e.to_string()))?;
+        let decode_error = |e: rodio::decoder::DecoderError| AudioError::Decode(e.to_string());
+        self.next_id += 1;
+        let id = VoiceId(self.next_id);
+        if stream {
+            let file = BufReader::new(File::open(path)?);
+            if looping {
+                sink.append(Decoder::new_looped(file).map_err(decode_error)?);
+            } else {
+                sink.append(Decoder::new(file).map_err(decode_error)?);
+            }
+        } else {
+            let bytes = Cursor::new(self.bytes(path)?);
+            let pan = Arc::new(AtomicU32::new(0.0f32.to_bits()));
+            if looping {
+                sink.append(panned(Decoder::new_looped(bytes).map_err(decode_error)?, &pan));
+            } else {
+                sink.append(panned(Decoder::new(bytes).map_err(decode_error)?, &pan));
+            }
+            self.pans.insert(id, pan);
+        }
+        sink.set_volume(volume);
+        self.sinks.insert(id, sink);
+        // Заодно выкидываем звуки, которые уже доиграли
+        self.sinks.retain(|_, s| !s.empty());
+        let sinks = &self.sinks;
+        self.pans.retain(|id, _| sinks.contains_key(id));
+        Ok(id)
+    }
+    fn set_volume(&mut self, voice: VoiceId, volume: f32) {
+        if let Some(sink) = self.sinks.get(&voice) {
+            sink.set_volume(volume);
+        }
+    }
+    fn set_pan(&mut self, voice: VoiceId, pan: f32) {
+        if let Some(p) = self.pans.get(&voice) {
+            p.store(pan.to_bits(), Ordering::Relaxed);
+        }
+    }
+    fn stop(&mut self, voice: VoiceId) {
+        self.pans.remove(&voice);
+        if let Some(sink) = self.sinks.remove(&voice) {
+            sink.stop();
+        }
+    }
+    fn is_playing(&self, voice: VoiceId) -> bool {
+        self.sinks.get(&voice).map(|s| !s.empty()).unwrap_or(false)
+    }
+}
+
+/// Сводит звук в моно и раскладывает его по двум каналам в зависимости от pan.
+/// pan можно менять, пока звук играет.
+fn panned<S>(source: S, pan: &Arc<AtomicU32>) -> impl Source<Item = f32> + Send
+where S: Source<Item = i16> + Send + 'static
+{
+    let (left, right) = pan_gains(0.0);
+    let pan = pan.clone();
+    ChannelVolume::new(source.convert_samples::<f32>(), vec![left, right])
+        .periodic_access(PAN_UPDATE_PERIOD, move |s| {
+            let (left, right) = pan_gains(f32::from_bits(pan.load(Ordering::Relaxed)));
+            s.set_volume(0, left);
+            s.set_volume(1, right);
+        })
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use cgmath::Point2;
+use crate::Rect;
+use crate::physics::collision::Obstacles;
+use crate::rect::Rectangular;
+use crate::tilemap::layer::TileLayer;
+use crate::tilemap::tileset::TileFlags;
+
+/// Сетка клеток карты без текстуры: размер тайла, слои и флаги тайлов.
+/// Всё, что касается клеток и столкновений, считается здесь, поэтому
+/// работает и без окна
+#[derive(Debug, Clone, Copy)]
+pub struct TileGrid<'a> {
+    tile_size: (u32, u32),
+    layers: &'a [TileLayer],
+    flags: &'a HashMap<u32, TileFlags>,
+}
+
+impl<'a> TileGrid<'a> {
+    pub fn new(tile_size: (u32, u32), layers: &'a [TileLayer],
+               flags: &'a HashMap<u32, TileFlags>) -> Self {
+        TileGrid {tile_size, layers, flags}
+    }
+    /// Размер карты в тайлах (по самому большому слою)
+    pub fn size_in_tiles(&self) -> (usize, usize) {
+        let width = self.layers.iter().map(|l| l.width()).max().unwrap_or(0);
+        let height = self.layers.iter().map(|l| l.height()).max().unwrap_or(0);
+        (width, height)
+    }
+    /// Клетка, в которой находится точка мира
+    pub fn cell_at(&self, point: Point2<f64>) -> Option<(usize, usize)> {
+        let (tile_w, tile_h) = self.tile_size;
+        let (width, height) = self.size_in_tiles();
+        if point.x < 0.0 || point.y < 0.0 {
+            return None;
+        }
+        let column = (point.x / tile_w as f64) as usize;
+        let row = (point.y / tile_h as f64) as usize;
+        if column >= width || row >= height {
+            return None;
+        }
+        Some((column, row))
+    }
+    /// Квадрат клетки в мире. Как и у спрайтов, позиция это левый нижний угол
+    pub fn cell_rect(&self, column: usize, row: usize) -> Rect {
+        let (tile_w, tile_h) = (self.tile_size.0 as f64, self.tile_size.1 as f64);
+        Rect::from_scaled((tile_w, tile_h), 1.0)
+            .with_position(column as f64 * tile_w, (row + 1) as f64 * tile_h)
+    }
+    /// Флаги клетки, собранные со всех слоёв
+    pub fn flags(&self, column: usize, row: usize) -> TileFlags {
+        let mut flags = TileFlags::default();
+        for layer in self.layers {
+            if let Some(id) = layer.get(column, row) {
+                flags.solid |= self.flags.get(&id).copied().unwrap_or_default().solid;
+            }
+        }
+        flags
+    }
+    /// Проверяет, есть ли твёрдый тайл в указанной точке мира
+    pub fn is_solid_at(&self, point: Point2<f64>) -> bool {
+        match self.cell_at(point) {
+            Some((column, row)) => self.flags(column, row).solid,
+            None => false,
+        }
+    }
+    /// Клетки с твёрдыми тайлами, которые пересекаются с rect
+    pub fn solid_cells_in(&self, rect: &Rect) -> Vec<(usize, usize)> {
+        let (columns, rows) = self.cells_in(rect);
+        let mut cells = Vec::new();
+        for row in rows {
+            for column in columns.clone() {
+                if self.flags(column, row).solid {
+                    cells.push((column, row));
+                }
+            }
+        }
+        cells
+    }
+    /// Пересекается ли rect хотя бы с одним твёрдым тайлом
+    pub fn overlaps_solid(&self, rect: &Rect) -> bool {
+        !self.solid_cells_in(rect).is_empty()
+    }
+    /// Диапазоны столбцов и строк, которые пересекаются с rect
+    pub fn cells_in(&self, rect: &Rect) -> (Range<usize>, Range<usize>) {
+        let (tile_w, tile_h) = self.tile_size;
+        let (width, height) = self.size_in_tiles();
+        let range = |from: f64, to: f64, tile: u32, max: usize| {
+            let start = (from / tile as f64).floor().clamp(0.0, max as f64) as usize;
+            let end = (to / tile as f64).ceil().clamp(0.0, max as f64) as usize;
+            start..end
+        };
+        (range(rect.left(), rect.right(), tile_w, width),
+         range(rect.bottom() - rect.height(), rect.bottom(), tile_h, height))
+    }
+}
+
+impl Obstacles for TileGrid<'_> {
+    fn overlaps(&self, rect: &Rect) -> bool {
+        self.overlaps_solid(rect)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glium::glutin::dpi::PhysicalSize;
+    use crate::tilemap::MapDescription;
+
+    /// Земля внизу, столб из твёрдого тайла 2 и нетвёрдый куст 3.
+    /// Тайл 1 лежит в другом слое, твёрдость клетки собирается со всех слоёв
+    const MAP: &str = "\
+tileset = ground.png
+tile_size = 10, 10
+solid = 1, 2
+[layer back]
+0,0,0,0
+0,0,0,0
+1,0,0,0
+[layer ground]
+0,0,3,0
+0,2,0,0
+2,2,2,2
+";
+
+    fn flags(description: &MapDescription) -> HashMap<u32, TileFlags> {
+        description.solid.iter().map(|id| (*id, TileFlags {solid: true})).collect()
+    }
+
+    fn rect(left: f64, bottom: f64, width: f64, height: f64) -> Rect {
+        Rect::new(Point2::new(left, bottom), PhysicalSize::new(width, height))
+    }
+
+    #[test]
+    fn cells_and_flags_follow_layers() {
+        let description = MapDescription::parse(MAP).unwrap();
+        let flags = flags(&description);
+        let grid = TileGrid::new(description.tile_size, &description.layers, &flags);
+        assert_eq!(grid.size_in_tiles(), (4, 3));
+        assert_eq!(grid.cell_at(Point2::new(25.0, 19.9)), Some((2, 1)));
+        assert_eq!(grid.cell_at(Point2::new(40.0, 5.0)), None);
+        assert_eq!(grid.cell_at(Point2::new(-0.1, 5.0)), None);
+        assert_eq!(grid.cell_rect(2, 1), rect(20.0, 20.0, 10.0, 10.0));
+        assert!(grid.flags(1, 1).solid);
+        assert!(grid.flags(0, 2).solid);
+        assert!(!grid.flags(2, 0).solid, "куст не твёрдый");
+        assert!(!grid.flags(3, 1).solid);
+        assert!(grid.is_solid_at(Point2::new(15.0, 15.0)));
+        assert!(!grid.is_solid_at(Point2::new(25.0, 5.0)));
+    }
+
+    #[test]
+    fn solid_cells_are_found_under_rect() {
+        let description = MapDescription::parse(MAP).unwrap();
+        let flags = flags(&description);
+        let grid = TileGrid::new(description.tile_size, &description.layers, &flags);
+        // Квадрат задевает клетки от (0, 1) до (2, 2)
+        let body = rect(5.0, 25.0, 20.0, 10.0);
+        assert_eq!(grid.cells_in(&body), (0..3, 1..3));
+        assert_eq!(grid.solid_cells_in(&body), vec![(1, 1), (0, 2), (1, 2), (2, 2)]);
+        // Квадрат вплотную над землёй её не задевает, а за краем карты клеток нет
+        let above = rect(20.0, 20.0, 20.0, 10.0);
+        assert!(grid.solid_cells_in(&above).is_empty());
+        assert!(!grid.overlaps(&above));
+        assert!(grid.overlaps(&rect(20.0, 20.5, 20.0, 10.0)));
+        assert!(!grid.overlaps(&rect(100.0, 30.0, 10.0, 10.0)));
+        assert_eq!(grid.cells_in(&rect(-50.0, 100.0, 10.0, 10.0)), (0..0, 3..3));
+    }
+}
@@ -0,0 +1,50 @@
+/// Слой карты - сетка номеров тайлов. Номер 0 значит, что клетка пустая.
+#[derive(Debug, Clone)]
+pub struct TileLayer {
+    name: String,
+    width: usize,
+    height: usize,
+    tiles: Vec<u32>,
+    hidden: bool,
+}
+
+impl TileLayer {
+    /// Создаёт слой. tiles идут построчно сверху вниз, длина должна быть width * height
+    pub fn new(name: String, width: usize, height: usize, tiles: Vec<u32>) -> Self {
+        assert_eq!(tiles.len(), width * height, "Размер слоя {name} не совпадает с числом тайлов");
+        TileLayer {name, width, height, tiles, hidden: false}
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Ширина слоя в тайлах
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    /// Высота слоя в тайлах
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// Возвращает номер тайла в клетке, либо None, если клетка пустая или за пределами слоя
+    pub fn get(&self, column: usize, row: usize) -> Option<u32> {
+        if column >= self.width || row >= self.height {
+            return None;
+        }
+        match self.tiles[row * self.width + column] {
+            0 => None,
+            id => Some(id),
+        }
+    }
+    /// Ставит в клетку тайл с номером id (0 очищает клетку)
+    pub fn set(&mut self, column: usize, row: usize, id: u32) {
+        assert!(column < self.width && row < self.height,
+                "Клетки ({column}, {row}) нет в слое {}", self.name);
+        self.tiles[row * self.width + column] = id;
+    }
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+}
@@ -0,0 +1,85 @@
+use std::ops::Range;
+use cgmath::Point2;
+use glium::texture::SrgbTexture2d;
+use crate::Rect;
+use crate::tilemap::grid::TileGrid;
+use crate::tilemap::layer::TileLayer;
+use crate::tilemap::parser::MapDescription;
+use crate::tilemap::tileset::{TileFlags, Tileset};
+
+/// Карта из тайлов. Левый верхний угол карты находится в точке (0, 0) мира,
+/// клетка (column, row) занимает квадрат размером с тайл.
+#[derive(Debug)]
+pub struct Tilemap {
+    tileset: Tileset,
+    layers: Vec<TileLayer>,
+}
+
+impl Tilemap {
+    pub fn new(tileset: Tileset, layers: Vec<TileLayer>) -> Self {
+        Tilemap {tileset, layers}
+    }
+    /// Создаёт карту из описания и уже загруженной текстуры тайлсета
+    pub fn from_description(description: MapDescription, texture: SrgbTexture2d) -> Self {
+        let mut tileset = Tileset::new(texture, description.tile_size,
+                                       description.spacing, description.margin);
+        for id in description.solid {
+            tileset.set_flags(id, TileFlags {solid: true});
+        }
+        Tilemap::new(tileset, description.layers)
+    }
+    pub fn tileset(&self) -> &Tileset {
+        &self.tileset
+    }
+    pub fn tileset_mut(&mut self) -> &mut Tileset {
+        &mut self.tileset
+    }
+    /// Слои карты в порядке отрисовки
+    pub fn layers(&self) -> &Vec<TileLayer> {
+        &self.layers
+    }
+    /// Ищет слой по имени
+    pub fn layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|l| l.name() == name)
+    }
+    /// Ищет слой по имени и возвращает изменяемую ссылку
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut TileLayer> {
+        self.layers.iter_mut().find(|l| l.name() == name)
+    }
+    /// Сетка клеток карты. Через неё считаются клетки и столкновения
+    pub fn grid(&self) -> TileGrid<'_> {
+        TileGrid::new(self.tileset.tile_size(), &self.layers, self.tileset.all_flags())
+    }
+    /// Размер карты в тайлах (по самому большому слою)
+    pub fn size_in_tiles(&self) -> (usize, usize) {
+        self.grid().size_in_tiles()
+    }
+    /// Клетка, в которой находится точка мира
+    pub fn cell_at(&self, point: Point2<f64>) -> Option<(usize, usize)> {
+        self.grid().cell_at(point)
+    }
+    /// Квадрат клетки в мире. Как и у спрайтов, позиция это левый нижний угол
+    pub fn cell_rect(&self, column: usize, row: usize) -> Rect {
+        self.grid().cell_rect(column, row)
+    }
+    /// Флаги клетки, собранные со всех слоёв
+    pub fn flags(&self, column: usize, row: usize) -> TileFlags {
+        self.grid().flags(column, row)
+    }
+    /// Проверяет, есть ли твёрдый тайл в указанной точке мира
+    pub fn is_solid_at(&self, point: Point2<f64>) -> bool {
+        self.grid().is_solid_at(point)
+    }
+    /// Клетки с твёрдыми тайлами, которые пересекаются с rect
+    pub fn solid_cells_in(&self, rect: &Rect) -> Vec<(usize, usize)> {
+        self.grid().solid_cells_in(rect)
+    }
+    /// Пересекается ли rect хотя бы с одним твёрдым тайлом
+    pub fn overlaps_solid(&self, rect: &Rect) -> bool {
+        self.grid().overlaps_solid(rect)
+    }
+    /// Диапазоны столбцов и строк, которые пересекаются с rect
+    pub fn cells_in(&self, rect: &Rect) -> (Range<usize>, Range<usize>) {
+        self.grid().cells_in(rect)
+    }
+}
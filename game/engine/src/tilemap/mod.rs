@@ -0,0 +1,12 @@
+//! Карты из тайлов. Карты лежат в `assets/maps`, картинки тайлсетов в `assets/tilesets`.
+pub mod grid;
+pub mod layer;
+pub mod map;
+pub mod parser;
+pub mod tileset;
+
+pub use grid::TileGrid;
+pub use layer::TileLayer;
+pub use map::Tilemap;
+pub use parser::{MapDescription, TilemapError};
+pub use tileset::{TileFlags, Tileset};
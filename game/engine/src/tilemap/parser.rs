@@ -0,0 +1,198 @@
+use std::fmt;
+use std::collections::HashSet;
+use crate::parse::{self, key_value, parse_number, parse_numbers, syntax, Line};
+use crate::tilemap::layer::TileLayer;
+
+/// Ошибка чтения карты
+#[derive(Debug)]
+pub enum TilemapError {
+    /// Файл не получилось прочитать
+    Io(std::io::Error),
+    /// Ошибка в самом файле карты, line это номер строки начиная с 1
+    Syntax { line: usize, reason: String },
+    /// В файле нет обязательного ключа
+    MissingKey(&'static str),
+}
+
+impl fmt::Display for TilemapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TilemapError::Io(e) => write!(f, "не удалось прочитать карту: {e}"),
+            TilemapError::Syntax { line, reason } => write!(f, "строка {line}: {reason}"),
+            TilemapError::MissingKey(key) => write!(f, "не указан {key}"),
+        }
+    }
+}
+
+impl std::error::Error for TilemapError {}
+
+impl From<std::io::Error> for TilemapError {
+    fn from(e: std::io::Error) -> Self {
+        TilemapError::Io(e)
+    }
+}
+
+impl From<(usize, String)> for TilemapError {
+    fn from((line, reason): (usize, String)) -> Self {
+        TilemapError::Syntax { line, reason }
+    }
+}
+
+/// Строка слоя вместе с номером строки файла, чтобы указать на строку другой длины
+type NumberedRow = (usize, Vec<u32>);
+
+/// Содержимое файла карты без загруженных текстур.
+///
+/// Формат файла простой: сначала идут настройки вида `ключ = значение`,
+/// потом слои. Слой начинается строкой `[layer имя]`, после которой идут
+/// строки с номерами тайлов через запятую (как при экспорте слоя из Tiled в CSV).
+/// Номер 0 означает пустую клетку, тайлы тайлсета нумеруются с 1.
+/// Строки, начинающиеся с `#`, игнорируются.
+/// ```text
+/// tileset = ../tilesets/ground.png
+/// tile_size = 32, 32
+/// spacing = 0
+/// margin = 0
+/// solid = 1, 2, 3
+/// [layer ground]
+/// 0,0,0,0
+/// 1,2,2,3
+/// ```
+#[derive(Debug, Clone)]
+pub struct MapDescription {
+    pub tileset: String,
+    pub tile_size: (u32, u32),
+    pub spacing: u32,
+    pub margin: u32,
+    pub solid: HashSet<u32>,
+    pub layers: Vec<TileLayer>,
+}
+
+impl MapDescription {
+    /// Разбирает текст файла карты
+    pub fn parse(source: &str) -> Result<MapDescription, TilemapError> {
+        let mut tileset = None;
+        let mut tile_size = None;
+        let mut spacing = 0;
+        let mut margin = 0;
+        let mut solid = HashSet::new();
+        let mut layers: Vec<(String, Vec<NumberedRow>)> = Vec::new();
+        for line in parse::lines(source) {
+            let (n, line) = match line? {
+                (n, Line::Section(header)) => {
+                    let name = header.strip_prefix("layer")
+                        .ok_or_else(|| syntax(n, "ожидалось [layer имя]"))?;
+                    layers.push((name.trim().to_string(), Vec::new()));
+                    continue;
+                }
+                (n, Line::Text(line)) => (n, line),
+            };
+            if let Some((_, rows)) = layers.last_mut() {
+                rows.push((n, parse_numbers(line, n)?));
+                continue;
+            }
+            let (key, value) = key_value(line, n)?;
+            match key {
+                "tileset" => tileset = Some(value.to_string()),
+                "tile_size" => match parse_numbers(value, n)?.as_slice() {
+                    [w, h] if *w > 0 && *h > 0 => tile_size = Some((*w, *h)),
+                    _ => {
+                        let reason = "tile_size должен быть парой положительных чисел";
+                        return Err(syntax(n, reason).into());
+                    }
+                },
+                "spacing" => spacing = parse_number(value, n)?,
+                "margin" => margin = parse_number(value, n)?,
+                "solid" => solid.extend(parse_numbers::<u32>(value, n)?),
+                other => return Err(syntax(n, &format!("неизвестный ключ {other}")).into()),
+            }
+        }
+        let mut tile_layers = Vec::with_capacity(layers.len());
+        for (name, rows) in layers {
+            let width = rows.first().map(|(_, r)| r.len()).unwrap_or(0);
+            if let Some((n, row)) = rows.iter().find(|(_, r)| r.len() != width) {
+                let reason = format!("в слое {name} строка из {} тайлов, а первая из {width}",
+                                     row.len());
+                return Err(syntax(*n, &reason).into());
+            }
+            let height = rows.len();
+            let tiles = rows.into_iter().flat_map(|(_, r)| r).collect();
+            tile_layers.push(TileLayer::new(name, width, height, tiles));
+        }
+        Ok(MapDescription {
+            tileset: tileset.ok_or(TilemapError::MissingKey("tileset"))?,
+            tile_size: tile_size.ok_or(TilemapError::MissingKey("tile_size"))?,
+            spacing,
+            margin,
+            solid,
+            layers: tile_layers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syntax_line(source: &str) -> usize {
+        match MapDescription::parse(source) {
+            Err(TilemapError::Syntax { line, .. }) => line,
+            other => panic!("ожидалась ошибка в строке, а вышло {other:?}"),
+        }
+    }
+
+    #[test]
+    fn map_is_parsed() {
+        let source = "\
+# пещера
+tileset = ../tilesets/cave.png
+tile_size = 32, 16
+spacing = 1
+margin = 2
+solid = 1, 2
+solid = 5
+
+[layer back]
+0, 4
+4, 0
+[ layer ground ]
+1,2
+0,3
+";
+        let map = MapDescription::parse(source).unwrap();
+        assert_eq!(map.tileset, "../tilesets/cave.png");
+        assert_eq!((map.tile_size, map.spacing, map.margin), ((32, 16), 1, 2));
+        assert_eq!(map.solid, HashSet::from([1, 2, 5]));
+        let names: Vec<_> = map.layers.iter().map(|l| l.name()).collect();
+        assert_eq!(names, ["back", "ground"]);
+        let ground = &map.layers[1];
+        assert_eq!((ground.width(), ground.height()), (2, 2));
+        let tiles = (ground.get(1, 0), ground.get(0, 1), ground.get(1, 1));
+        assert_eq!(tiles, (Some(2), None, Some(3)));
+    }
+
+    #[test]
+    fn errors_point_at_their_line() {
+        let header = "tileset = t.png\ntile_size = 8, 8\n";
+        assert_eq!(syntax_line("tileset = t.png\n\ntile_size = 8\n"), 3);
+        assert_eq!(syntax_line("tileset = t.png\ntile_size = 0, 8\n"), 2);
+        assert_eq!(syntax_line("# карта\nheight = 3\n"), 2);
+        assert_eq!(syntax_line("tileset\n"), 1);
+        assert_eq!(syntax_line(&format!("{header}[ground]\n")), 3);
+        assert_eq!(syntax_line(&format!("{header}[layer a\n")), 3);
+        assert_eq!(syntax_line(&format!("{header}[layer a]\n1,2\n1,x\n")), 5);
+        // Строка другой длины указывается сама, а не первая строка слоя
+        assert_eq!(syntax_line(&format!("{header}[layer a]\n1,2\n1,2\n\n1\n")), 7);
+    }
+
+    #[test]
+    fn missing_keys_are_named() {
+        let missing = |source| match MapDescription::parse(source) {
+            Err(TilemapError::MissingKey(key)) => key,
+            other => panic!("ожидался пропущенный ключ, а вышло {other:?}"),
+        };
+        assert_eq!(missing("tile_size = 8, 8\n"), "tileset");
+        assert_eq!(missing("tileset = t.png\n[layer a]\n1\n"), "tile_size");
+        assert_eq!(TilemapError::MissingKey("tileset").to_string(), "не указан tileset");
+    }
+}
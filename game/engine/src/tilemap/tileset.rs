@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use glium::texture::SrgbTexture2d;
+use crate::graphics::traits::HasTexture;
+
+/// Флаги столкновений для одного тайла
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TileFlags {
+    /// Сквозь тайл нельзя пройти
+    pub solid: bool,
+}
+
+/// Тайлсет - картинка, нарезанная на тайлы одинакового размера.
+/// spacing это расстояние между тайлами, margin это отступ от края картинки.
+#[derive(Debug)]
+pub struct Tileset {
+    texture: SrgbTexture2d,
+    tile_size: (u32, u32),
+    spacing: u32,
+    margin: u32,
+    flags: HashMap<u32, TileFlags>,
+}
+
+impl Tileset {
+    pub fn new(texture: SrgbTexture2d, tile_size: (u32, u32), spacing: u32, margin: u32) -> Self {
+        Tileset {texture, tile_size, spacing, margin, flags: HashMap::new()}
+    }
+    /// Размер одного тайла в пикселях
+    pub fn tile_size(&self) -> (u32, u32) {
+        self.tile_size
+    }
+    /// Сколько тайлов помещается в картинке по горизонтали и по вертикали
+    pub fn grid(&self) -> (u32, u32) {
+        let (width, height) = self.texture.dimensions();
+        let (tile_w, tile_h) = self.tile_size;
+        let count = |size: u32, tile: u32| {
+            (size.saturating_sub(2 * self.margin) + self.spacing) / (tile + self.spacing)
+        };
+        (count(width, tile_w), count(height, tile_h))
+    }
+    /// Общее количество тайлов
+    pub fn len(&self) -> u32 {
+        let (columns, rows) = self.grid();
+        columns * rows
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Координаты текстуры тайла с номером id (нумерация с 1):
+    /// (левый, нижний, правый, верхний). None, если такого тайла нет.
+    pub fn tex_coords(&self, id: u32) -> Option<[f32; 4]> {
+        if id == 0 || id > self.len() {
+            return None;
+        }
+        let (columns, _) = self.grid();
+        let (tile_w, tile_h) = self.tile_size;
+        let (width, height) = self.texture.dimensions();
+        let (column, row) = ((id - 1) % columns, (id - 1) / columns);
+        let x = self.margin + column * (tile_w + self.spacing);
+        let y = self.margin + row * (tile_h + self.spacing);
+        // Картинка загружается перевёрнутой, поэтому верх картинки это v = 1
+        Some([
+            x as f32 / width as f32,
+            1.0 - (y + tile_h) as f32 / height as f32,
+            (x + tile_w) as f32 / width as f32,
+            1.0 - y as f32 / height as f32,
+        ])
+    }
+    /// Возвращает флаги тайла
+    pub fn flags(&self, id: u32) -> TileFlags {
+        self.flags.get(&id).copied().unwrap_or_default()
+    }
+    /// Флаги всех тайлов, у которых они есть
+    pub fn all_flags(&self) -> &HashMap<u32, TileFlags> {
+        &self.flags
+    }
+    /// Устанавливает флаги тайла
+    pub fn set_flags(&mut self, id: u32, flags: TileFlags) {
+        self.flags.insert(id, flags);
+    }
+}
+
+impl HasTexture for Tileset {
+    type Texture = SrgbTexture2d;
+    fn get_texture(&self) -> &Self::Texture {
+        &self.texture
+    }
+    fn get_texture_mut(&mut self) -> &mut Self::Texture {
+        &mut self.texture
+    }
+}
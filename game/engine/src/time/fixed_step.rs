@@ -0,0 +1,66 @@
+/// Делит настоящее время между кадрами на одинаковые шаги симуляции.
+/// Если физику всегда двигать одним и тем же шагом, её можно повторить в точности:
+/// для повторов и для игры по сети.
+/// ```text
+/// let ticks = fixed_step.advance(clock.get_time().as_millis() as u32);
+/// for _ in 0..ticks {
+///     // обновить мир на fixed_step.step() миллисекунд
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct FixedStep {
+    step: u32,
+    accumulated: u32,
+    /// Больше этого шагов за один кадр не делается, чтобы после долгой паузы
+    /// игра не пыталась догнать всё пропущенное время
+    max_ticks: u32,
+}
+
+impl FixedStep {
+    /// Шаг в step миллисекунд
+    pub fn new(step: u32) -> Self {
+        FixedStep {step: step.max(1), accumulated: 0, max_ticks: 8}
+    }
+    pub fn with_max_ticks(mut self, max_ticks: u32) -> Self {
+        self.max_ticks = max_ticks.max(1);
+        self
+    }
+    /// Длина шага в миллисекундах
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+    /// Добавляет прошедшее время и возвращает, сколько шагов нужно сделать
+    pub fn advance(&mut self, dt: u32) -> u32 {
+        self.accumulated = self.accumulated.saturating_add(dt);
+        let ticks = self.accumulated / self.step;
+        self.accumulated %= self.step;
+        if ticks > self.max_ticks {
+            // Отстали слишком сильно, лишнее время просто выбрасывается
+            return self.max_ticks;
+        }
+        ticks
+    }
+    /// Какая доля следующего шага уже прошла, от 0 до 1. Пригодится для сглаживания движения
+    pub fn alpha(&self) -> f64 {
+        self.accumulated as f64 / self.step as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_are_limited_by_max_ticks() {
+        let mut fixed_step = FixedStep::new(10).with_max_ticks(3);
+        assert_eq!(fixed_step.advance(25), 2);
+        assert_eq!(fixed_step.alpha(), 0.5);
+        // После долгой паузы делается не больше max_ticks шагов,
+        // а остаток времени меньше шага не теряется
+        assert_eq!(fixed_step.advance(1000), 3);
+        assert_eq!(fixed_step.alpha(), 0.5);
+        assert_eq!(fixed_step.advance(5), 1);
+        assert_eq!(fixed_step.advance(u32::MAX), 3);
+        assert_eq!(fixed_step.advance(0), 0);
+    }
+}
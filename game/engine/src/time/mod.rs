@@ -1,3 +1,5 @@
 pub mod clock;
+pub mod fixed_step;
 pub mod timers;
-pub use self::clock::Clock;
\ No newline at end of file
+pub use self::clock::Clock;
+pub use self::fixed_step::FixedStep;
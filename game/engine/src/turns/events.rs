@@ -0,0 +1,30 @@
+/// Чем закончился матч
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchOutcome {
+    /// Осталась одна команда с живыми бойцами, здесь её индекс
+    Winner(usize),
+    /// Живых бойцов не осталось ни у кого
+    Draw,
+}
+
+/// События матча, на которые подписываются интерфейс и компьютерный противник.
+/// Команды указываются индексами, бойцы именами спрайтов
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchEvent {
+    /// Начался ход бойца unit команды team
+    TurnStarted { turn: u32, team: usize, unit: String },
+    /// Время на ход вышло, а выстрела так и не было
+    TurnTimedOut { team: usize },
+    /// Боец выстрелил, у него есть retreat_time миллисекунд, чтобы отбежать
+    Fired { team: usize, unit: String, retreat_time: u32 },
+    /// Ход окончен, ждём, пока всё перестанет двигаться
+    WaitingForPhysics,
+    /// Ход окончательно закончился
+    TurnEnded { turn: u32, team: usize },
+    /// Боец потерял здоровье
+    UnitDamaged { team: usize, unit: String, health: i32 },
+    /// Боец умер или его спрайт пропал из группы
+    UnitDied { team: usize, unit: String },
+    /// Матч окончен
+    MatchOver(MatchOutcome),
+}
@@ -0,0 +1,302 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+use cgmath::InnerSpace;
+use crate::graphics::sprite::SpriteGroup;
+use crate::physics::components::PhysicsComponent;
+use crate::save::MatchState;
+use crate::turns::events::{MatchEvent, MatchOutcome};
+use crate::turns::team::Team;
+
+/// Настройки ходов. Время в миллисекундах
+#[derive(Debug, Clone, Copy)]
+pub struct TurnSettings {
+    /// Сколько времени даётся на ход
+    pub turn_time: u32,
+    /// Сколько времени даётся, чтобы отбежать после выстрела
+    pub retreat_time: u32,
+    /// Объект с меньшей скоростью (пиксели в секунду) считается остановившимся
+    pub settle_speed: f64,
+    /// Дольше этого физику не ждём, даже если что-то ещё катается
+    pub settle_timeout: u32,
+}
+
+impl Default for TurnSettings {
+    fn default() -> Self {
+        TurnSettings {
+            turn_time: 30_000,
+            retreat_time: 3_000,
+            settle_speed: 5.0,
+            settle_timeout: 10_000,
+        }
+    }
+}
+
+/// Что сейчас происходит в ходе
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnPhase {
+    /// Матч ещё не начат
+    NotStarted,
+    /// Боец целится и может выстрелить, time_left это оставшееся время хода
+    Aiming { time_left: u32 },
+    /// Боец выстрелил и отбегает
+    Retreat { time_left: u32 },
+    /// Ждём, пока всё перестанет двигаться
+    Settling { elapsed: u32 },
+    /// Матч окончен
+    Finished(MatchOutcome),
+}
+
+/// MatchManager ведёт пошаговый матч: решает, чей ход, считает время хода,
+/// ждёт, пока уляжется физика, и определяет победителя.
+/// update нужно вызывать каждый кадр, а о выстреле сообщать через fire.
+pub struct MatchManager {
+    teams: Vec<Team>,
+    settings: TurnSettings,
+    phase: TurnPhase,
+    turn: u32,
+    current_team: usize,
+    current_unit: Option<usize>,
+    subscribers: Vec<Sender<MatchEvent>>,
+}
+
+impl MatchManager {
+    pub fn new(teams: Vec<Team>, settings: TurnSettings) -> Self {
+        MatchManager {
+            teams,
+            settings,
+            phase: TurnPhase::NotStarted,
+            turn: 0,
+            current_team: 0,
+            current_unit: None,
+            subscribers: Vec::new(),
+        }
+    }
+    /// Продолжает сохранённый матч. Подписчиков у него ещё нет
+    pub fn restore(state: MatchState, settings: TurnSettings) -> Self {
+        let current_unit = state.current_unit
+            .filter(|u| state.teams.get(state.current_team).map(|t| *u < t.units().len())
+                .unwrap_or(false));
+        MatchManager {
+            teams: state.teams,
+            settings,
+            phase: state.phase,
+            turn: state.turn,
+            current_team: state.current_team,
+            current_unit,
+            subscribers: Vec::new(),
+        }
+    }
+    /// Состояние матча для сохранения
+    pub fn snapshot(&self) -> MatchState {
+        MatchState {
+            teams: self.teams.clone(),
+            turn: self.turn,
+            current_team: self.current_team,
+            current_unit: self.current_unit,
+            phase: self.phase,
+        }
+    }
+    /// Подписывается на события матча. Каждый подписчик получает все события,
+    /// забирать их можно через try_iter
+    pub fn subscribe(&mut self) -> Receiver<MatchEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+    pub fn teams(&self) -> &Vec<Team> {
+        &self.teams
+    }
+    pub fn settings(&self) -> &TurnSettings {
+        &self.settings
+    }
+    pub fn phase(&self) -> TurnPhase {
+        self.phase
+    }
+    /// Номер хода, начиная с 1
+    pub fn turn(&self) -> u32 {
+        self.turn
+    }
+    /// Индекс команды, которая сейчас ходит
+    pub fn current_team(&self) -> usize {
+        self.current_team
+    }
+    /// Имя спрайта бойца, который сейчас ходит
+    pub fn current_unit(&self) -> Option<&str> {
+        let unit = self.current_unit?;
+        Some(self.teams[self.current_team].units()[unit].sprite())
+    }
+    /// Может ли текущий боец сейчас управляться игроком или компьютером
+    pub fn can_act(&self) -> bool {
+        matches!(self.phase, TurnPhase::Aiming {..} | TurnPhase::Retreat {..})
+    }
+    /// Может ли текущий боец сейчас выстрелить
+    pub fn can_fire(&self) -> bool {
+        matches!(self.phase, TurnPhase::Aiming {..})
+    }
+    /// Оставшееся время хода или отступления
+    pub fn time_left(&self) -> Option<u32> {
+        match self.phase {
+            TurnPhase::Aiming { time_left } | TurnPhase::Retreat { time_left } => Some(time_left),
+            _ => None,
+        }
+    }
+    pub fn is_over(&self) -> bool {
+        matches!(self.phase, TurnPhase::Finished(_))
+    }
+    /// Начинает матч с хода первой команды
+    pub fn start(&mut self) {
+        if self.phase != TurnPhase::NotStarted {
+            return;
+        }
+        self.current_team = self.teams.len().saturating_sub(1);
+        self.next_turn();
+    }
+    /// Сообщает, что текущий боец выстрелил. Возвращает false, если стрелять сейчас нельзя
+    pub fn fire(&mut self) -> bool {
+        if !self.can_fire() {
+            return false;
+        }
+        let retreat_time = self.settings.retreat_time;
+        self.phase = TurnPhase::Retreat {time_left: retreat_time};
+        let unit = self.current_unit().unwrap_or_default().to_string();
+        self.emit(MatchEvent::Fired {team: self.current_team, unit, retreat_time});
+        true
+    }
+    /// Досрочно заканчивает ход, например если игрок решил пропустить его
+    pub fn end_turn(&mut self) {
+        if self.can_act() {
+            self.start_settling();
+        }
+    }
+    /// Наносит бойцу урон. Урон считается где угодно (взрыв, падение),
+    /// а здесь только учитывается
+    pub fn damage(&mut self, sprite: &str, amount: i32) {
+        let team = match self.team_of(sprite) {
+            Some(t) => t,
+            None => return,
+        };
+        let died = self.teams[team].change_health(sprite, -amount);
+        let health = self.teams[team].unit(sprite).map(|u| u.health()).unwrap_or(0);
+        self.emit(MatchEvent::UnitDamaged {team, unit: sprite.to_string(), health});
+        if died {
+            self.unit_died(team, sprite);
+        }
+    }
+    /// Индекс команды, в которой есть боец с таким спрайтом
+    pub fn team_of(&self, sprite: &str) -> Option<usize> {
+        self.teams.iter().position(|t| t.unit(sprite).is_some())
+    }
+    /// Двигает таймеры хода. Бойцы, чьих спрайтов больше нет в группе, считаются умершими.
+    /// Следующий ход начинается только когда все PhysicsComponent в группе остановились.
+    pub fn update(&mut self, dt: u32, sprites: &SpriteGroup) {
+        let settled = self.is_settled(sprites);
+        self.advance(dt, |name| sprites.find(name).is_some(), settled);
+    }
+    /// То же, что update, но без спрайтов, например для симуляции без окна.
+    /// has_sprite говорит, есть ли ещё спрайт с таким именем, а settled,
+    /// остановилось ли всё, что двигается
+    pub fn advance<F: Fn(&str) -> bool>(&mut self, dt: u32, has_sprite: F, settled: bool) {
+        if matches!(self.phase, TurnPhase::NotStarted | TurnPhase::Finished(_)) {
+            return;
+        }
+        self.check_missing(has_sprite);
+        match self.phase {
+            TurnPhase::Aiming { time_left } => {
+                if time_left <= dt {
+                    self.emit(MatchEvent::TurnTimedOut {team: self.current_team});
+                    self.start_settling();
+                } else {
+                    self.phase = TurnPhase::Aiming {time_left: time_left - dt};
+                }
+            }
+            TurnPhase::Retreat { time_left } => {
+                if time_left <= dt {
+                    self.start_settling();
+                } else {
+                    self.phase = TurnPhase::Retreat {time_left: time_left - dt};
+                }
+            }
+            TurnPhase::Settling { elapsed } => {
+                let elapsed = elapsed + dt;
+                self.phase = TurnPhase::Settling {elapsed};
+                if elapsed >= self.settings.settle_timeout || settled {
+                    self.emit(MatchEvent::TurnEnded {turn: self.turn, team: self.current_team});
+                    match self.outcome() {
+                        Some(outcome) => {
+                            self.phase = TurnPhase::Finished(outcome);
+                            self.current_unit = None;
+                            self.emit(MatchEvent::MatchOver(outcome));
+                        }
+                        None => self.next_turn(),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    /// Остановились ли все объекты с физикой
+    pub fn is_settled(&self, sprites: &SpriteGroup) -> bool {
+        let limit = self.settings.settle_speed;
+        sprites.query::<(PhysicsComponent,)>()
+            .all(|(_, p)| p.velocity().magnitude() < limit)
+    }
+    /// Результат матча, если он уже понятен
+    pub fn outcome(&self) -> Option<MatchOutcome> {
+        let mut alive = self.teams.iter().enumerate().filter(|(_, t)| t.is_alive());
+        match (alive.next(), alive.next()) {
+            (None, _) => Some(MatchOutcome::Draw),
+            (Some((i, _)), None) => Some(MatchOutcome::Winner(i)),
+            _ => None,
+        }
+    }
+    fn start_settling(&mut self) {
+        self.phase = TurnPhase::Settling {elapsed: 0};
+        self.emit(MatchEvent::WaitingForPhysics);
+    }
+    /// Передаёт ход следующей команде, у которой есть живые бойцы
+    fn next_turn(&mut self) {
+        let count = self.teams.len();
+        for offset in 1..=count {
+            let team = (self.current_team + offset) % count;
+            if let Some(unit) = self.teams[team].next_unit() {
+                self.turn += 1;
+                self.current_team = team;
+                self.current_unit = Some(unit);
+                self.phase = TurnPhase::Aiming {time_left: self.settings.turn_time};
+                let unit = self.teams[team].units()[unit].sprite().to_string();
+                self.emit(MatchEvent::TurnStarted {turn: self.turn, team, unit});
+                return;
+            }
+        }
+        let outcome = self.outcome().unwrap_or(MatchOutcome::Draw);
+        self.phase = TurnPhase::Finished(outcome);
+        self.emit(MatchEvent::MatchOver(outcome));
+    }
+    /// Убивает бойцов, спрайты которых пропали из группы
+    fn check_missing<F: Fn(&str) -> bool>(&mut self, has_sprite: F) {
+        let mut missing = Vec::new();
+        for (i, team) in self.teams.iter().enumerate() {
+            for unit in team.units().iter().filter(|u| u.is_alive()) {
+                if !has_sprite(unit.sprite()) {
+                    missing.push((i, unit.sprite().to_string()));
+                }
+            }
+        }
+        for (team, sprite) in missing {
+            let health = self.teams[team].unit(&sprite).map(|u| u.health()).unwrap_or(0);
+            if self.teams[team].change_health(&sprite, -health) {
+                self.unit_died(team, &sprite);
+            }
+        }
+    }
+    fn unit_died(&mut self, team: usize, sprite: &str) {
+        self.emit(MatchEvent::UnitDied {team, unit: sprite.to_string()});
+        // Если умер тот, кто сейчас ходит, ход заканчивается
+        if team == self.current_team && self.current_unit() == Some(sprite) {
+            self.end_turn();
+        }
+    }
+    fn emit(&mut self, event: MatchEvent) {
+        // Подписчики, которые уже не слушают, просто забываются
+        self.subscribers.retain(|s| s.send(event.clone()).is_ok());
+    }
+}
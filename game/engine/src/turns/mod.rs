@@ -0,0 +1,7 @@
+pub mod events;
+pub mod manager;
+pub mod team;
+
+pub use events::{MatchEvent, MatchOutcome};
+pub use manager::{MatchManager, TurnPhase, TurnSettings};
+pub use team::{Team, Unit};
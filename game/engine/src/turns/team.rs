@@ -0,0 +1,89 @@
+/// Боец команды. Боец это спрайт с указанным именем и его здоровье
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    sprite: String,
+    health: i32,
+}
+
+impl Unit {
+    pub fn new(sprite: &str, health: i32) -> Self {
+        Unit {sprite: sprite.to_string(), health}
+    }
+    /// Имя спрайта бойца
+    pub fn sprite(&self) -> &str {
+        &self.sprite
+    }
+    pub fn health(&self) -> i32 {
+        self.health
+    }
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+}
+
+/// Команда бойцов. Бойцы ходят по очереди, мёртвые пропускаются
+#[derive(Debug, Clone, PartialEq)]
+pub struct Team {
+    name: String,
+    units: Vec<Unit>,
+    /// Индекс бойца, который пойдёт в следующий ход этой команды
+    next_unit: usize,
+}
+
+impl Team {
+    pub fn new(name: &str) -> Self {
+        Team {name: name.to_string(), units: Vec::new(), next_unit: 0}
+    }
+    /// Команда из сохранения
+    pub(crate) fn restore(name: &str, units: Vec<Unit>, next_unit: usize) -> Self {
+        let next_unit = if units.is_empty() {0} else {next_unit % units.len()};
+        Team {name: name.to_string(), units, next_unit}
+    }
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.units.push(unit);
+        self
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn units(&self) -> &Vec<Unit> {
+        &self.units
+    }
+    /// Ищет бойца по имени спрайта
+    pub fn unit(&self, sprite: &str) -> Option<&Unit> {
+        self.units.iter().find(|u| u.sprite == sprite)
+    }
+    pub(crate) fn unit_mut(&mut self, sprite: &str) -> Option<&mut Unit> {
+        self.units.iter_mut().find(|u| u.sprite == sprite)
+    }
+    /// Есть ли в команде живые бойцы
+    pub fn is_alive(&self) -> bool {
+        self.units.iter().any(|u| u.is_alive())
+    }
+    /// Индекс бойца, который пойдёт в следующий ход команды
+    pub(crate) fn next_unit_index(&self) -> usize {
+        self.next_unit
+    }
+    /// Выбирает следующего живого бойца и запоминает, кто пойдёт после него
+    pub(crate) fn next_unit(&mut self) -> Option<usize> {
+        let len = self.units.len();
+        for offset in 0..len {
+            let i = (self.next_unit + offset) % len;
+            if self.units[i].is_alive() {
+                self.next_unit = (i + 1) % len;
+                return Some(i);
+            }
+        }
+        None
+    }
+    /// Меняет здоровье бойца на delta. Возвращает true, если боец от этого умер
+    pub(crate) fn change_health(&mut self, sprite: &str, delta: i32) -> bool {
+        match self.unit_mut(sprite) {
+            Some(unit) if unit.is_alive() => {
+                unit.health += delta;
+                !unit.is_alive()
+            }
+            _ => false,
+        }
+    }
+}
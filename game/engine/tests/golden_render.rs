@@ -0,0 +1,216 @@
+//! Эталонные картинки SoftwareRenderer: слои, смешивание по прозрачности, кадры анимации,
+//! вспышка и частицы под спрайтом. Спрайты рисуются тем же sprite_quads и draw_layered,
+//! что и в игре, только без текстур в видеокарте.
+//! Эталоны лежат в tests/golden, перезаписать их можно с переменной окружения UPDATE_GOLDEN
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use cgmath::Point2;
+use glium::glutin::dpi::PhysicalSize;
+use image::{Rgba, RgbaImage};
+use engine::graphics::particles::{Curve, ParticleEmitter, ParticleSettings};
+use engine::graphics::render::{check_golden, Drawable, ImageCache, SoftwareRenderer};
+use engine::graphics::traits::{FrameList, Layered};
+use engine::graphics::{Flash, Material};
+use engine::misc_traits::named::Named;
+use engine::rect::Rectangular;
+use engine::Rect;
+
+const SIZE: u32 = 16;
+
+/// Спрайт без окна: всё, что нужно для рисования, и путь к картинке в ImageCache
+struct TestSprite {
+    name: Option<String>,
+    texture: PathBuf,
+    rect: Rect,
+    layer: u32,
+    frames: (u32, u32),
+    current_frame: u32,
+    hidden: bool,
+    material: Option<Material>,
+    flash: Option<Flash>,
+    emitters: Vec<ParticleEmitter>,
+}
+
+impl Rectangular for TestSprite {
+    fn get_rect(&self) -> &Rect {
+        &self.rect
+    }
+    fn get_rect_mut(&mut self) -> &mut Rect {
+        &mut self.rect
+    }
+}
+
+impl Layered for TestSprite {
+    fn get_layer(&self) -> u32 {
+        self.layer
+    }
+    fn get_layer_mut(&mut self) -> &mut u32 {
+        &mut self.layer
+    }
+}
+
+impl FrameList for TestSprite {
+    fn get_frames(&self) -> (u32, u32) {
+        self.frames
+    }
+    fn get_frames_mut(&mut self) -> (&mut u32, &mut u32) {
+        (&mut self.frames.0, &mut self.frames.1)
+    }
+}
+
+impl Named for TestSprite {
+    fn get_name(&self) -> &Option<String> {
+        &self.name
+    }
+    fn get_name_mut(&mut self) -> &mut Option<String> {
+        &mut self.name
+    }
+}
+
+impl Drawable for TestSprite {
+    fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+    fn current_frame(&self) -> u32 {
+        self.current_frame
+    }
+    fn texture_path(&self) -> Option<&Path> {
+        Some(&self.texture)
+    }
+    fn current_material(&self) -> Option<Cow<'_, Material>> {
+        match &self.flash {
+            Some(flash) => Some(Cow::Owned(flash.material())),
+            None => self.material.as_ref().map(Cow::Borrowed),
+        }
+    }
+    fn particle_emitters(&self) -> Vec<&ParticleEmitter> {
+        self.emitters.iter().collect()
+    }
+}
+
+fn golden(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(name)
+}
+
+fn sprite(texture: &str, left: f64, bottom: f64, size: f64, layer: u32) -> TestSprite {
+    TestSprite {
+        name: Some(texture.to_string()),
+        texture: PathBuf::from(texture),
+        rect: Rect::new(Point2::new(left, bottom), PhysicalSize::new(size, size)),
+        layer,
+        frames: (1, 1),
+        current_frame: 1,
+        hidden: false,
+        material: None,
+        flash: None,
+        emitters: Vec::new(),
+    }
+}
+
+fn cache() -> ImageCache {
+    let mut cache = ImageCache::new();
+    cache.insert(Path::new("red"), RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255])));
+    cache.insert(Path::new("blue"), RgbaImage::from_pixel(2, 2, Rgba([0, 0, 255, 255])));
+    cache.insert(Path::new("green50"), RgbaImage::from_pixel(2, 2, Rgba([0, 255, 0, 128])));
+    // Лист 2x2 кадра по 2x2 пикселя: красный, зелёный сверху, синий, жёлтый снизу
+    let sheet = RgbaImage::from_fn(4, 4, |x, y| match (x < 2, y < 2) {
+        (true, true) => Rgba([255, 0, 0, 255]),
+        (false, true) => Rgba([0, 255, 0, 255]),
+        (true, false) => Rgba([0, 0, 255, 255]),
+        (false, false) => Rgba([255, 255, 0, 255]),
+    });
+    cache.insert(Path::new("sheet"), sheet);
+    cache
+}
+
+fn render(sprites: &[TestSprite]) -> RgbaImage {
+    let mut renderer = SoftwareRenderer::new(SIZE, SIZE);
+    renderer.draw_sprites(sprites, &mut cache()).unwrap();
+    renderer.into_image()
+}
+
+#[test]
+fn upper_layer_is_drawn_over_lower_in_any_order() {
+    let forward = render(&[sprite("red", 2.0, 10.0, 8.0, 1), sprite("blue", 6.0, 14.0, 8.0, 2)]);
+    let backward = render(&[sprite("blue", 6.0, 14.0, 8.0, 2), sprite("red", 2.0, 10.0, 8.0, 1)]);
+    assert_eq!(forward, backward);
+    // Пересечение квадратов принадлежит верхнему слою
+    assert_eq!(*forward.get_pixel(7, 7), Rgba([0, 0, 255, 255]));
+    assert_eq!(*forward.get_pixel(3, 3), Rgba([255, 0, 0, 255]));
+    assert_eq!(*forward.get_pixel(0, 0), Rgba([0, 0, 0, 0]));
+    check_golden(&forward, &golden("layers.png"), 0).unwrap();
+    check_golden(&backward, &golden("layers.png"), 0).unwrap();
+}
+
+#[test]
+fn half_transparent_quad_blends_over_opaque() {
+    let image = render(&[sprite("red", 0.0, 16.0, 16.0, 1), sprite("green50", 4.0, 12.0, 8.0, 2)]);
+    let mixed = image.get_pixel(8, 8).0;
+    assert!(mixed[0].abs_diff(127) <= 1 && mixed[1].abs_diff(128) <= 1 && mixed[2] == 0,
+            "{mixed:?}");
+    assert_eq!(*image.get_pixel(1, 1), Rgba([255, 0, 0, 255]));
+    check_golden(&image, &golden("alpha.png"), 0).unwrap();
+}
+
+#[test]
+fn every_frame_of_sheet_is_drawn() {
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 0, 255]];
+    for (frame, color) in (1..=4).zip(colors) {
+        let mut sheet = sprite("sheet", 0.0, 16.0, 16.0, 1);
+        sheet.frames = (2, 2);
+        sheet.current_frame = frame;
+        let image = render(&[sheet]);
+        assert!(image.pixels().all(|p| p.0 == color), "кадр {frame}");
+        check_golden(&image, &golden(&format!("frame_{frame}.png")), 0).unwrap();
+    }
+}
+
+#[test]
+fn hidden_sprite_is_not_drawn() {
+    let mut red = sprite("red", 0.0, 16.0, 16.0, 1);
+    red.hidden = true;
+    assert!(render(&[red]).pixels().all(|p| p.0 == [0, 0, 0, 0]));
+}
+
+#[test]
+fn flash_fills_sprite_and_wins_over_material() {
+    let mut red = sprite("red", 0.0, 16.0, 16.0, 1);
+    red.material = Some(Material::flash([0.0, 0.0, 1.0, 1.0], 1.0));
+    assert!(render(&[red]).pixels().all(|p| p.0 == [0, 0, 255, 255]));
+    // Вспышка рисуется вместо материала и со временем сходит на нет
+    let mut red = sprite("red", 0.0, 16.0, 16.0, 1);
+    red.material = Some(Material::flash([0.0, 0.0, 1.0, 1.0], 1.0));
+    let mut flash = Flash::white(100);
+    flash.update(50);
+    red.flash = Some(flash);
+    let image = render(&[red]);
+    assert!(image.pixels().all(|p| p.0 == [255, 128, 128, 255]), "{:?}", image.get_pixel(0, 0));
+    check_golden(&image, &golden("flash.png"), 0).unwrap();
+    // Материалы, которые на процессоре не рисуются, не меняют спрайт
+    let mut red = sprite("red", 0.0, 16.0, 16.0, 1);
+    red.material = Some(Material::outline([1.0, 1.0, 1.0, 1.0], 2.0));
+    assert!(render(&[red]).pixels().all(|p| p.0 == [255, 0, 0, 255]));
+}
+
+#[test]
+fn particles_are_drawn_under_their_sprite() {
+    let settings = ParticleSettings {
+        speed: (0.0, 0.0),
+        lifetime: (1000, 1000),
+        size: Curve::constant(12.0),
+        color: Curve::constant([0.0, 1.0, 0.0, 1.0]),
+        alpha: Curve::constant(1.0),
+        ..Default::default()
+    };
+    let mut emitter = ParticleEmitter::new(settings).with_seed(1).with_emitting(false);
+    emitter.set_position(Point2::new(8.0, 8.0));
+    emitter.burst(1);
+    let mut red = sprite("red", 6.0, 10.0, 4.0, 3);
+    red.emitters.push(emitter);
+    // Синий спрайт слоем ниже закрыт частицей, хотя нарисован позже
+    let image = render(&[red, sprite("blue", 0.0, 16.0, 16.0, 1)]);
+    assert_eq!(*image.get_pixel(8, 8), Rgba([255, 0, 0, 255]));
+    assert_eq!(*image.get_pixel(3, 3), Rgba([0, 255, 0, 255]));
+    assert_eq!(*image.get_pixel(0, 0), Rgba([0, 0, 255, 255]));
+    check_golden(&image, &golden("particles.png"), 0).unwrap();
+}
@@ -0,0 +1,245 @@
+//! Сетевой матч на LockstepSession через настоящий TCP на 127.0.0.1:
+//! начало матча, обмен действиями, рассинхронизация, уход игрока до начала
+//! и возвращение после обрыва связи
+use std::io;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use engine::net::{LockstepSession, NetEvent, Packet, PlayerId, PROTOCOL_VERSION};
+use engine::replay::InputAction;
+
+const SEED: u64 = 42;
+const STEP: u32 = 16;
+/// Дольше этого событий не ждём. Переподключение пробуется раз в секунду
+const WAIT: Duration = Duration::from_secs(10);
+
+/// Сессия игрока и все события, которые она вернула
+struct Player {
+    session: LockstepSession,
+    events: Vec<NetEvent>,
+}
+
+impl Player {
+    fn poll(&mut self) {
+        self.events.extend(self.session.poll());
+    }
+    fn has(&self, event: &NetEvent) -> bool {
+        self.events.contains(event)
+    }
+}
+
+/// Создатель матча на свободном порту и присоединившийся к нему игрок
+fn players(through: impl FnOnce(SocketAddr) -> SocketAddr) -> (Player, Player) {
+    let host = LockstepSession::host("127.0.0.1:0", "Лисы", 2, SEED, STEP).unwrap();
+    let addr = through(host.local_addr().unwrap());
+    let client = LockstepSession::join(addr, "Мишени").unwrap();
+    (Player { session: host, events: Vec::new() }, Player { session: client, events: Vec::new() })
+}
+
+/// Зовёт poll у обоих, пока у первого не появится нужное событие
+fn wait_for(player: &mut Player, other: &mut Player, found: impl Fn(&NetEvent) -> bool) {
+    let start = Instant::now();
+    while !player.events.iter().any(&found) {
+        assert!(start.elapsed() < WAIT, "не дождались события, пришли только {:?}", player.events);
+        player.poll();
+        other.poll();
+        thread::sleep(Duration::from_millis(2));
+    }
+}
+
+/// Действия всех игроков на шаге, как их вернул next_tick
+type TickActions = Vec<(PlayerId, InputAction)>;
+
+/// Делает шаг у обоих игроков. Пока действий другого нет, зовёт poll.
+/// После шага отправляет контрольные суммы
+fn step(host: &mut Player, client: &mut Player,
+        host_actions: Vec<InputAction>, client_actions: Vec<InputAction>,
+        checksums: (u64, u64)) -> (TickActions, TickActions) {
+    let start = Instant::now();
+    let (mut host_actions, mut client_actions) = (host_actions, client_actions);
+    let (mut host_tick, mut client_tick) = (None, None);
+    while host_tick.is_none() || client_tick.is_none() {
+        assert!(start.elapsed() < WAIT, "шаг так и не сделан");
+        if host_tick.is_none() {
+            host_tick = host.session.next_tick(&mut host_actions);
+        }
+        if client_tick.is_none() {
+            client_tick = client.session.next_tick(&mut client_actions);
+        }
+        host.poll();
+        client.poll();
+        thread::sleep(Duration::from_millis(1));
+    }
+    host.session.end_tick(checksums.0);
+    client.session.end_tick(checksums.1);
+    (host_tick.unwrap(), client_tick.unwrap())
+}
+
+fn started(event: &NetEvent) -> bool {
+    matches!(event, NetEvent::Started {..})
+}
+
+#[test]
+fn sessions_start_exchange_inputs_and_detect_desync() {
+    let (mut host, mut client) = players(|addr| addr);
+    wait_for(&mut client, &mut host, started);
+    wait_for(&mut host, &mut client, started);
+    assert!(client.has(&NetEvent::Joined { player: 1 }));
+    assert!(client.has(&NetEvent::Started { players: vec![0, 1] }));
+    assert!(host.has(&NetEvent::PlayerJoined { player: 1, name: "Мишени".to_string() }));
+    assert!(host.has(&NetEvent::Started { players: vec![0, 1] }));
+    let session = &client.session;
+    assert_eq!((session.seed(), session.step(), session.local_player()), (SEED, STEP, Some(1)));
+
+    // Действия начинают действовать через несколько шагов и приходят обоим одинаковыми
+    let fire = InputAction::Fire { angle: -0.5, speed: 300.0 };
+    let mut ticks = vec![step(&mut host, &mut client, vec![fire.clone()],
+                              vec![InputAction::EndTurn], (0, 0))];
+    for tick in 1..8 {
+        let client_checksum = if tick == 5 { 999 } else { tick };
+        ticks.push(step(&mut host, &mut client, Vec::new(), Vec::new(), (tick, client_checksum)));
+    }
+    for (host_tick, client_tick) in &ticks {
+        assert_eq!(host_tick, client_tick);
+    }
+    let with_actions: Vec<_> = ticks.iter().filter(|(actions, _)| !actions.is_empty()).collect();
+    assert_eq!(with_actions.len(), 1);
+    assert_eq!(with_actions[0].0, vec![(0, fire), (1, InputAction::EndTurn)]);
+
+    let desync = |event: &NetEvent| matches!(event, NetEvent::Desync {..});
+    wait_for(&mut host, &mut client, desync);
+    wait_for(&mut client, &mut host, desync);
+    assert!(host.has(&NetEvent::Desync { tick: 5, player: 1, ours: 5, theirs: 999 }));
+    assert!(client.has(&NetEvent::Desync { tick: 5, player: 0, ours: 999, theirs: 5 }));
+    assert_eq!(host.events.iter().filter(|e| desync(e)).count(), 1);
+}
+
+#[test]
+fn player_lost_before_start_frees_the_slot() {
+    let host = LockstepSession::host("127.0.0.1:0", "Лисы", 3, SEED, STEP).unwrap();
+    let addr = host.local_addr().unwrap();
+    let mut host = Player { session: host, events: Vec::new() };
+    let join = |name| Player { session: LockstepSession::join(addr, name).unwrap(),
+                               events: Vec::new() };
+    let mut first = join("Мишени");
+    wait_for(&mut first, &mut host, |e| *e == NetEvent::Joined { player: 1 });
+    // Ушедший до начала игрок не считается: матч ждёт, а его место получает следующий
+    drop(first);
+    let mut second = join("Волки");
+    wait_for(&mut host, &mut second, |e| *e == NetEvent::PlayerLeft { player: 1 });
+    assert!(!host.session.is_started());
+    wait_for(&mut second, &mut host, |e| *e == NetEvent::Joined { player: 1 });
+    assert!(!host.session.is_started());
+    let mut third = join("Ежи");
+    wait_for(&mut third, &mut host, started);
+    wait_for(&mut second, &mut host, started);
+    assert!(third.has(&NetEvent::Joined { player: 2 }));
+    assert!(host.has(&NetEvent::Started { players: vec![0, 1, 2] }));
+    assert!(second.has(&NetEvent::Started { players: vec![0, 1, 2] }));
+    assert!(host.has(&NetEvent::PlayerJoined { player: 1, name: "Волки".to_string() }));
+}
+
+#[test]
+fn client_resumes_after_connection_drops() {
+    let mut proxy = None;
+    let (mut host, mut client) = players(|addr| {
+        let p = Proxy::new(addr);
+        let addr = p.addr;
+        proxy = Some(p);
+        addr
+    });
+    let proxy = proxy.unwrap();
+    wait_for(&mut host, &mut client, started);
+    wait_for(&mut client, &mut host, started);
+    for tick in 0..3 {
+        step(&mut host, &mut client, Vec::new(), Vec::new(), (tick, tick));
+    }
+
+    proxy.cut();
+    wait_for(&mut host, &mut client, |e| *e == NetEvent::Reconnected { player: 1 });
+    assert!(host.has(&NetEvent::PlayerLost { player: 1 }));
+    assert!(client.has(&NetEvent::Disconnected));
+    assert!(!host.events.iter().any(|e| matches!(e, NetEvent::PlayerLeft {..})));
+    assert!(!client.has(&NetEvent::HostLost));
+
+    // После возвращения матч идёт дальше, и действия снова доходят
+    let jump = InputAction::Jump;
+    let mut got = Vec::new();
+    for tick in 3..10 {
+        let actions = if tick == 3 { vec![jump.clone()] } else { Vec::new() };
+        let (host_tick, client_tick) = step(&mut host, &mut client, Vec::new(), actions,
+                                            (tick, tick));
+        assert_eq!(host_tick, client_tick);
+        got.extend(host_tick);
+    }
+    assert_eq!(got, vec![(1, jump)]);
+}
+
+#[test]
+fn every_packet_survives_text_round_trip() {
+    let packets = [
+        Packet::Hello { version: PROTOCOL_VERSION, name: "Рыжий лис".to_string() },
+        Packet::Resume { version: PROTOCOL_VERSION, player: 2, tick: 1234 },
+        Packet::Welcome { player: 1, seed: u64::MAX, step: 16, delay: 4 },
+        Packet::Joined { player: 3, name: "Мишени и ко".to_string() },
+        Packet::Start { players: vec![0, 1, 2] },
+        Packet::Start { players: Vec::new() },
+        Packet::Input { player: 1, tick: 77, actions: Vec::new() },
+        Packet::Input {
+            player: 0,
+            tick: 5,
+            actions: vec![
+                InputAction::Fire { angle: -0.25, speed: 410.5 },
+                InputAction::Walk { direction: -1.0 },
+                InputAction::Jump,
+                InputAction::EndTurn,
+                InputAction::Custom { name: "taunt".to_string(), value: 3.0 },
+            ],
+        },
+        Packet::Checksum { player: 1, tick: 9, value: 0x9f1c2a3b4d5e6f70 },
+        Packet::Leave { player: 2, tick: 100 },
+        Packet::Reject { reason: "матч уже идёт".to_string() },
+    ];
+    for packet in packets {
+        assert_eq!(Packet::parse(&packet.to_string()), Some(packet));
+    }
+}
+
+/// Пересылает соединения на target и умеет их разом оборвать, как при потере сети
+struct Proxy {
+    addr: SocketAddr,
+    streams: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl Proxy {
+    fn new(target: SocketAddr) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let streams = Arc::new(Mutex::new(Vec::new()));
+        let open = Arc::clone(&streams);
+        thread::spawn(move || {
+            for client in listener.incoming().flatten() {
+                let Ok(server) = TcpStream::connect(target) else { continue };
+                open.lock().unwrap().extend([client.try_clone().unwrap(),
+                                             server.try_clone().unwrap()]);
+                pipe(client.try_clone().unwrap(), server.try_clone().unwrap());
+                pipe(server, client);
+            }
+        });
+        Proxy { addr, streams }
+    }
+    /// Обрывает все соединения, которые сейчас идут через прокси. Новые он принимает дальше
+    fn cut(&self) {
+        for stream in self.streams.lock().unwrap().drain(..) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn pipe(mut from: TcpStream, mut to: TcpStream) {
+    thread::spawn(move || {
+        let _ = io::copy(&mut from, &mut to);
+        let _ = to.shutdown(Shutdown::Both);
+    });
+}
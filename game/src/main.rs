@@ -2,28 +2,54 @@ extern crate cgmath;
 extern crate glium;
 extern crate image;
 
-use std::path::Path;
+use std::env;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use cgmath::Vector2;
+use cgmath::{Point2, Vector2};
 
 use glium::glutin;
 use glium::glutin::dpi::PhysicalSize;
+use glium::glutin::event::VirtualKeyCode;
 use glium::glutin::platform::run_return::EventLoopExtRunReturn;
-use glium::Surface;
 use measurements::Mass;
+use rand::Rng;
 use engine::component::traits::Composite;
 
+use engine::graphics::{Flash, ParallaxBackground, ScalePolicy};
 use engine::graphics::sprite::SpriteManager;
+use engine::graphics::postprocess::{PostEffect, PostProcessor};
+use engine::graphics::lighting::{LightRenderer, Lighting};
+use engine::graphics::debug::DebugOverlay;
+use engine::graphics::capture::{CaptureFormat, CaptureSettings, ScreenCapture};
 use engine::graphics::sprite::SpriteGroup;
 use engine::group::SomeGroup;
 use engine::programs::ProgramManager;
 use engine::rect::Rectangular;
-use engine::time::Clock;
+use engine::time::{Clock, FixedStep};
+use engine::input::Input;
+use engine::sounds::{Listener, SoundManager};
 use engine::misc_traits::named::Named;
 use engine::physics::components::PhysicsComponent;
+use engine::turns::{MatchManager, Team, TurnSettings, Unit};
+use engine::messaging::bus::MessageBus;
+use engine::component::registry::ComponentRegistry;
+use engine::save::{user_data_dir, GameState, SaveStorage};
+use engine::ai::Shot;
+use engine::replay::{Checksum, InputAction, Replay, ReplayPlayer, ReplayRecorder};
+use engine::turns::MatchEvent;
+use engine::net::{LockstepSession, NetEvent};
 
 const SCREEN_WIDTH: u32 = 1224;
 const SCREEN_HEIGHT: u32 = 768;
+/// Шаг симуляции в миллисекундах
+const SIMULATION_STEP: u32 = 16;
+/// Сколько миллисекунд боец мигает белым после попадания
+const HIT_FLASH_TIME: u32 = 200;
+/// Сколько миллисекунд экран проявляется или затемняется при смене сцены
+const SCENE_FADE_TIME: u32 = 1000;
+/// Сколько секунд длится запись по F10
+const RECORDING_SECONDS: f32 = 10.0;
 
 fn main() {
     let mut event_loop = glutin::event_loop::EventLoop::new();
@@ -32,9 +58,46 @@ fn main() {
         .with_title(format!("Cool foxes"));
     let cb = glutin::ContextBuilder::new();
     let display = glium::Display::new(wb, cb, &event_loop).unwrap();
-    let rect_program = ProgramManager::rect_drawing_program(&display);
-    let sprite_manager = SpriteManager::from(&display, &rect_program,
-                                             SCREEN_WIDTH, SCREEN_HEIGHT);
+    let mut programs = match ProgramManager::new(&display) {
+        Ok(p) => p,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+    // Свои шейдеры для материалов лежат в assets/shaders, имя программы это имя файла
+    for error in programs.load_dir(&display, Path::new("./assets/shaders")) {
+        println!("{error}");
+    }
+    // Эффекты постобработки сцены лежат в scenes/main.post
+    let effects = match PostEffect::load_chain(Path::new("./scenes/main.post")) {
+        Ok(effects) => effects,
+        Err(e) => {
+            println!("Сцена без постобработки: {e}");
+            Vec::new()
+        }
+    };
+    let mut post = match PostProcessor::new(&display, &mut programs, effects) {
+        Ok(p) => p,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+    let mut lights = match LightRenderer::new(&display, &mut programs) {
+        Ok(l) => l,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+    // Игра проявляется из чёрного экрана
+    post.fade_mut().cover([0.0, 0.0, 0.0, 1.0]);
+    post.fade_mut().fade_in(SCENE_FADE_TIME);
+    let mut sprite_manager = SpriteManager::from(&display, programs.default_program(),
+                                             SCREEN_WIDTH, SCREEN_HEIGHT)
+        .with_programs(&programs)
+        .with_scale_policy(ScalePolicy::Letterbox);
     let mut sprites = SpriteGroup::new();
     let gravity = 9.8;
     sprites.push(sprite_manager.build_sprite(Path::new("./assets/images/fox.png"), 0.10)
@@ -49,8 +112,23 @@ fn main() {
         .with_component(
             PhysicsComponent::new(Mass::from_kilograms(100.0), gravity,
             Some(Vector2::new(-100.0, 10.0)))));
-    sprites.push(sprite_manager.build_bg(Path::new("./assets/images/bg.png"))
-        .with_position(0, 768));
+    let background = ParallaxBackground::new()
+        .with_layer(sprite_manager.build_parallax_layer(Path::new("./assets/images/bg.png"), 0.0)
+            .with_repeat(true));
+    // Карта уровня лежит в assets/maps/level.txt, без неё уровень пустой
+    let tilemap = match sprite_manager.build_tilemap(Path::new("./assets/maps/level.txt")) {
+        Ok(tilemap) => Some(tilemap),
+        Err(e) => {
+            println!("Уровень без карты: {e}");
+            None
+        }
+    };
+    let mut game_match = MatchManager::new(vec![
+        Team::new("Лисы").with_unit(Unit::new("fox", 100)),
+        Team::new("Мишени").with_unit(Unit::new("target", 100)),
+    ], TurnSettings::default());
+    let mut match_events = game_match.subscribe();
+    game_match.start();
     println!("{:#?}", sprites);
     let mut dt;
     let fps = 60;
@@ -58,28 +136,237 @@ fn main() {
     let mut frame_handling_start: Instant;
     let mut running = true;
     let mut clock = Clock::new();
+    let mut input = Input::new();
+    let mut sounds = SoundManager::with_default_backend();
+    let mut bus = MessageBus::new();
+    let saves = SaveStorage::new("coolfoxes");
+    let registry = ComponentRegistry::new();
+    let arg = |key: &str| env::args().skip_while(|a| a != key).nth(1);
+    // С ключом --replay файл игра не слушает игрока, а проигрывает записанный матч
+    let mut player = arg("--replay")
+        .and_then(|path| match Replay::load(Path::new(&path)) {
+            Ok(replay) => Some(ReplayPlayer::new(replay)),
+            Err(e) => {
+                println!("Не удалось загрузить повтор {path}: {e}");
+                None
+            }
+        });
+    let mut recorder = ReplayRecorder::new(rand::random(), SIMULATION_STEP);
+    let mut rng = match &player {
+        Some(p) => p.rng(),
+        None => recorder.rng(),
+    };
+    let mut fixed_step = FixedStep::new(player.as_ref().map(|p| p.step()).unwrap_or(SIMULATION_STEP));
+    let mut pending_actions = Vec::new();
+    // С ключом --host адрес игра ждёт второго игрока, а с ключом --join адрес
+    // присоединяется к нему. Каждый игрок управляет командой со своим номером
+    let mut session = match (arg("--host"), arg("--join")) {
+        (Some(addr), _) => Some(LockstepSession::host(addr.as_str(), "Лисы", 2,
+                                                      recorder.replay().seed, SIMULATION_STEP)),
+        (_, Some(addr)) => Some(LockstepSession::join(addr.as_str(), "Мишени")),
+        _ => None,
+    }.and_then(|s| s.map_err(|e| println!("Не удалось начать игру по сети: {e}")).ok());
+    // Снимки экрана (F12) и записи (F10) сохраняются в папку из ключа --captures.
+    // С ключом --capture-png запись это последовательность PNG, а не GIF
+    let capture_dir = arg("--captures").map(PathBuf::from).unwrap_or_else(|| {
+        user_data_dir().unwrap_or_default().join("coolfoxes").join("captures")
+    });
+    let capture_format = if env::args().any(|a| a == "--capture-png") {
+        CaptureFormat::PngSequence
+    } else {
+        CaptureFormat::Gif
+    };
+    let mut capture = ScreenCapture::new(CaptureSettings::new(&capture_dir)
+        .with_format(capture_format));
+    // С ключом --lighting файл сцена освещается как ночной уровень, например scenes/night.light
+    let lighting = arg("--lighting")
+        .and_then(|path| Lighting::load(Path::new(&path))
+            .map_err(|e| println!("Сцена без освещения: {e}"))
+            .ok());
+    // F3 показывает квадраты спрайтов, скорости и столкновения поверх игры
+    let mut overlay = DebugOverlay::new();
     let start_time = Instant::now();
     let mut wait = Duration::from_secs(1);
     while running {
         let mut frame = display.draw();
         frame_handling_start = Instant::now();
         dt = clock.get_time().as_millis() as u32;
+        sounds.update(dt);
+        sprite_manager.advance_time(dt);
         //println!("{}", dt);
         // Start with white background.
-        frame.clear_color(1.0, 1.0, 1.0, 1.0);
-        sprites.call(|mut sprite| {
-            sprite = sprite.updated(dt);
-            sprite_manager.draw(&sprite, &mut frame);
-            if start_time.elapsed() >= wait {
+        if let Some(session) = &mut session {
+            for event in session.poll() {
+                if let NetEvent::Started {..} = event {
+                    // Зерно и шаг у всех игроков должны быть как у создателя матча
+                    recorder = ReplayRecorder::new(session.seed(), session.step());
+                    rng = recorder.rng();
+                    fixed_step = FixedStep::new(session.step());
+                }
+                println!("{:?}", event);
+            }
+        }
+        let my_turn = session.as_ref()
+            .map(|s| s.local_player() == Some(game_match.current_team()))
+            .unwrap_or(true);
+        if player.is_none() && my_turn {
+            if input.is_key_pressed(VirtualKeyCode::Space) {
+                pending_actions.push(aim(&game_match, &sprites));
+            }
+            if input.is_key_pressed(VirtualKeyCode::Return) {
+                pending_actions.push(InputAction::EndTurn);
+            }
+        }
+        // Мир двигается только целыми шагами, поэтому матч можно повторить в точности
+        let step = fixed_step.step();
+        for _ in 0..fixed_step.advance(dt) {
+            let actions = match (&mut player, &mut session) {
+                (Some(p), _) => p.actions(),
+                (None, Some(s)) => match s.next_tick(&mut pending_actions) {
+                    Some(actions) => actions.into_iter().map(|(_, action)| action).collect(),
+                    // Действия другого игрока ещё не пришли, ждём
+                    None => break,
+                },
+                (None, None) => std::mem::take(&mut pending_actions),
+            };
+            for action in actions {
+                if player.is_none() {
+                    recorder.record(action.clone());
+                }
+                apply_action(&action, &mut game_match, &mut sprites);
+            }
+            sprites.call(|sprite| sprite.updated(step));
+            bus.deliver(&mut sprites);
+            game_match.update(step, &sprites);
+            for event in match_events.try_iter() {
+                match &event {
+                    MatchEvent::TurnStarted {..} => {
+                        // Каждый ход дует новый ветер
+                        let wind = rng.gen_range(-20.0..20.0);
+                        for (_, physics) in sprites.query_mut::<PhysicsComponent>() {
+                            physics.set_wind(wind);
+                        }
+                    }
+                    MatchEvent::MatchOver(_) =>
+                        post.fade_mut().fade_out([0.0, 0.0, 0.0, 1.0], SCENE_FADE_TIME * 2),
+                    MatchEvent::UnitDamaged { unit, .. } => {
+                        if let Some(sprite) = sprites.find_get_mut(unit) {
+                            sprite.flash(Flash::white(HIT_FLASH_TIME));
+                        }
+                    }
+                    _ => {}
+                }
+                println!("{:?}", event);
+            }
+            let checksum = Checksum::of_world(&sprites, Some(&game_match));
+            match &mut player {
+                Some(p) => if let Err(e) = p.end_tick(checksum) {
+                    println!("{e}");
+                },
+                None => recorder.end_tick(checksum),
+            }
+            if let Some(session) = &mut session {
+                session.end_tick(checksum);
+            }
+        }
+        post.update(dt);
+        if let Err(e) = post.resize(&display, display.get_framebuffer_dimensions()) {
+            println!("{e}");
+        }
+        if let Err(e) = lights.resize(&display, display.get_framebuffer_dimensions()) {
+            println!("{e}");
+        }
+        let mut scene = post.target();
+        sprite_manager.clear(&mut scene, 1.0, 1.0, 1.0, 1.0);
+        sprite_manager.draw_background(&background, &mut scene);
+        if let Some(tilemap) = &tilemap {
+            sprite_manager.draw_tilemap(tilemap, &mut scene);
+        }
+        sprite_manager.draw_sprites(&sprites, &mut scene);
+        if let Some(lighting) = &lighting {
+            lights.render(&programs, &sprite_manager, lighting, &sprites, &mut scene);
+        }
+        post.apply(&programs, &mut frame);
+        if input.is_key_pressed(VirtualKeyCode::F3) {
+            overlay.toggle();
+        }
+        if overlay.is_enabled() {
+            sprite_manager.draw_shapes(overlay.build(&sprites, tilemap.as_ref()), &mut frame);
+        }
+        if start_time.elapsed() >= wait {
+            for sprite in sprites.get_elements().iter().flatten() {
                 println!("{:?}", sprite)
             }
-            sprite
         }
-        );
+        let camera = sprite_manager.camera().position();
+        let listener = Point2::new(camera.x + SCREEN_WIDTH as f64 / 2.0,
+                                   camera.y + SCREEN_HEIGHT as f64 / 2.0);
+        sounds.update_emitters(&mut sprites, Listener::Point(listener));
         if start_time.elapsed() >= wait {
             wait += Duration::from_secs(1);
         }
+        if input.is_key_pressed(VirtualKeyCode::F12) {
+            match capture.screenshot(&sprite_manager, &frame) {
+                Ok(path) => println!("Снимок экрана сохранён в {}", path.display()),
+                Err(e) => println!("Не удалось сохранить снимок экрана: {e}"),
+            }
+        }
+        let recorded = if !input.is_key_pressed(VirtualKeyCode::F10) {
+            capture.update(&sprite_manager, &frame, dt)
+        } else if capture.is_recording() {
+            capture.stop_recording().map(|()| {
+                println!("Запись остановлена и дописывается");
+                None
+            })
+        } else {
+            capture.start_recording(RECORDING_SECONDS).map(|path| {
+                println!("Идёт запись в {}", path.display());
+                None
+            })
+        };
+        match recorded {
+            Ok(Some(path)) => println!("Запись сохранена в {}", path.display()),
+            Ok(None) => {}
+            Err(e) => println!("Не удалось записать игру: {e}"),
+        }
         frame.finish().unwrap();
+        if input.is_key_pressed(VirtualKeyCode::F11) {
+            sprite_manager.toggle_fullscreen();
+        }
+        if input.is_key_pressed(VirtualKeyCode::F8) && player.is_none() {
+            let dir = user_data_dir().unwrap_or_default().join("coolfoxes").join("replays");
+            let path = dir.join(format!("replay-{}.txt", recorder.replay().seed));
+            match recorder.replay().save(&path) {
+                Ok(()) => println!("Повтор сохранён в {}", path.display()),
+                Err(e) => println!("Не удалось сохранить повтор: {e}"),
+            }
+        }
+        // Загрузка сохранения испортила бы повтор и игру по сети, поэтому там её нет
+        if let (Some(saves), None, None) = (&saves, &player, &session) {
+            if input.is_key_pressed(VirtualKeyCode::F5) {
+                let state = GameState::capture(&sprites, Some(&game_match));
+                match saves.save("quicksave", &state) {
+                    Ok(()) => println!("Игра сохранена в {}", saves.path("quicksave").display()),
+                    Err(e) => println!("Не удалось сохранить игру: {e}"),
+                }
+            }
+            if input.is_key_pressed(VirtualKeyCode::F9) {
+                let loaded = saves.load("quicksave").and_then(|state| {
+                    Ok((sprite_manager.load_sprites(&state, &registry)?, state.game_match))
+                });
+                match loaded {
+                    Ok((loaded_sprites, loaded_match)) => {
+                        sprites = loaded_sprites;
+                        if let Some(state) = loaded_match {
+                            game_match = MatchManager::restore(state, TurnSettings::default());
+                            match_events = game_match.subscribe();
+                        }
+                    }
+                    Err(e) => println!("Не удалось загрузить игру: {e}"),
+                }
+            }
+        }
+        input.end_frame();
 
         // Handles keyboard input.
         event_handling_start = Instant::now();
@@ -87,13 +374,24 @@ fn main() {
         // Большая и страшная обработка событий и времени между кадрами
         event_loop.run_return(|event, _, control_flow|{
         match event {
-            glutin::event::Event::WindowEvent { event, .. } => match event {
-                glutin::event::WindowEvent::CloseRequested => {
-                    *control_flow = glutin::event_loop::ControlFlow::Exit;
-                        running = false;
-                    return;
-                },
-                _ => return,
+            glutin::event::Event::WindowEvent { event, .. } => {
+                input.handle_event(&event, sprite_manager.viewport());
+                match event {
+                    glutin::event::WindowEvent::CloseRequested => {
+                        *control_flow = glutin::event_loop::ControlFlow::Exit;
+                            running = false;
+                        return;
+                    },
+                    glutin::event::WindowEvent::Resized(size) => {
+                        sprite_manager.resize(size);
+                        return;
+                    },
+                    glutin::event::WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                        sprite_manager.resize(*new_inner_size);
+                        return;
+                    },
+                    _ => return,
+                }
             },
             glutin::event::Event::NewEvents(cause) => match cause {
                 glutin::event::StartCause::ResumeTimeReached { .. } => (),
@@ -114,4 +412,43 @@ fn main() {
             *control_flow = glutin::event_loop::ControlFlow::WaitUntil(next_frame_time);
     });
     }
-}
\ No newline at end of file
+    if let Some(session) = &mut session {
+        session.leave();
+    }
+    match capture.finish() {
+        Ok(paths) => {
+            for path in paths {
+                println!("Запись сохранена в {}", path.display());
+            }
+        }
+        Err(e) => println!("Не удалось записать игру: {e}"),
+    }
+}
+
+/// Выстрел текущего бойца под 45 градусов в сторону середины экрана
+fn aim(game_match: &MatchManager, sprites: &SpriteGroup) -> InputAction {
+    let x = game_match.current_unit()
+        .and_then(|name| sprites.find_get(name))
+        .map(|sprite| sprite.x())
+        .unwrap_or(0.0);
+    let angle = if x < SCREEN_WIDTH as f64 / 2.0 {-PI / 4.0} else {-3.0 * PI / 4.0};
+    InputAction::Fire {angle, speed: 300.0}
+}
+
+/// Применяет действие игрока к миру
+fn apply_action(action: &InputAction, game_match: &mut MatchManager, sprites: &mut SpriteGroup) {
+    match action {
+        InputAction::Fire { angle, speed } => {
+            let unit = game_match.current_unit().map(String::from);
+            if let (Some(unit), true) = (unit, game_match.fire()) {
+                let physics = sprites.find_get_mut(&unit)
+                    .and_then(|sprite| sprite.get_component_mut::<PhysicsComponent>());
+                if let Some(physics) = physics {
+                    physics.set_velocity(Shot {angle: *angle, speed: *speed}.velocity());
+                }
+            }
+        }
+        InputAction::EndTurn => game_match.end_turn(),
+        _ => (),
+    }
+}